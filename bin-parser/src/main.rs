@@ -1,9 +1,197 @@
 #![allow(unused)]
 
-use datatbls::parser;
+use anyhow::{Result, bail};
+use datatbls::{parser, datatbls_mgr::DataTblsManager, query::Query, vfs::Vfs, mpq::{MpqArchive, MpqWriter, FileOptions}, layout};
+
+const SOURCE_USAGE: &str = "[--data <dir> | --game <dir> [--mod <mpq>]... [--direct]]";
+const QUERY_USAGE: &str = "usage: bin-parser query [source] [--columns <expr,expr,...>] <table> <filter>";
+const WHICH_USAGE: &str = "usage: bin-parser which [source] <file>...";
+const PACK_USAGE: &str = "usage: bin-parser pack [--base <mpq>] [--mount <dir>] [--store] [--encrypt] [--fix-key] <out.mpq> <dir>...";
+const INFER_USAGE: &str = "usage: bin-parser infer [--name <NAME>] <file.bin> <file.txt>";
+
+/// where the game files come from: an extracted data dir, or the game's mpqs
+/// with mod mpqs and the loose files of `-direct` on top
+struct Source {
+    data    : String,
+    game    : Option<String>,
+    mods    : Vec<String>,
+    direct  : bool,
+}
+
+impl Source {
+    /// takes the source options out of `args`, the rest is returned in order
+    fn parse(args: &[String]) -> (Source, Vec<String>) {
+        let mut source = Source{
+            data    : parser::default_data_path(),
+            game    : None,
+            mods    : Vec::new(),
+            direct  : false,
+        };
+        let mut rest = Vec::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--data" => source.data = args.next().cloned().unwrap_or_default(),
+                "--game" => source.game = args.next().cloned(),
+                "--mod" => source.mods.extend(args.next().cloned()),
+                "--direct" => source.direct = true,
+                _ => rest.push(arg.clone()),
+            }
+        }
+
+        (source, rest)
+    }
+
+    fn vfs(&self) -> Result<Vfs> {
+        match &self.game {
+            Some(game) => Vfs::game(game, &self.mods, self.direct),
+            None => {
+                let mut vfs = Vfs::new();
+                vfs.add_dir(&self.data, "data")?;
+                Ok(vfs)
+            }
+        }
+    }
+}
+
+/// `query [source] [--columns <expr,expr,...>] <table> <filter>`, prints the
+/// matching rows tab separated
+fn query(args: &[String]) -> Result<()> {
+    let (source, args) = Source::parse(args);
+    let mut columns = String::new();
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--columns" => columns = args.next().cloned().unwrap_or_default(),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let (table, filter) = match positional.as_slice() {
+        [table, filter] => (*table, *filter),
+        _ => bail!("{QUERY_USAGE}\nsource: {SOURCE_USAGE}"),
+    };
+
+    let split = Query::split_columns(&columns);
+
+    let mut tbls = DataTblsManager::new();
+    tbls.load_vfs(&source.vfs()?)?;
+
+    println!("{}", tbls.query(table, filter, &split)?.to_txt());
+
+    Ok(())
+}
+
+/// `which [source] <file>...`, prints the layer each file is read from
+fn which(args: &[String]) -> Result<()> {
+    let (source, files) = Source::parse(args);
+
+    if files.is_empty() {
+        bail!("{WHICH_USAGE}\nsource: {SOURCE_USAGE}");
+    }
+
+    let vfs = source.vfs()?;
+
+    for file in files.iter() {
+        match vfs.which(file) {
+            Some(layer) => println!("{file}\t{layer}"),
+            None => println!("{file}\tnot found"),
+        }
+    }
+
+    Ok(())
+}
+
+/// `pack [--base <mpq>] [--mount <dir>] [--store] [--encrypt] [--fix-key] <out.mpq> <dir>...`,
+/// writes the files under each dir, named as if the dir were `--mount` (`data`
+/// by default), over the files of `--base`
+fn pack(args: &[String]) -> Result<()> {
+    let mut base = None;
+    let mut mount = "data".to_string();
+    let mut options = FileOptions::default();
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--base" => base = args.next().cloned(),
+            "--mount" => mount = args.next().cloned().unwrap_or_default(),
+            "--store" => options.compress = false,
+            "--encrypt" => options.encrypt = true,
+            "--fix-key" => options.fix_key = true,
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let (out, dirs) = match positional.split_first() {
+        Some((out, dirs)) if !dirs.is_empty() => (*out, dirs),
+        _ => bail!("{PACK_USAGE}"),
+    };
+
+    let mut writer = match base {
+        Some(base) => MpqWriter::from_archive(&MpqArchive::open(base)?)?,
+        None => MpqWriter::new(),
+    };
+
+    for dir in dirs {
+        writer.add_dir(dir, &mount, options)?;
+    }
+
+    writer.write(out)?;
+    println!("{out}: {} files", writer.len());
+
+    Ok(())
+}
+
+/// `infer [--name <NAME>] <file.bin> <file.txt>`, prints a draft schema of the
+/// bin's records followed by what it leaves open as comments
+fn infer(args: &[String]) -> Result<()> {
+    let mut name = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => name = args.next().cloned(),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let [bin, txt] = positional[..] else {
+        bail!("{INFER_USAGE}");
+    };
+
+    let name = name.unwrap_or_else(|| {
+        let stem = std::path::Path::new(bin).file_stem().unwrap_or_default().to_string_lossy();
+        layout::field_name(&stem).to_ascii_uppercase()
+    });
+
+    let txt = String::from_utf8_lossy(&std::fs::read(txt)?).into_owned();
+    let layout = layout::infer(&std::fs::read(bin)?, &txt)?;
+
+    print!("{}", layout.to_rust(&name));
+    for line in layout.report() {
+        println!("// {line}");
+    }
+
+    Ok(())
+}
 
 fn main() {
-    if let Err(err) = parser::run() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(|a| a.as_str()) {
+        Some("query") => query(&args[1..]),
+        Some("which") => which(&args[1..]),
+        Some("pack") => pack(&args[1..]),
+        Some("infer") => infer(&args[1..]),
+        _ => parser::run(),
+    };
+
+    if let Err(err) = result {
         panic!("{err}");
     }
 }
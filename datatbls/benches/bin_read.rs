@@ -0,0 +1,54 @@
+use criterion::{criterion_group, criterion_main, Criterion, black_box};
+
+use datatbls::bin::{BinFile, BinView, Schema};
+use datatbls::fields;
+
+const RECORD_SIZE: usize = 0x1A8;
+const RECORD_COUNT: usize = 4096;
+
+/// a weapons.bin sized table of letters, the values do not matter but strings
+/// have to be utf-8
+fn items_bin() -> Vec<u8> {
+    let mut data = (RECORD_COUNT as u32).to_le_bytes().to_vec();
+    data.extend((0..RECORD_COUNT * RECORD_SIZE).map(|i| b'a' + (i * 7 % 26) as u8));
+    data
+}
+
+fn read(c: &mut Criterion) {
+    let data = items_bin();
+
+    c.bench_function("read items", |b| b.iter(|| {
+        BinFile::from_bytes(black_box(data.clone()), &fields::ITEMS).read().unwrap()
+    }));
+
+    // the owned path the tools used before views: decode every record, then
+    // look the field up by name
+    c.bench_function("read items and sum by name", |b| b.iter(|| {
+        let rec = BinFile::from_bytes(black_box(data.clone()), &fields::ITEMS).read().unwrap();
+        rec.iter().map(|r| r.get("level").value.u8() as i64).sum::<i64>()
+    }));
+
+    let schema = Schema::new(&fields::ITEMS);
+
+    c.bench_function("view items", |b| b.iter(|| {
+        let view = BinView::parse(black_box(&data), &schema).unwrap();
+        let level = schema.index_of("level").unwrap();
+        view.iter().map(|r| r.at(level).as_i64().unwrap()).sum::<i64>()
+    }));
+}
+
+fn lookup(c: &mut Criterion) {
+    let rec = BinFile::from_bytes(items_bin(), &fields::ITEMS).read().unwrap();
+
+    c.bench_function("lookup by name", |b| b.iter(|| {
+        rec.iter().map(|r| r.get(black_box("level")).value.u8() as u64).sum::<u64>()
+    }));
+
+    c.bench_function("lookup by index", |b| b.iter(|| {
+        let level = rec.schema().index_of(black_box("level")).unwrap();
+        rec.iter().map(|r| r.at(level).value.u8() as u64).sum::<u64>()
+    }));
+}
+
+criterion_group!(benches, read, lookup);
+criterion_main!(benches);
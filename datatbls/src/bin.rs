@@ -2,6 +2,7 @@
 
 use std::path::Path;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::io::{Seek, Cursor};
 use ml::io::{File, ReadExt, LittleEndian};
 use anyhow::Result;
@@ -25,7 +26,7 @@ pub enum Value {
 
     StringId(u16),
     ItemCode(u32),
-    String(usize, Option<String>),
+    String(String),
 }
 
 impl std::fmt::Debug for Value {
@@ -46,7 +47,7 @@ impl std::fmt::Debug for Value {
 
             Self::StringId(v) => write!(f, "StringId: 0x{v:04X} ({v})"),
             Self::ItemCode(v) => write!(f, "ItemCode: 0x{v:08X} ({v})"),
-            Self::String(size, s) => write!(f, "String: {}", s.as_ref().unwrap()),
+            Self::String(s) => write!(f, "String: {s}"),
         }
     }
 }
@@ -73,7 +74,7 @@ macro_rules! value_impl {
 impl Value {
     pub fn str(&self) -> &str {
         match self {
-            Self::String(size, s) => s.as_ref().unwrap(),
+            Self::String(s) => s,
             _ => panic!("type is {self:?}"),
         }
     }
@@ -84,6 +85,28 @@ impl Value {
             _ => panic!("type is {self:?}"),
         }
     }
+
+    pub fn item_code(&self) -> u32 {
+        match self {
+            Self::ItemCode(code) => *code,
+            _ => panic!("type is {self:?}"),
+        }
+    }
+
+    /// the value of any scalar type widened to `i64`, `None` for arrays and strings
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int8(v) => Some(*v as i64),
+            Self::Int16(v) => Some(*v as i64),
+            Self::Int32(v) => Some(*v as i64),
+            Self::UInt8(v) => Some(*v as i64),
+            Self::UInt16(v) => Some(*v as i64),
+            Self::UInt32(v) => Some(*v as i64),
+            Self::StringId(v) => Some(*v as i64),
+            Self::ItemCode(v) => Some(*v as i64),
+            _ => None,
+        }
+    }
 }
 
 value_impl!(i8, Int8);
@@ -93,163 +116,613 @@ value_impl!(u8, UInt8);
 value_impl!(u16, UInt16);
 value_impl!(u32, UInt32);
 
+/// the storage of a scalar field or of an array element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Int8,
+    Int16,
+    Int32,
+    UInt8,
+    UInt16,
+    UInt32,
+    StringId,
+    ItemCode,
+}
+
+impl Primitive {
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Int8 | Self::UInt8 => 1,
+            Self::Int16 | Self::UInt16 | Self::StringId => 2,
+            Self::Int32 | Self::UInt32 | Self::ItemCode => 4,
+        }
+    }
+
+    /// the name `field!` takes
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Int8 => "i8",
+            Self::Int16 => "i16",
+            Self::Int32 => "i32",
+            Self::UInt8 => "u8",
+            Self::UInt16 => "u16",
+            Self::UInt32 => "u32",
+            Self::StringId => "StringId",
+            Self::ItemCode => "ItemCode",
+        }
+    }
+
+    /// the little endian bytes of `v` truncated to the type's size
+    fn encode(&self, v: i64) -> Vec<u8> {
+        match self {
+            Self::Int8 | Self::UInt8 => vec![v as u8],
+            Self::Int16 | Self::UInt16 | Self::StringId => (v as u16).to_le_bytes().to_vec(),
+            Self::Int32 | Self::UInt32 | Self::ItemCode => (v as u32).to_le_bytes().to_vec(),
+        }
+    }
+
+    fn decode(&self, b: &[u8]) -> Value {
+        macro_rules! le {
+            ($t:ty) => { <$t>::from_le_bytes(b.try_into().unwrap()) };
+        }
+
+        match self {
+            Self::Int8 => Value::Int8(b[0] as i8),
+            Self::Int16 => Value::Int16(le!(i16)),
+            Self::Int32 => Value::Int32(le!(i32)),
+            Self::UInt8 => Value::UInt8(b[0]),
+            Self::UInt16 => Value::UInt16(le!(u16)),
+            Self::UInt32 => Value::UInt32(le!(u32)),
+            Self::StringId => Value::StringId(le!(u16)),
+            Self::ItemCode => Value::ItemCode(le!(u32)),
+        }
+    }
+}
+
+/// how a field is stored in a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Primitive(Primitive),
+    /// a fixed number of elements
+    Array(Primitive, usize),
+    /// a fixed number of bytes, nul padded
+    String(usize),
+}
+
+impl FieldType {
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Primitive(p) => p.size(),
+            Self::Array(p, len) => p.size() * len,
+            Self::String(size) => *size,
+        }
+    }
+
+    /// decodes a value of the type from its `size()` bytes, string ids and item
+    /// codes in arrays are read as plain numbers
+    pub fn decode(&self, b: &[u8]) -> Value {
+        macro_rules! array {
+            ($variant:ident, $t:ty) => {
+                Value::$variant(b.chunks_exact(std::mem::size_of::<$t>()).map(|c| <$t>::from_le_bytes(c.try_into().unwrap())).collect())
+            };
+        }
+
+        match self {
+            Self::Primitive(p) => p.decode(b),
+            Self::Array(Primitive::Int8, _) => Value::I8Array(b.iter().map(|v| *v as i8).collect()),
+            Self::Array(Primitive::Int16, _) => array!(I16Array, i16),
+            Self::Array(Primitive::Int32, _) => array!(I32Array, i32),
+            Self::Array(Primitive::UInt8, _) => Value::U8Array(b.to_vec()),
+            Self::Array(Primitive::UInt16 | Primitive::StringId, _) => array!(U16Array, u16),
+            Self::Array(Primitive::UInt32 | Primitive::ItemCode, _) => array!(U32Array, u32),
+            Self::String(_) => Value::String(String::from_utf8_lossy(until_nul(b)).into_owned()),
+        }
+    }
+
+    /// whether `value` is what `decode` makes of this type
+    pub fn accepts(&self, value: &Value) -> bool {
+        use Primitive as P;
+
+        match self {
+            Self::Primitive(p) => matches!((p, value),
+                (P::Int8, Value::Int8(_)) | (P::Int16, Value::Int16(_)) | (P::Int32, Value::Int32(_)) |
+                (P::UInt8, Value::UInt8(_)) | (P::UInt16, Value::UInt16(_)) | (P::UInt32, Value::UInt32(_)) |
+                (P::StringId, Value::StringId(_)) | (P::ItemCode, Value::ItemCode(_))
+            ),
+            Self::Array(p, len) => array_len(value) == Some(*len) && matches!((p, value),
+                (P::Int8, Value::I8Array(_)) | (P::Int16, Value::I16Array(_)) | (P::Int32, Value::I32Array(_)) |
+                (P::UInt8, Value::U8Array(_)) | (P::UInt16 | P::StringId, Value::U16Array(_)) |
+                (P::UInt32 | P::ItemCode, Value::U32Array(_))
+            ),
+            Self::String(_) => matches!(value, Value::String(_)),
+        }
+    }
+
+    /// the `size()` bytes of `value`, strings are nul padded. bytes a string
+    /// had after its nul are not part of its value, they are written as zero
+    pub fn encode(&self, value: &Value) -> Result<Vec<u8>> {
+        if !self.accepts(value) {
+            anyhow::bail!("{value:?} is not a {self}");
+        }
+
+        let bytes = match (self, value) {
+            (Self::Primitive(p), v) => p.encode(v.as_i64().unwrap()),
+            (Self::Array(p, _), v) => array_elements(v).into_iter().flat_map(|e| p.encode(e)).collect(),
+            (Self::String(size), Value::String(s)) => {
+                if s.len() > *size {
+                    anyhow::bail!("\"{s}\" is longer than {size} bytes");
+                }
+
+                let mut b = s.as_bytes().to_vec();
+                b.resize(*size, 0);
+                b
+            },
+            _ => unreachable!(),
+        };
+
+        Ok(bytes)
+    }
+}
+
+fn array_len(value: &Value) -> Option<usize> {
+    match value {
+        Value::I8Array(v) => Some(v.len()),
+        Value::I16Array(v) => Some(v.len()),
+        Value::I32Array(v) => Some(v.len()),
+        Value::U8Array(v) => Some(v.len()),
+        Value::U16Array(v) => Some(v.len()),
+        Value::U32Array(v) => Some(v.len()),
+        _ => None,
+    }
+}
+
+fn array_elements(value: &Value) -> Vec<i64> {
+    match value {
+        Value::I8Array(v) => v.iter().map(|e| *e as i64).collect(),
+        Value::I16Array(v) => v.iter().map(|e| *e as i64).collect(),
+        Value::I32Array(v) => v.iter().map(|e| *e as i64).collect(),
+        Value::U8Array(v) => v.iter().map(|e| *e as i64).collect(),
+        Value::U16Array(v) => v.iter().map(|e| *e as i64).collect(),
+        Value::U32Array(v) => v.iter().map(|e| *e as i64).collect(),
+        _ => panic!("type is {value:?}"),
+    }
+}
+
+/// the `field!` spelling, e.g. `u16[6]` or `str[32]`
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Primitive(p) => write!(f, "{}", p.name()),
+            Self::Array(p, len) => write!(f, "{}[{len}]", p.name()),
+            Self::String(size) => write!(f, "str[{size}]"),
+        }
+    }
+}
+
+/// a named bit of a packed flag field, `name` is the boolean column of the .txt
+#[derive(Debug)]
+pub struct BitFlag {
+    pub name    : &'static str,
+    pub mask    : u32,
+}
+
+impl BitFlag {
+    pub const fn new(name: &'static str, bit: u32) -> BitFlag {
+        BitFlag{
+            name,
+            mask: 1 << bit,
+        }
+    }
+}
+
+/// named values of an enumerated field, values not listed are shown as numbers
+#[derive(Debug)]
+pub struct Enumeration {
+    pub name    : &'static str,
+    pub values  : &'static [(i64, &'static str)],
+}
+
+impl Enumeration {
+    pub const fn new(name: &'static str, values: &'static [(i64, &'static str)]) -> Enumeration {
+        Enumeration{
+            name,
+            values,
+        }
+    }
+
+    pub fn name_of(&self, value: i64) -> Option<&'static str> {
+        self.values.iter().find(|(v, _)| *v == value).map(|(_, n)| *n)
+    }
+
+    pub fn value_of(&self, name: &str) -> Option<i64> {
+        self.values.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)).map(|(v, _)| *v)
+    }
+
+    pub fn format(&self, value: i64) -> String {
+        self.name_of(value).map(|n| n.to_string()).unwrap_or_else(|| value.to_string())
+    }
+}
+
 #[derive(Debug)]
 #[derive(Clone)]
 pub struct Field {
     pub name    : String,
-    pub value   : Value,
+    pub ty      : FieldType,
     pub offset  : u64,
+    pub flags   : Option<&'static [BitFlag]>,
+    pub enumeration : Option<&'static Enumeration>,
+    pub reference   : Option<&'static str>,
+    pub calc        : Option<&'static str>,
 }
 
 impl Field {
-    pub fn new<T: Into<Value>>(name: &str, value: T, offset: u64) -> Field {
+    pub fn new(name: &str, ty: FieldType, offset: u64) -> Field {
         Field{
             name    : name.to_string(),
-            value   : value.into(),
+            ty,
             offset,
+            flags   : None,
+            enumeration : None,
+            reference   : None,
+            calc        : None,
         }
     }
 
-    pub fn validate_fields_offset(fields: &Vec<Field>) {
-        let mut offset = 0u64;
+    /// marks the value as an offset into the calc code buffer `code`
+    pub fn with_calc(mut self, code: &'static str) -> Field {
+        self.calc = Some(code);
+        self
+    }
+
+    /// marks the value as a row index into the table `table`
+    pub fn with_ref(mut self, table: &'static str) -> Field {
+        self.reference = Some(table);
+        self
+    }
+
+    pub fn with_enum(mut self, enumeration: &'static Enumeration) -> Field {
+        self.enumeration = Some(enumeration);
+        self
+    }
+
+    pub fn with_flags(mut self, flags: &'static [BitFlag]) -> Field {
+        self.flags = Some(flags);
+        self
+    }
+
+    /// the bytes a value of the field's type takes
+    pub fn size(&self) -> usize {
+        self.ty.size()
+    }
+
+    /// decodes a value of the field's type from its `size()` bytes
+    pub fn decode(&self, b: &[u8]) -> Value {
+        self.ty.decode(b)
+    }
+}
 
-        for f in fields.iter() {
-            if f.offset != offset {
-                panic!("field {} offset is 0x{:X}, expect 0x{:X}", f.name, offset, f.offset);
+/// a fixed size string field without its nul padding
+fn until_nul(b: &[u8]) -> &[u8] {
+    &b[..b.iter().position(|v| *v == 0).unwrap_or(b.len())]
+}
+
+/// the layout of a table, shared by all its records so names are looked up in
+/// one place and stored once
+#[derive(Debug)]
+pub struct Schema {
+    fields      : Vec<Field>,
+    index       : HashMap<String, usize>,
+    record_size : usize,
+    /// the bytes of a record no field covers, in order
+    gaps        : Vec<std::ops::Range<usize>>,
+}
+
+impl Schema {
+    /// records end with the last field
+    pub fn new(fields: &[Field]) -> Schema {
+        let size = fields.iter().map(|f| f.offset as usize + f.size()).max().unwrap_or(0);
+        Self::with_record_size(fields, size)
+    }
+
+    /// a layout of `record_size` byte records where `fields` sit at their
+    /// offsets, in any order. the fields do not have to cover every byte, the
+    /// rest is kept as raw gaps. panics when fields overlap or do not fit
+    pub fn with_record_size(fields: &[Field], record_size: usize) -> Schema {
+        let mut sorted: Vec<&Field> = fields.iter().collect();
+        sorted.sort_by_key(|f| f.offset);
+
+        let mut gaps = Vec::new();
+        let mut pos = 0;
+
+        for f in sorted {
+            let start = f.offset as usize;
+
+            if start < pos {
+                panic!("field {} at 0x{start:X} overlaps the field before it, which ends at 0x{pos:X}", f.name);
             }
 
-            match &f.value {
-                Value::Int8(_) => offset += 1,
-                Value::Int16(_) => offset += 2,
-                Value::Int32(_) => offset += 4,
-                Value::UInt8(_) => offset += 1,
-                Value::UInt16(_) => offset += 2,
-                Value::UInt32(_) => offset += 4,
-                Value::I8Array(v) => offset += v.capacity() as u64 * 1,
-                Value::I16Array(v) => offset += v.capacity() as u64 * 2,
-                Value::I32Array(v) => offset += v.capacity() as u64 * 4,
-                Value::U8Array(v) => offset += v.capacity() as u64 * 1,
-                Value::U16Array(v) => offset += v.capacity() as u64 * 2,
-                Value::U32Array(v) => offset += v.capacity() as u64 * 4,
-
-                Value::StringId(_) => offset += 2,
-                Value::ItemCode(_) => offset += 4,
-                Value::String(size, s) => offset += *size as u64,
+            if start > pos {
+                gaps.push(pos..start);
             }
+
+            pos = start + f.size();
+        }
+
+        if pos > record_size {
+            panic!("fields end at 0x{pos:X}, past the record size 0x{record_size:X}");
+        }
+
+        if pos < record_size {
+            gaps.push(pos..record_size);
+        }
+
+        Self {
+            index       : fields.iter().enumerate().map(|(i, f)| (f.name.clone(), i)).collect(),
+            fields      : Vec::from(fields),
+            record_size,
+            gaps,
         }
     }
 
-    pub fn read<T: ReadExt>(&mut self, fs: &mut T) -> Result<()> {
-        match &mut self.value {
-            Value::UInt8(_) => {
-                self.value = Value::from(fs.u8());
-            },
-            Value::UInt16(_) => {
-                self.value = Value::from(fs.u16::<LE>());
-            },
-            Value::UInt32(_) => {
-                self.value = Value::from(fs.u32::<LE>());
-            },
-            Value::Int8(_) => {
-                self.value = Value::from(fs.i8());
-            },
-            Value::Int16(_) => {
-                self.value = Value::from(fs.i16::<LE>());
-            },
-            Value::Int32(_) => {
-                self.value = Value::from(fs.i32::<LE>());
-            },
-            Value::I8Array(v) => {
-                for i in 0..v.capacity() {
-                    v[i] = fs.i8();
-                }
-            },
-            Value::I16Array(v) => {
-                for i in 0..v.capacity() {
-                    v[i] = fs.i16::<LE>();
-                }
-            },
-            Value::I32Array(v) => {
-                for i in 0..v.capacity() {
-                    v[i] = fs.i32::<LE>();
-                }
-            },
-            Value::U8Array(v) => {
-                for i in 0..v.capacity() {
-                    v[i] = fs.u8();
-                }
-            },
-            Value::U16Array(v) => {
-                for i in 0..v.capacity() {
-                    v[i] = fs.u16::<LE>();
-                }
-            },
-            Value::U32Array(v) => {
-                for i in 0..v.capacity() {
-                    v[i] = fs.u32::<LE>();
-                }
-            },
-            Value::StringId(_) => {
-                self.value = Value::StringId(fs.u16::<LE>());
-            },
+    /// checks the layout of a whole table: panics when fields overlap or
+    /// leave bytes no field covers
+    pub fn validate(fields: &[Field]) {
+        let schema = Self::new(fields);
 
-            Value::ItemCode(_) => {
-                self.value = Value::ItemCode(fs.u32::<LE>());
-            },
-            Value::String(size, s) => {
-                let b = fs.read_bytes(*size)?;
-                let b = &b[0..b.iter().position(|v| *v == 0).unwrap_or(b.len())];
-                let s = String::from_utf8(b.to_vec()).unwrap();
-                self.value = Value::String(*size, Some(s));
-            },
+        if let Some(gap) = schema.gaps.first() {
+            panic!("no field covers 0x{:X}..0x{:X}", gap.start, gap.end);
         }
+    }
 
-        Ok(())
+    pub fn record_size(&self) -> usize {
+        self.record_size
+    }
+
+    /// the bytes of field `idx` in the bytes of a record
+    pub fn field_range(&self, idx: usize) -> std::ops::Range<usize> {
+        let offset = self.fields[idx].offset as usize;
+        offset..offset + self.fields[idx].size()
+    }
+
+    pub fn gaps(&self) -> &[std::ops::Range<usize>] {
+        &self.gaps
+    }
+
+    /// the bytes of a record in gaps
+    pub fn gap_size(&self) -> usize {
+        self.gaps.iter().map(|g| g.len()).sum()
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// the position of field `name`, for lookups through `Record::at` in loops
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
     }
 }
 
-#[derive(Debug)]
-pub struct Record {
-    fields: Vec<Field>,
-    hm: HashMap<String, usize>,
+/// a field of a record: the schema's field with the record's value
+#[derive(Debug, Clone, Copy)]
+pub struct FieldRef<'a> {
+    pub field   : &'a Field,
+    pub value   : &'a Value,
+}
+
+impl std::ops::Deref for FieldRef<'_> {
+    type Target = Field;
+
+    fn deref(&self) -> &Field {
+        self.field
+    }
 }
 
-impl From<Vec<Field>> for Record {
-    fn from(fields: Vec<Field>) -> Self {
-        let mut hm = HashMap::new();
-        for (i, f) in fields.iter().enumerate() {
-            hm.insert(f.name.clone(), i);
+impl FieldRef<'_> {
+    /// the calc offsets of the value, one per array element
+    pub fn calc_offsets(&self) -> Vec<u32> {
+        match &self.value {
+            Value::Int32(v) => vec![*v as u32],
+            Value::UInt32(v) => vec![*v],
+            Value::I32Array(v) => v.iter().map(|v| *v as u32).collect(),
+            Value::U32Array(v) => v.to_vec(),
+            _ => panic!("{} can not be a calc", self.name),
         }
+    }
 
-        Self {
-            fields,
-            hm,
+    /// the referenced rows, `None` where the value is a null reference (-1 / 0xFFFF)
+    pub fn ref_rows(&self) -> Vec<Option<usize>> {
+        fn row<T: TryInto<usize> + Copy>(v: T, null: bool) -> Option<usize> {
+            if null { None } else { v.try_into().ok() }
+        }
+
+        match &self.value {
+            Value::Int8(v) => vec![row(*v, *v < 0)],
+            Value::Int16(v) => vec![row(*v, *v < 0)],
+            Value::Int32(v) => vec![row(*v, *v < 0)],
+            Value::UInt8(v) => vec![row(*v, *v == u8::MAX)],
+            Value::UInt16(v) => vec![row(*v, *v == u16::MAX)],
+            Value::UInt32(v) => vec![row(*v, *v == u32::MAX)],
+            Value::I8Array(v) => v.iter().map(|v| row(*v, *v < 0)).collect(),
+            Value::I16Array(v) => v.iter().map(|v| row(*v, *v < 0)).collect(),
+            Value::I32Array(v) => v.iter().map(|v| row(*v, *v < 0)).collect(),
+            Value::U8Array(v) => v.iter().map(|v| row(*v, *v == u8::MAX)).collect(),
+            Value::U16Array(v) => v.iter().map(|v| row(*v, *v == u16::MAX)).collect(),
+            Value::U32Array(v) => v.iter().map(|v| row(*v, *v == u32::MAX)).collect(),
+            _ => panic!("{} can not be a reference", self.name),
+        }
+    }
+
+    pub fn ref_row(&self) -> Option<usize> {
+        self.ref_rows()[0]
+    }
+
+    pub fn enum_name(&self) -> Option<&'static str> {
+        self.enumeration?.name_of(self.value.as_i64()?)
+    }
+
+    /// the enumerated name of the value, or the number when it has none
+    pub fn enum_format(&self) -> String {
+        let value = self.value.as_i64().unwrap_or_else(|| panic!("{} is not a scalar", self.name));
+        match self.enumeration {
+            Some(e) => e.format(value),
+            None => value.to_string(),
         }
     }
+
+    pub fn bits(&self) -> u32 {
+        self.value.as_i64().unwrap_or_else(|| panic!("{} is not a scalar", self.name)) as u32
+    }
+
+    pub fn is_set(&self, flag: &str) -> bool {
+        let flags = self.flags.unwrap_or_else(|| panic!("{} has no flags", self.name));
+        let flag = flags.iter().find(|f| f.name == flag).unwrap_or_else(|| panic!("{} has no flag {flag}", self.name));
+        self.bits() & flag.mask != 0
+    }
+
+    /// every named flag with its state, in schema order
+    pub fn flag_values(&self) -> Vec<(&'static str, bool)> {
+        let bits = self.bits();
+        self.flags.unwrap_or(&[]).iter().map(|f| (f.name, bits & f.mask != 0)).collect()
+    }
+
+    /// the set bits no flag names
+    pub fn unnamed_bits(&self) -> u32 {
+        self.flags.unwrap_or(&[]).iter().fold(self.bits(), |bits, f| bits & !f.mask)
+    }
+
+    /// names of the set flags, unnamed set bits are returned as `bit N`
+    pub fn set_flags(&self) -> Vec<String> {
+        let bits = self.bits();
+        let unnamed = self.unnamed_bits();
+
+        let named = self.flags.unwrap_or(&[]).iter().filter(|f| bits & f.mask != 0).map(|f| f.name.to_string());
+        let unnamed = (0..32).filter(|b| unnamed & (1 << b) != 0).map(|b| format!("bit {b}"));
+
+        named.chain(unnamed).collect()
+    }
+}
+
+/// one row, its values in schema order and the bytes of the schema's gaps
+#[derive(Debug)]
+pub struct Record {
+    schema  : Arc<Schema>,
+    values  : Box<[Value]>,
+    gaps    : Box<[u8]>,
 }
 
 impl Record {
-    pub fn iter(&self) -> std::slice::Iter<'_, Field> {
-        self.fields.iter()
+    /// a record with zeroed gaps
+    pub fn new(schema: Arc<Schema>, values: Vec<Value>) -> Record {
+        let gaps = vec![0; schema.gap_size()];
+        Self::with_gaps(schema, values, gaps)
     }
 
-    pub fn get(&self, key: &str) -> &Field {
-        let idx = self.hm.get(key).expect(format!("{key} not exists").as_str());
-        &self.fields[*idx]
+    /// `gaps` holds the bytes of `Schema::gaps` one after another
+    pub fn with_gaps(schema: Arc<Schema>, values: Vec<Value>, gaps: Vec<u8>) -> Record {
+        assert_eq!(schema.len(), values.len(), "record does not match its schema");
+        assert_eq!(schema.gap_size(), gaps.len(), "gaps do not match the schema");
+
+        Self {
+            schema,
+            values  : values.into_boxed_slice(),
+            gaps    : gaps.into_boxed_slice(),
+        }
+    }
+
+    /// replaces the value of `key`, panics when it is not of the field's type
+    pub fn set(&mut self, key: &str, value: Value) {
+        let idx = self.schema.index_of(key).unwrap_or_else(|| panic!("{key} not exists"));
+        let ty = self.schema.fields[idx].ty;
+
+        if !ty.accepts(&value) {
+            panic!("{key} is {ty}, not {value:?}");
+        }
+
+        self.values[idx] = value;
+    }
+
+    pub fn gap_bytes(&self) -> &[u8] {
+        &self.gaps
+    }
+
+    /// the record as stored in a bin, gaps as they were read and strings
+    /// zero padded, see `FieldType::encode`
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut b = vec![0; self.schema.record_size];
+        let mut gaps = self.gaps.iter();
+
+        for range in self.schema.gaps.iter() {
+            for (dst, src) in b[range.clone()].iter_mut().zip(&mut gaps) {
+                *dst = *src;
+            }
+        }
+
+        for (idx, (f, value)) in self.schema.fields.iter().zip(self.values.iter()).enumerate() {
+            let bytes = f.ty.encode(value).map_err(|e| anyhow::anyhow!("{}: {e}", f.name))?;
+            b[self.schema.field_range(idx)].copy_from_slice(&bytes);
+        }
+
+        Ok(b)
+    }
+
+    pub fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = FieldRef<'_>> {
+        self.schema.fields.iter().zip(self.values.iter()).map(|(field, value)| FieldRef{field, value})
+    }
+
+    pub fn get(&self, key: &str) -> FieldRef<'_> {
+        self.find(key).unwrap_or_else(|| panic!("{key} not exists"))
+    }
+
+    /// like `get` but `None` for unknown keys
+    pub fn find(&self, key: &str) -> Option<FieldRef<'_>> {
+        self.schema.index_of(key).map(|idx| self.at(idx))
+    }
+
+    /// the field at `idx` of the schema, see `Schema::index_of`
+    pub fn at(&self, idx: usize) -> FieldRef<'_> {
+        FieldRef{
+            field   : &self.schema.fields[idx],
+            value   : &self.values[idx],
+        }
     }
 }
 
 pub struct BinRecord {
-    records: Vec<Record>,
+    schema  : Arc<Schema>,
+    records : Vec<Record>,
+}
+
+impl Default for BinRecord {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BinRecord {
     pub fn new() -> BinRecord {
         BinRecord{
-            records: Vec::new(),
+            schema  : Arc::new(Schema::new(&[])),
+            records : Vec::new(),
         }
     }
 
+    pub fn schema(&self) -> &Arc<Schema> {
+        &self.schema
+    }
+
     pub fn iter(&self) -> std::slice::Iter<'_, Record> {
         self.records.iter()
     }
@@ -257,41 +730,454 @@ impl BinRecord {
     pub fn records(&self) -> &Vec<Record> {
         &self.records
     }
+
+    pub fn get_mut(&mut self, row: usize) -> Option<&mut Record> {
+        self.records.get_mut(row)
+    }
+
+    /// the bin file of the records
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut b = Vec::with_capacity(4 + self.records.len() * self.schema.record_size);
+        b.extend((self.records.len() as u32).to_le_bytes());
+
+        for r in self.records.iter() {
+            b.extend(r.to_bytes()?);
+        }
+
+        Ok(b)
+    }
+
+    pub fn write<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
 }
 
 pub struct BinFile {
-    file: File,
-    fields: Vec<Field>,
+    data    : Vec<u8>,
+    schema  : Arc<Schema>,
 }
 
 impl BinFile {
     pub fn open<T: AsRef<Path>>(path: T, fields: &[Field]) -> Result<BinFile> {
-        Ok(BinFile{
-            file    : File::open(path)?,
-            fields  : Vec::from(fields),
-        })
+        let mut file = File::open(path)?;
+        let size = file.size()? as usize;
+
+        Ok(Self::from_bytes(file.read_bytes(size)?, fields))
+    }
+
+    /// a bin already in memory, e.g. read out of an mpq
+    pub fn from_bytes(data: Vec<u8>, fields: &[Field]) -> BinFile {
+        Self::with_schema(data, Arc::new(Schema::new(fields)))
+    }
+
+    /// like `from_bytes` sharing a schema with other tables of the same layout,
+    /// or with a partial schema made by `Schema::with_record_size`
+    pub fn with_schema(data: Vec<u8>, schema: Arc<Schema>) -> BinFile {
+        BinFile{
+            data,
+            schema,
+        }
     }
 
     pub fn read(&mut self) -> Result<BinRecord> {
-        let buf = self.file.read_bytes(self.file.size().unwrap() as usize)?;
-        let mut r = Cursor::new(&buf);
+        let view = BinView::parse(&self.data, &self.schema)?;
+        let records = view.iter().map(|r| Record::with_gaps(self.schema.clone(), r.values(), r.gap_bytes())).collect();
+
+        Ok(BinRecord{
+            schema: self.schema.clone(),
+            records,
+        })
+    }
+
+    /// the records borrowed from the file's bytes, see `BinView`
+    pub fn view(&self) -> Result<BinView<'_>> {
+        BinView::parse(&self.data, &self.schema)
+    }
+}
 
-        let mut record = BinRecord::new();
-        let record_count = r.u32::<LE>();
+/// a bin parsed in place: records and their fields are views into `data`,
+/// which can be an mmap or an in-archive buffer. values are decoded on access
+/// and strings are borrowed. this is the compact form, `BinFile::read` still
+/// allocates a `Vec` per array and a `String` per string field of every record
+#[derive(Clone, Copy)]
+pub struct BinView<'a> {
+    schema  : &'a Schema,
+    records : &'a [u8],
+    count   : usize,
+}
+
+impl<'a> BinView<'a> {
+    /// checks the size of `data` and that the strings are utf-8, so the
+    /// accessors do not fail later
+    pub fn parse(data: &'a [u8], schema: &'a Schema) -> Result<BinView<'a>> {
+        if data.len() < 4 {
+            anyhow::bail!("bin is {} bytes, too short for a record count", data.len());
+        }
+
+        let count = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+        let size = count * schema.record_size();
+
+        if data.len() - 4 < size {
+            anyhow::bail!("{count} records of 0x{:X} bytes need 0x{size:X} bytes, bin has 0x{:X}", schema.record_size(), data.len() - 4);
+        }
 
-        for i in 0..record_count {
-            let mut fields = self.fields.clone();
+        let view = BinView{
+            schema,
+            records : &data[4..4 + size],
+            count,
+        };
 
-            for f in fields.iter_mut() {
-                let pos = r.stream_position().unwrap();
-                f.read(&mut r)?;
+        for (idx, f) in schema.fields().iter().enumerate() {
+            if let FieldType::String(_) = f.ty {
+                for (row, r) in view.iter().enumerate() {
+                    if let Err(e) = std::str::from_utf8(until_nul(r.at(idx).bytes)) {
+                        anyhow::bail!("{} of record {row}: {e}", f.name);
+                    }
+                }
             }
+        }
+
+        Ok(view)
+    }
+
+    pub fn schema(&self) -> &'a Schema {
+        self.schema
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn get(&self, row: usize) -> Option<RecordView<'a>> {
+        let size = self.schema.record_size();
 
-            record.records.push(fields.into());
+        (row < self.count).then(|| RecordView{
+            schema  : self.schema,
+            bytes   : &self.records[row * size..(row + 1) * size],
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = RecordView<'a>> + 'a {
+        let view = *self;
+        (0..self.count).map(move |row| view.get(row).unwrap())
+    }
+}
+
+/// one record of a `BinView`
+#[derive(Clone, Copy)]
+pub struct RecordView<'a> {
+    schema  : &'a Schema,
+    bytes   : &'a [u8],
+}
+
+impl<'a> RecordView<'a> {
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = FieldView<'a>> + 'a {
+        let view = *self;
+        (0..self.schema.len()).map(move |idx| view.at(idx))
+    }
+
+    pub fn get(&self, key: &str) -> FieldView<'a> {
+        self.find(key).unwrap_or_else(|| panic!("{key} not exists"))
+    }
+
+    pub fn find(&self, key: &str) -> Option<FieldView<'a>> {
+        self.schema.index_of(key).map(|idx| self.at(idx))
+    }
+
+    pub fn at(&self, idx: usize) -> FieldView<'a> {
+        FieldView{
+            field   : &self.schema.fields[idx],
+            bytes   : &self.bytes[self.schema.field_range(idx)],
+        }
+    }
+
+    /// every value decoded, in schema order
+    pub fn values(&self) -> Vec<Value> {
+        self.iter().map(|f| f.value()).collect()
+    }
+
+    /// the bytes no field covers with their offsets
+    pub fn gaps(&self) -> impl Iterator<Item = (usize, &'a [u8])> + 'a {
+        let bytes = self.bytes;
+        self.schema.gaps.iter().map(move |g| (g.start, &bytes[g.clone()]))
+    }
+
+    /// the gap bytes one after another, as `Record::with_gaps` takes them
+    pub fn gap_bytes(&self) -> Vec<u8> {
+        self.gaps().flat_map(|(_, b)| b.iter().copied()).collect()
+    }
+}
+
+/// a field of a `RecordView` and its undecoded bytes
+#[derive(Clone, Copy)]
+pub struct FieldView<'a> {
+    pub field   : &'a Field,
+    pub bytes   : &'a [u8],
+}
+
+impl<'a> FieldView<'a> {
+    pub fn value(&self) -> Value {
+        self.field.decode(self.bytes)
+    }
+
+    /// the string without copying, panics for other types
+    pub fn str(&self) -> &'a str {
+        match self.field.ty {
+            FieldType::String(_) => std::str::from_utf8(until_nul(self.bytes)).unwrap(),
+            _ => panic!("{} is not a string", self.field.name),
+        }
+    }
+
+    /// the value of a scalar field widened to `i64`, `None` for arrays and strings
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.field.ty {
+            FieldType::Primitive(p) => p.decode(self.bytes).as_i64(),
+            _ => None,
         }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// `rows` zeroed records of `fields`
+    pub(crate) fn zeroed(fields: &[Field], rows: usize) -> BinRecord {
+        let size = Schema::new(fields).record_size();
+        let mut b = (rows as u32).to_le_bytes().to_vec();
+        b.resize(4 + rows * size, 0);
+
+        BinFile::from_bytes(b, fields).read().unwrap()
+    }
+
+    fn sample_fields() -> Vec<Field> {
+        vec![
+            crate::field!("name", str[8], 0x00),
+            crate::field!("id", u16, 0x08),
+            crate::field!("level", i8, 0x0A),
+            crate::field!("stats", i16[2], 0x0C),
+        ]
+    }
+
+    /// two records of `sample_fields` with a byte of padding at 0x0B
+    fn sample_bytes() -> Vec<u8> {
+        let mut b = 2u32.to_le_bytes().to_vec();
+        b.extend(b"axe\0\0\0\0\0");
+        b.extend([0x34, 0x12, 0xFF, 0xAA, 0x01, 0x00, 0xFE, 0xFF]);
+        b.extend(b"sword\0\0\0");
+        b.extend([0x07, 0x00, 0x05, 0xBB, 0x00, 0x00, 0x02, 0x00]);
+        b
+    }
+
+    #[test]
+    fn records_look_fields_up_by_name_and_index() {
+        let bin = BinFile::from_bytes(sample_bytes(), &sample_fields()).read().unwrap();
+        let schema = bin.schema();
+
+        assert_eq!(schema.len(), 4);
+        assert_eq!(schema.record_size(), 0x10);
+        assert_eq!(schema.gaps().len(), 1);
+        assert_eq!(schema.gaps()[0], 0x0B..0x0C);
+        assert_eq!(schema.index_of("level"), Some(2));
+        assert_eq!(schema.index_of("missing"), None);
+        assert!(Schema::new(&[]).is_empty());
+        assert!(BinRecord::default().records().is_empty());
+
+        let axe = &bin.records()[0];
+        assert_eq!(axe.get("name").value.str(), "axe");
+        assert_eq!(axe.get("id").value.u16(), 0x1234);
+        assert_eq!(axe.get("level").value.i8(), -1);
+        assert!(matches!(axe.get("stats").value, Value::I16Array(v) if v[..] == [1, -2]));
+        assert_eq!(axe.gap_bytes(), [0xAA]);
+
+        let sword = &bin.records()[1];
+        let idx = schema.index_of("id").unwrap();
+        assert_eq!(sword.at(idx).value.u16(), 7);
+        assert_eq!(sword.at(idx).name, "id");
+        assert!(sword.find("missing").is_none());
+        assert_eq!(sword.iter().map(|f| f.field.name.as_str()).collect::<Vec<_>>(), ["name", "id", "level", "stats"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing not exists")]
+    fn get_panics_for_unknown_fields() {
+        let bin = BinFile::from_bytes(sample_bytes(), &sample_fields()).read().unwrap();
+        bin.records()[0].get("missing");
+    }
+
+    #[test]
+    fn set_values_are_written_back() {
+        let mut bin = BinFile::from_bytes(sample_bytes(), &sample_fields()).read().unwrap();
+        let axe = bin.get_mut(0).unwrap();
+        axe.set("level", Value::Int8(30));
+        axe.set("name", Value::String("hatchet".to_string()));
+        assert_eq!(axe.get("level").value.i8(), 30);
+
+        let bytes = bin.to_bytes().unwrap();
+        assert_eq!(&bytes[4..0x10], b"hatchet\0\x34\x12\x1E\xAA");
+        assert_eq!(bytes[0x14..], sample_bytes()[0x14..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "level is i8")]
+    fn set_panics_for_the_wrong_type() {
+        let mut bin = BinFile::from_bytes(sample_bytes(), &sample_fields()).read().unwrap();
+        bin.get_mut(0).unwrap().set("level", Value::Int32(30));
+    }
+
+    #[test]
+    fn view_borrows_strings_from_the_slice() {
+        let data = sample_bytes();
+        let schema = Schema::new(&sample_fields());
+        let view = BinView::parse(&data, &schema).unwrap();
+
+        assert_eq!(view.len(), 2);
+        assert!(!view.is_empty());
+        assert!(view.get(2).is_none());
+
+        let sword = view.get(1).unwrap();
+        let name = sword.get("name").str();
+        assert_eq!(name, "sword");
+        assert!(data.as_ptr_range().contains(&name.as_ptr()));
+
+        assert_eq!(sword.get("level").as_i64(), Some(5));
+        assert_eq!(sword.get("stats").as_i64(), None);
+        assert_eq!(sword.bytes(), &data[0x14..0x24]);
+        assert_eq!(sword.gaps().collect::<Vec<_>>(), [(0x0B, &[0xBB][..])]);
+
+        let names: Vec<&str> = view.iter().map(|r| r.get("name").str()).collect();
+        assert_eq!(names, ["axe", "sword"]);
+
+        assert!(BinView::parse(&0u32.to_le_bytes(), &schema).unwrap().is_empty());
+    }
+
+    #[test]
+    fn view_reports_short_bins_and_bad_strings() {
+        let schema = Schema::new(&sample_fields());
+        let err = |data: &[u8]| BinView::parse(data, &schema).err().unwrap().to_string();
+        let data = sample_bytes();
+
+        assert_eq!(err(&data[..3]), "bin is 3 bytes, too short for a record count");
+        assert_eq!(err(&data[..0x20]), "2 records of 0x10 bytes need 0x20 bytes, bin has 0x1C");
+
+        let mut bad = data.clone();
+        bad[0x14] = 0xFF;
+        assert!(err(&bad).starts_with("name of record 1: invalid utf-8"));
+    }
+
+    #[test]
+    fn enumerations_show_names_and_unknown_values_as_numbers() {
+        assert_eq!(crate::fields::CHAR_CLASS.format(4), "bar");
+        assert_eq!(crate::fields::CHAR_CLASS.format(-1), "-1");
+        assert_eq!(crate::fields::CHAR_CLASS.format(7), "7");
+        assert_eq!(crate::fields::CHAR_CLASS.name_of(7), None);
+        assert_eq!(crate::fields::CHAR_CLASS.value_of("DRU"), Some(5));
+        assert_eq!(crate::fields::CHAR_CLASS.value_of("7"), None);
+
+        let mut items = zeroed(&crate::fields::ITEMS, 2);
+        items.get_mut(0).unwrap().set("sub_type", Value::UInt8(1));
+        items.get_mut(1).unwrap().set("sub_type", Value::UInt8(9));
+
+        let armor = items.records()[0].get("sub_type");
+        assert_eq!(armor.enum_name(), Some("armor"));
+        assert_eq!(armor.enum_format(), "armor");
+
+        let unknown = items.records()[1].get("sub_type");
+        assert_eq!(unknown.enum_name(), None);
+        assert_eq!(unknown.enum_format(), "9");
+        assert_eq!(items.records()[1].get("level").enum_format(), "0");
+    }
 
-        // println!("records: {record_count}");
+    #[test]
+    fn flags_name_their_bits_and_show_the_rest_by_number() {
+        let mut items = zeroed(&crate::fields::ITEMS, 1);
+        items.get_mut(0).unwrap().set("bit_field_1", Value::UInt32(0b1001 | 1 << 31));
+        items.get_mut(0).unwrap().set("cost", Value::UInt32(0b101));
+        let flags = items.records()[0].get("bit_field_1");
 
-        Ok(record)
+        assert_eq!(flags.flag_values(), [("nodurability", true), ("quivered", false)]);
+        assert_eq!(flags.unnamed_bits(), 0b1000 | 1 << 31);
+        assert_eq!(flags.set_flags(), ["nodurability", "bit 3", "bit 31"]);
+        assert!(flags.is_set("nodurability"));
+        assert!(!flags.is_set("quivered"));
+
+        // fields without flags have every set bit unnamed
+        let cost = items.records()[0].get("cost");
+        assert!(cost.flag_values().is_empty());
+        assert_eq!(cost.set_flags(), ["bit 0", "bit 2"]);
+    }
+
+    #[test]
+    fn partial_schemas_keep_the_bytes_they_skip() {
+        let fields = [crate::field!("level", i8, 0x0A), crate::field!("name", str[8], 0x00)];
+        let schema = Arc::new(Schema::with_record_size(&fields, 0x10));
+
+        assert_eq!(schema.len(), 2);
+        assert_eq!(schema.gaps(), [0x08..0x0A, 0x0B..0x10]);
+        assert_eq!(schema.gap_size(), 7);
+
+        let data = sample_bytes();
+        let bin = BinFile::with_schema(data.clone(), schema).read().unwrap();
+        assert_eq!(bin.records()[1].get("level").value.i8(), 5);
+        assert_eq!(bin.records()[0].gap_bytes(), [0x34, 0x12, 0xAA, 0x01, 0x00, 0xFE, 0xFF]);
+
+        assert_eq!(bin.to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn full_schemas_round_trip_every_byte() {
+        let data = sample_bytes();
+        let bin = BinFile::with_schema(data.clone(), Arc::new(Schema::new(&sample_fields()))).read().unwrap();
+
+        assert_eq!(bin.to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn string_padding_is_written_as_zero() {
+        let mut data = sample_bytes();
+        data[4 + 5] = b'x';
+
+        let bin = BinFile::from_bytes(data, &sample_fields()).read().unwrap();
+        assert_eq!(bin.records()[0].get("name").value.str(), "axe");
+        assert_eq!(bin.to_bytes().unwrap(), sample_bytes());
+    }
+
+    #[test]
+    #[should_panic(expected = "field id at 0x6 overlaps the field before it, which ends at 0x8")]
+    fn overlapping_fields_panic() {
+        Schema::with_record_size(&[crate::field!("name", str[8], 0x00), crate::field!("id", u16, 0x06)], 0x10);
+    }
+
+    #[test]
+    #[should_panic(expected = "fields end at 0x11, past the record size 0x10")]
+    fn fields_past_the_record_panic() {
+        Schema::with_record_size(&[crate::field!("name", str[8], 0x00), crate::field!("id", u8, 0x10)], 0x10);
+    }
+
+    #[test]
+    #[should_panic(expected = "no field covers 0xB..0xC")]
+    fn validate_rejects_gaps() {
+        Schema::validate(&sample_fields());
+    }
+
+    #[test]
+    fn every_table_layout_is_whole() {
+        use crate::fields;
+
+        for table in [
+            &fields::SKILLS as &[Field], &fields::SKILL_DESC, &fields::ITEMS, &fields::ITEM_STAT_COST, &fields::PROPERTIES,
+            &fields::UNIQUE_ITEMS, &fields::SET_ITEMS, &fields::SETS, &fields::ITEM_TYPES, &fields::RUNES,
+        ] {
+            Schema::validate(table);
+        }
     }
 }
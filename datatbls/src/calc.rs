@@ -0,0 +1,846 @@
+#![allow(unused)]
+
+//! calc expressions of the .txt files are compiled into a separate code
+//! buffer (skillscode.bin, skilldesccode.bin, itemscode.bin) and the bins
+//! only keep offsets into it. The code is a small stack machine:
+//!
+//! ```text
+//! 00                      end
+//! 01 u8 u16 u8            reference u8 of `REF_FUNCS`: id u16, param u8, e.g. skill(36.blvl)
+//! 02                      cond ? a : b        (pops b, a, cond)
+//! 03 i8 / 04 i16 / 05 i32 push constant
+//! 06 .. 10                < > <= >= == != + - * / ^  (pops b, a)
+//! 11                      negate
+//! 12 u8                   value u8 of the calc table, e.g. ln12
+//! 13 u8                   builtin u8 of `BUILTINS`: min(a,b), max(a,b), rand(a,b)
+//! ```
+//!
+//! The value codes are the rows of skillcalc.txt and misccalc.txt in file
+//! order; references and their params are the lookups the skills.txt formulas
+//! use. The operator and call opcodes follow the game's calc compiler (Fog.dll)
+//! and are only checked against hand assembled code, see the tests.
+
+use std::path::Path;
+use std::io::Cursor;
+use ml::io::{File, ReadExt, LittleEndian};
+use anyhow::{Result, bail};
+
+type LE = LittleEndian;
+
+pub enum CalcArgs {
+    /// a value of the context, written without parens: `ln12`
+    None,
+    /// a function with a fixed number of arguments: `min(a,b)`
+    Fixed(u8),
+    /// an id qualified lookup: `skill(36.blvl)`
+    Ref(&'static [&'static str]),
+}
+
+pub struct CalcFunc {
+    pub name    : &'static str,
+    pub args    : CalcArgs,
+}
+
+impl CalcFunc {
+    const fn value(name: &'static str) -> CalcFunc {
+        CalcFunc{name, args: CalcArgs::None}
+    }
+
+    const fn func(name: &'static str, args: u8) -> CalcFunc {
+        CalcFunc{name, args: CalcArgs::Fixed(args)}
+    }
+
+    const fn refs(name: &'static str, params: &'static [&'static str]) -> CalcFunc {
+        CalcFunc{name, args: CalcArgs::Ref(params)}
+    }
+}
+
+pub static SKILL_REF_PARAMS: &[&str] = &[
+    "ln12", "ln34", "ln56", "ln78",
+    "par1", "par2", "par3", "par4", "par5", "par6", "par7", "par8",
+    "dm12", "dm34", "dm56", "dm78",
+    "clc1", "clc2", "clc3", "clc4",
+    "edmn", "edmx", "edln", "usmc", "toht",
+    "lvl", "blvl",
+];
+
+pub static MISSILE_REF_PARAMS: &[&str] = &[
+    "ln12", "ln34", "ln56", "ln78",
+    "par1", "par2", "par3", "par4", "par5", "par6", "par7", "par8",
+    "dm12", "dm34", "dm56", "dm78",
+    "clc1", "clc2", "clc3", "clc4",
+    "edmn", "edmx", "edln",
+];
+
+pub static STAT_REF_PARAMS: &[&str] = &["accr", "base", "mod"];
+
+/// skillcalc.txt, used by skills.bin and skilldesc.bin
+pub static SKILL_CALC: &[CalcFunc] = &[
+    CalcFunc::value("ln12"),
+    CalcFunc::value("ln34"),
+    CalcFunc::value("ln56"),
+    CalcFunc::value("ln78"),
+    CalcFunc::value("par1"),
+    CalcFunc::value("par2"),
+    CalcFunc::value("par3"),
+    CalcFunc::value("par4"),
+    CalcFunc::value("par5"),
+    CalcFunc::value("par6"),
+    CalcFunc::value("par7"),
+    CalcFunc::value("par8"),
+    CalcFunc::value("dm12"),
+    CalcFunc::value("dm34"),
+    CalcFunc::value("dm56"),
+    CalcFunc::value("dm78"),
+    CalcFunc::value("clc1"),
+    CalcFunc::value("clc2"),
+    CalcFunc::value("clc3"),
+    CalcFunc::value("clc4"),
+    CalcFunc::value("edmn"),
+    CalcFunc::value("edmx"),
+    CalcFunc::value("edln"),
+    CalcFunc::value("usmc"),
+    CalcFunc::value("toht"),
+    CalcFunc::value("lvl"),
+    CalcFunc::value("blvl"),
+];
+
+/// misccalc.txt, used by items.bin and properties
+pub static MISC_CALC: &[CalcFunc] = &[
+    CalcFunc::value("lvl"),
+];
+
+/// functions the calc compiler knows itself, the same for every calc table
+pub static BUILTINS: &[CalcFunc] = &[
+    CalcFunc::func("min", 2),
+    CalcFunc::func("max", 2),
+    CalcFunc::func("rand", 2),
+];
+
+/// id qualified lookups, the same for every calc table
+pub static REF_FUNCS: &[CalcFunc] = &[
+    CalcFunc::refs("skill", SKILL_REF_PARAMS),
+    CalcFunc::refs("sklvl", SKILL_REF_PARAMS),
+    CalcFunc::refs("miss", MISSILE_REF_PARAMS),
+    CalcFunc::refs("stat", STAT_REF_PARAMS),
+];
+
+pub fn calc_table(name: &str) -> &'static [CalcFunc] {
+    match name {
+        "items" => MISC_CALC,
+        _ => SKILL_CALC,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl BinOp {
+    const ALL: [BinOp; 11] = [
+        BinOp::Lt, BinOp::Gt, BinOp::Le, BinOp::Ge, BinOp::Eq,
+        BinOp::Ne, BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div, BinOp::Pow,
+    ];
+
+    fn opcode(self) -> u8 {
+        0x06 + Self::ALL.iter().position(|op| *op == self).unwrap() as u8
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::Le => "<=",
+            BinOp::Ge => ">=",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Pow => "^",
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::Eq | BinOp::Ne => 1,
+            BinOp::Add | BinOp::Sub => 2,
+            BinOp::Mul | BinOp::Div => 3,
+            BinOp::Pow => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Const(i32),
+    Value(&'static str),
+    Call(&'static str, Vec<Expr>),
+    Ref(&'static str, u16, &'static str),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// supplies the values an expression refers to
+pub trait CalcContext {
+    /// a value without parens such as `ln12` or `blvl`
+    fn value(&self, name: &str) -> i64;
+    /// an id qualified lookup such as `skill(36.blvl)`
+    fn reference(&self, func: &str, id: u16, param: &str) -> i64;
+}
+
+const OP_END    : u8 = 0x00;
+const OP_REF    : u8 = 0x01;
+const OP_COND   : u8 = 0x02;
+const OP_I8     : u8 = 0x03;
+const OP_I16    : u8 = 0x04;
+const OP_I32    : u8 = 0x05;
+const OP_NEG    : u8 = 0x11;
+const OP_VALUE  : u8 = 0x12;
+const OP_BUILTIN: u8 = 0x13;
+
+const PREC_COND : u8 = 0;
+const PREC_UNARY: u8 = 5;
+const PREC_ATOM : u8 = 6;
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Cond(..) => PREC_COND,
+            Expr::Binary(op, ..) => op.precedence(),
+            Expr::Neg(_) => PREC_UNARY,
+            Expr::Const(v) if *v < 0 => PREC_UNARY,
+            _ => PREC_ATOM,
+        }
+    }
+
+    fn fmt_prec(&self, f: &mut std::fmt::Formatter<'_>, prec: u8) -> std::fmt::Result {
+        if self.precedence() < prec {
+            write!(f, "(")?;
+            self.fmt_prec(f, 0)?;
+            return write!(f, ")");
+        }
+
+        match self {
+            Expr::Const(v) => write!(f, "{v}"),
+            Expr::Value(name) => write!(f, "{name}"),
+            Expr::Call(name, args) => {
+                write!(f, "{name}(")?;
+                for (i, a) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    a.fmt_prec(f, 0)?;
+                }
+                write!(f, ")")
+            },
+            Expr::Ref(name, id, param) => write!(f, "{name}({id}.{param})"),
+            Expr::Neg(e) => {
+                write!(f, "-")?;
+                e.fmt_prec(f, PREC_UNARY)
+            },
+            Expr::Binary(op, a, b) => {
+                // operators are left associative, the right side needs parens on equal precedence
+                a.fmt_prec(f, op.precedence())?;
+                write!(f, "{}", op.symbol())?;
+                match **b {
+                    // keep `a-(-5)` from reading as `a--5`
+                    Expr::Neg(_) | Expr::Const(i32::MIN..=-1) => b.fmt_prec(f, PREC_ATOM),
+                    _ => b.fmt_prec(f, op.precedence() + 1),
+                }
+            },
+            Expr::Cond(c, a, b) => {
+                c.fmt_prec(f, PREC_COND + 1)?;
+                write!(f, "?")?;
+                a.fmt_prec(f, PREC_COND + 1)?;
+                write!(f, ":")?;
+                b.fmt_prec(f, PREC_COND)
+            },
+        }
+    }
+
+    /// evaluates like the game does, in wrapping 32-bit arithmetic: comparisons
+    /// are 0/1, division by zero and negative powers are 0 and `rand(a,b)` is
+    /// taken as the middle of its range.
+    pub fn eval(&self, ctx: &dyn CalcContext) -> i64 {
+        // operands are in i32 range, so only `^` can overflow an i64 before
+        // the result is wrapped
+        let v = match self {
+            Expr::Const(v) => *v as i64,
+            Expr::Value(name) => ctx.value(name),
+            Expr::Call(name, args) => {
+                let args: Vec<i64> = args.iter().map(|a| a.eval(ctx)).collect();
+                match *name {
+                    "min" => args[0].min(args[1]),
+                    "max" => args[0].max(args[1]),
+                    "rand" => (args[0] + args[1]) / 2,
+                    _ => 0,
+                }
+            },
+            Expr::Ref(name, id, param) => ctx.reference(name, *id, param),
+            Expr::Neg(e) => -e.eval(ctx),
+            Expr::Binary(op, a, b) => {
+                let a = a.eval(ctx);
+                let b = b.eval(ctx);
+                match op {
+                    BinOp::Lt => (a < b) as i64,
+                    BinOp::Gt => (a > b) as i64,
+                    BinOp::Le => (a <= b) as i64,
+                    BinOp::Ge => (a >= b) as i64,
+                    BinOp::Eq => (a == b) as i64,
+                    BinOp::Ne => (a != b) as i64,
+                    BinOp::Add => a + b,
+                    BinOp::Sub => a - b,
+                    BinOp::Mul => a * b,
+                    BinOp::Div => if b == 0 { 0 } else { a / b },
+                    BinOp::Pow => if b < 0 { 0 } else { a.wrapping_pow(b as u32) },
+                }
+            },
+            Expr::Cond(c, a, b) => if c.eval(ctx) != 0 { a.eval(ctx) } else { b.eval(ctx) },
+        };
+
+        v as i32 as i64
+    }
+
+    /// decodes the expression starting at the beginning of `code`
+    pub fn decode(code: &[u8], funcs: &'static [CalcFunc]) -> Result<Expr> {
+        let mut r = CodeReader{code, pos: 0};
+        let mut stack = Vec::<Expr>::new();
+
+        fn pop(stack: &mut Vec<Expr>) -> Result<Box<Expr>> {
+            match stack.pop() {
+                Some(e) => Ok(Box::new(e)),
+                None => bail!("calc stack underflow"),
+            }
+        }
+
+        loop {
+            if r.pos >= code.len() {
+                bail!("calc code is not terminated");
+            }
+
+            let op = r.u8()?;
+
+            match op {
+                OP_END => break,
+                OP_COND => {
+                    let b = pop(&mut stack)?;
+                    let a = pop(&mut stack)?;
+                    let c = pop(&mut stack)?;
+                    stack.push(Expr::Cond(c, a, b));
+                },
+                OP_I8 => stack.push(Expr::Const(i8::from_le_bytes(r.bytes()?) as i32)),
+                OP_I16 => stack.push(Expr::Const(i16::from_le_bytes(r.bytes()?) as i32)),
+                OP_I32 => stack.push(Expr::Const(i32::from_le_bytes(r.bytes()?))),
+                0x06..=0x10 => {
+                    let b = pop(&mut stack)?;
+                    let a = pop(&mut stack)?;
+                    stack.push(Expr::Binary(BinOp::ALL[(op - 0x06) as usize], a, b));
+                },
+                OP_NEG => {
+                    let e = pop(&mut stack)?;
+                    stack.push(Expr::Neg(e));
+                },
+                OP_VALUE => {
+                    let index = r.u8()? as usize;
+                    match funcs.get(index) {
+                        Some(func) => stack.push(Expr::Value(func.name)),
+                        None => bail!("unknown calc value {index}"),
+                    }
+                },
+                OP_BUILTIN => {
+                    let index = r.u8()? as usize;
+                    let (name, n) = match BUILTINS.get(index) {
+                        Some(CalcFunc{name, args: CalcArgs::Fixed(n)}) => (*name, *n as usize),
+                        _ => bail!("unknown calc builtin {index}"),
+                    };
+
+                    if stack.len() < n {
+                        bail!("calc stack underflow in {name}");
+                    }
+                    let args = stack.split_off(stack.len() - n);
+                    stack.push(Expr::Call(name, args));
+                },
+                OP_REF => {
+                    let index = r.u8()? as usize;
+                    let (name, params) = match REF_FUNCS.get(index) {
+                        Some(CalcFunc{name, args: CalcArgs::Ref(params)}) => (*name, *params),
+                        _ => bail!("unknown calc reference {index}"),
+                    };
+
+                    let id = u16::from_le_bytes(r.bytes()?);
+                    let param = r.u8()? as usize;
+                    match params.get(param) {
+                        Some(param) => stack.push(Expr::Ref(name, id, param)),
+                        None => bail!("unknown param {param} of {name}"),
+                    }
+                },
+                _ => bail!("unknown calc opcode 0x{op:02X} at 0x{:X}", r.pos - 1),
+            }
+        }
+
+        if stack.len() != 1 {
+            bail!("calc leaves {} values on the stack", stack.len());
+        }
+
+        Ok(stack.pop().unwrap())
+    }
+
+    pub fn encode(&self, funcs: &[CalcFunc]) -> Result<Vec<u8>> {
+        let mut code = Vec::new();
+        self.emit(&mut code, funcs)?;
+        code.push(OP_END);
+        Ok(code)
+    }
+
+    fn emit(&self, code: &mut Vec<u8>, funcs: &[CalcFunc]) -> Result<()> {
+        let find = |table: &[CalcFunc], name: &str| match table.iter().position(|f| f.name == name) {
+            Some(i) => Ok(i as u8),
+            None => bail!("unknown calc function {name}"),
+        };
+
+        match self {
+            Expr::Const(v) => {
+                if let Ok(v) = i8::try_from(*v) {
+                    code.push(OP_I8);
+                    code.extend(v.to_le_bytes());
+                } else if let Ok(v) = i16::try_from(*v) {
+                    code.push(OP_I16);
+                    code.extend(v.to_le_bytes());
+                } else {
+                    code.push(OP_I32);
+                    code.extend(v.to_le_bytes());
+                }
+            },
+            Expr::Value(name) => {
+                code.push(OP_VALUE);
+                code.push(find(funcs, name)?);
+            },
+            Expr::Call(name, args) => {
+                for a in args.iter() {
+                    a.emit(code, funcs)?;
+                }
+                code.push(OP_BUILTIN);
+                code.push(find(BUILTINS, name)?);
+            },
+            Expr::Ref(name, id, param) => {
+                let index = find(REF_FUNCS, name)?;
+                let position = match &REF_FUNCS[index as usize].args {
+                    CalcArgs::Ref(params) => params.iter().position(|p| p == param),
+                    _ => None,
+                };
+                let position = match position {
+                    Some(p) => p as u8,
+                    None => bail!("{name} has no param {param}"),
+                };
+
+                code.push(OP_REF);
+                code.push(index);
+                code.extend(id.to_le_bytes());
+                code.push(position);
+            },
+            Expr::Neg(e) => {
+                e.emit(code, funcs)?;
+                code.push(OP_NEG);
+            },
+            Expr::Binary(op, a, b) => {
+                a.emit(code, funcs)?;
+                b.emit(code, funcs)?;
+                code.push(op.opcode());
+            },
+            Expr::Cond(c, a, b) => {
+                c.emit(code, funcs)?;
+                a.emit(code, funcs)?;
+                b.emit(code, funcs)?;
+                code.push(OP_COND);
+            },
+        }
+
+        Ok(())
+    }
+
+    /// parses the .txt form of a calc, e.g. `ln12*blvl+par3` or `min(skill(36.blvl),20)`
+    pub fn parse(s: &str, funcs: &'static [CalcFunc]) -> Result<Expr> {
+        let mut p = Parser{s: s.as_bytes(), pos: 0, funcs};
+        let e = p.cond()?;
+        p.skip_ws();
+
+        if p.pos != p.s.len() {
+            bail!("unexpected '{}' in calc `{s}`", &s[p.pos..]);
+        }
+
+        Ok(e)
+    }
+}
+
+/// reads the operands of `Expr::decode`, failing at the end of the code
+/// instead of panicking
+struct CodeReader<'a> {
+    code    : &'a [u8],
+    pos     : usize,
+}
+
+impl CodeReader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        match self.code.get(self.pos..self.pos + N) {
+            Some(b) => {
+                self.pos += N;
+                Ok(b.try_into().unwrap())
+            },
+            None => bail!("calc code is truncated"),
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_prec(f, 0)
+    }
+}
+
+struct Parser<'a> {
+    s       : &'a [u8],
+    pos     : usize,
+    funcs   : &'static [CalcFunc],
+}
+
+impl<'a> Parser<'a> {
+    fn skip_ws(&mut self) {
+        while self.pos < self.s.len() && self.s[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, tok: &str) -> bool {
+        self.skip_ws();
+        if self.s[self.pos..].starts_with(tok.as_bytes()) {
+            self.pos += tok.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, tok: &str) -> Result<()> {
+        if !self.eat(tok) {
+            bail!("expect '{tok}' at {}", self.pos);
+        }
+        Ok(())
+    }
+
+    fn cond(&mut self) -> Result<Expr> {
+        let c = self.binary(1)?;
+
+        if self.eat("?") {
+            let a = self.binary(1)?;
+            self.expect(":")?;
+            let b = self.cond()?;
+            return Ok(Expr::Cond(Box::new(c), Box::new(a), Box::new(b)));
+        }
+
+        Ok(c)
+    }
+
+    fn binary(&mut self, prec: u8) -> Result<Expr> {
+        if prec > 4 {
+            return self.unary();
+        }
+
+        let mut a = self.binary(prec + 1)?;
+
+        'outer: loop {
+            // longer symbols first so `<=` is not read as `<`
+            for op in [BinOp::Le, BinOp::Ge, BinOp::Eq, BinOp::Ne, BinOp::Lt, BinOp::Gt, BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div, BinOp::Pow] {
+                if op.precedence() == prec && self.eat(op.symbol()) {
+                    let b = self.binary(prec + 1)?;
+                    a = Expr::Binary(op, Box::new(a), Box::new(b));
+                    continue 'outer;
+                }
+            }
+            break;
+        }
+
+        Ok(a)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("-") {
+            return match self.unary()? {
+                Expr::Const(v) => Ok(Expr::Const(-v)),
+                e => Ok(Expr::Neg(Box::new(e))),
+            };
+        }
+
+        if self.eat("(") {
+            let e = self.cond()?;
+            self.expect(")")?;
+            return Ok(e);
+        }
+
+        self.skip_ws();
+        let start = self.pos;
+
+        if self.pos < self.s.len() && self.s[self.pos].is_ascii_digit() {
+            while self.pos < self.s.len() && self.s[self.pos].is_ascii_digit() {
+                self.pos += 1;
+            }
+            let v = std::str::from_utf8(&self.s[start..self.pos])?.parse::<i32>()?;
+            return Ok(Expr::Const(v));
+        }
+
+        while self.pos < self.s.len() && (self.s[self.pos].is_ascii_alphanumeric() || self.s[self.pos] == b'_') {
+            self.pos += 1;
+        }
+
+        let name = std::str::from_utf8(&self.s[start..self.pos])?;
+        let func = match self.funcs.iter().chain(BUILTINS).chain(REF_FUNCS).find(|f| f.name.eq_ignore_ascii_case(name)) {
+            Some(func) => func,
+            None => bail!("unknown calc function '{name}' at {start}"),
+        };
+
+        match func.args {
+            CalcArgs::None => Ok(Expr::Value(func.name)),
+            CalcArgs::Fixed(n) => {
+                self.expect("(")?;
+                let mut args = Vec::new();
+                for i in 0..n {
+                    if i > 0 {
+                        self.expect(",")?;
+                    }
+                    args.push(self.cond()?);
+                }
+                self.expect(")")?;
+                Ok(Expr::Call(func.name, args))
+            },
+            CalcArgs::Ref(params) => {
+                self.expect("(")?;
+                let id = match self.unary()? {
+                    Expr::Const(id) => id as u16,
+                    e => bail!("{} expects a numeric id, got {e}", func.name),
+                };
+                self.expect(".")?;
+                self.skip_ws();
+                let start = self.pos;
+                while self.pos < self.s.len() && self.s[self.pos].is_ascii_alphanumeric() {
+                    self.pos += 1;
+                }
+                let param = std::str::from_utf8(&self.s[start..self.pos])?;
+                let param = match params.iter().find(|p| p.eq_ignore_ascii_case(param)) {
+                    Some(p) => *p,
+                    None => bail!("{} has no param {param}", func.name),
+                };
+                self.expect(")")?;
+                Ok(Expr::Ref(func.name, id, param))
+            },
+        }
+    }
+}
+
+/// a calc code buffer such as skillscode.bin
+pub struct CalcCode {
+    code    : Vec<u8>,
+    funcs   : &'static [CalcFunc],
+}
+
+impl CalcCode {
+    pub fn new(funcs: &'static [CalcFunc]) -> Self {
+        Self {
+            code: Vec::new(),
+            funcs,
+        }
+    }
+
+    pub fn open<T: AsRef<Path>>(path: T, funcs: &'static [CalcFunc]) -> Result<CalcCode> {
+        let mut file = File::open(path)?;
+        let code = file.read_bytes(file.size()? as usize)?;
+
+        Ok(Self::from_bytes(code, funcs))
+    }
+
+    pub fn from_bytes(code: Vec<u8>, funcs: &'static [CalcFunc]) -> CalcCode {
+        Self {
+            code,
+            funcs,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// decodes the calc at `offset`, `None` for the empty calc (-1)
+    pub fn decode(&self, offset: u32) -> Result<Option<Expr>> {
+        if offset == u32::MAX {
+            return Ok(None);
+        }
+
+        match self.code.get(offset as usize..) {
+            Some(code) => Ok(Some(Expr::decode(code, self.funcs)?)),
+            None => bail!("calc offset 0x{offset:X} is out of the code buffer"),
+        }
+    }
+
+    /// compiles `expr` to the end of the buffer and returns its offset
+    pub fn encode(&mut self, expr: &str) -> Result<u32> {
+        let code = Expr::parse(expr, self.funcs)?.encode(self.funcs)?;
+        let offset = self.code.len() as u32;
+        self.code.extend(code);
+        Ok(offset)
+    }
+
+    pub fn save<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        std::fs::write(path, &self.code)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Ctx;
+
+    impl CalcContext for Ctx {
+        fn value(&self, name: &str) -> i64 {
+            match name {
+                "ln12" => 10,
+                "par1" => 3,
+                "lvl" => 7,
+                "blvl" => 5,
+                _ => 0,
+            }
+        }
+
+        fn reference(&self, func: &str, id: u16, param: &str) -> i64 {
+            match (func, id, param) {
+                ("skill", 36, "blvl") => 25,
+                _ => 0,
+            }
+        }
+    }
+
+    /// hand assembled skillscode.bin entries and the text they decode to
+    const CODE: &[(&[u8], &str)] = &[
+        (&[0x12, 0x00, 0x12, 0x06, 0x12, 0x1A, 0x0E, 0x0C, 0x00], "ln12+par3*blvl"),
+        (&[0x01, 0x00, 0x24, 0x00, 0x1A, 0x03, 0x14, 0x13, 0x00, 0x00], "min(skill(36.blvl),20)"),
+        (&[0x12, 0x19, 0x03, 0x05, 0x07, 0x12, 0x00, 0x12, 0x04, 0x11, 0x02, 0x00], "lvl>5?ln12:-par1"),
+        (&[0x12, 0x00, 0x03, 0x02, 0x10, 0x00], "ln12^2"),
+        (&[0x04, 0xE8, 0x03, 0x12, 0x1A, 0x0D, 0x03, 0x02, 0x0F, 0x00], "(1000-blvl)/2"),
+        (&[0x05, 0xA0, 0x86, 0x01, 0x00, 0x00], "100000"),
+        (&[0x01, 0x03, 0x0A, 0x00, 0x02, 0x00], "stat(10.mod)"),
+    ];
+
+    #[test]
+    fn decodes_skill_code() {
+        for (code, text) in CODE {
+            assert_eq!(Expr::decode(code, SKILL_CALC).unwrap().to_string(), *text);
+        }
+    }
+
+    #[test]
+    fn encodes_to_the_same_code() {
+        for (code, text) in CODE {
+            assert_eq!(Expr::parse(text, SKILL_CALC).unwrap().encode(SKILL_CALC).unwrap(), *code, "{text}");
+        }
+    }
+
+    #[test]
+    fn parse_encode_decode_round_trips() {
+        let exprs = [
+            "ln12*blvl+par3",
+            "ln12-(par1-par2)",
+            "ln12-(-5)",
+            "-(ln12+par1)",
+            "max(min(lvl,20),rand(1,par2))",
+            "sklvl(59.lvl)*miss(12.edmx)",
+            "lvl>=10?(blvl<3?1:2):par1==par2",
+            "ln12^(par1-1)",
+            "-40000",
+            "70000*ln12",
+        ];
+
+        for text in exprs.iter() {
+            let expr = Expr::parse(text, SKILL_CALC).unwrap();
+            let decoded = Expr::decode(&expr.encode(SKILL_CALC).unwrap(), SKILL_CALC).unwrap();
+
+            assert_eq!(decoded, expr, "{text}");
+            assert_eq!(decoded.to_string(), *text);
+        }
+    }
+
+    #[test]
+    fn builtins_and_references_are_not_table_values() {
+        assert!(SKILL_CALC.iter().all(|f| matches!(f.args, CalcArgs::None)));
+        assert!(MISC_CALC.iter().all(|f| matches!(f.args, CalcArgs::None)));
+
+        // the same builtin index in either table
+        let expr = Expr::parse("max(lvl,1)", MISC_CALC).unwrap();
+        assert_eq!(expr.encode(MISC_CALC).unwrap(), [0x12, 0x00, 0x03, 0x01, 0x13, 0x01, 0x00]);
+        assert_eq!(expr.encode(SKILL_CALC).unwrap(), [0x12, 0x19, 0x03, 0x01, 0x13, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn evaluates_like_the_game() {
+        let eval = |text: &str| Expr::parse(text, SKILL_CALC).unwrap().eval(&Ctx);
+
+        assert_eq!(eval("ln12+par1*blvl"), 25);
+        assert_eq!(eval("min(skill(36.blvl),20)"), 20);
+        assert_eq!(eval("lvl>5?ln12:-par1"), 10);
+        assert_eq!(eval("ln12/0"), 0);
+        assert_eq!(eval("par1^2"), 9);
+        assert_eq!(eval("par1^(0-1)"), 0);
+        assert_eq!(eval("rand(2,8)"), 5);
+        assert_eq!(eval("ln12<par1"), 0);
+
+        // 32-bit wrapping like the game
+        assert_eq!(eval("2147483647+1"), i32::MIN as i64);
+        assert_eq!(eval("2147483647*2147483647*2147483647"), 2147483647);
+        assert_eq!(eval("-(0-2147483647-1)"), i32::MIN as i64);
+        assert_eq!(eval("(0-2147483647-1)/(0-1)"), i32::MIN as i64);
+        assert_eq!(eval("2^40"), 0);
+    }
+
+    #[test]
+    fn reports_bad_code() {
+        let err = |code: &[u8]| Expr::decode(code, SKILL_CALC).unwrap_err().to_string();
+
+        assert_eq!(err(&[0x03, 0x01]), "calc code is not terminated");
+        assert_eq!(err(&[0x0C, 0x00]), "calc stack underflow");
+        assert_eq!(err(&[0x03, 0x01, 0x03, 0x02, 0x00]), "calc leaves 2 values on the stack");
+        assert_eq!(err(&[0x14, 0x00]), "unknown calc opcode 0x14 at 0x0");
+        assert_eq!(err(&[0x12, 0xFF, 0x00]), "unknown calc value 255");
+        assert_eq!(err(&[0x01, 0x00, 0x01, 0x00, 0x63, 0x00]), "unknown param 99 of skill");
+
+        for code in [&[0x03][..], &[0x04, 0x01], &[0x05, 0x01, 0x02, 0x03], &[0x12], &[0x13], &[0x01, 0x00, 0x01], &[0x01, 0x00, 0x01, 0x00]] {
+            assert_eq!(err(code), "calc code is truncated", "{code:02X?}");
+        }
+
+        let calc = CalcCode::from_bytes(vec![0x03, 0x01, 0x00, 0x05, 0xFF], SKILL_CALC);
+        assert_eq!(calc.decode(3).unwrap_err().to_string(), "calc code is truncated");
+
+        assert!(Expr::parse("ln12+", SKILL_CALC).is_err());
+        assert!(Expr::parse("nope(1)", SKILL_CALC).is_err());
+        assert!(Expr::parse("skill(36.nope)", SKILL_CALC).is_err());
+    }
+}
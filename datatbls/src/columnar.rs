@@ -0,0 +1,199 @@
+#![allow(unused)]
+
+//! converts a `BinRecord` into an Arrow `RecordBatch` and writes it as Parquet,
+//! built with the `parquet` feature.
+//!
+//! Scalars map onto the integer type of the same width, arrays onto fixed size
+//! lists, `ItemCode` and strings onto utf8. `StringId` stays a uint16 column with
+//! the resolved text in a `name_text` utf8 column next to it.
+
+use std::sync::Arc;
+use anyhow::Result;
+use arrow_array::{
+    ArrayRef, RecordBatch, FixedSizeListArray, StringArray,
+    Int8Array, Int16Array, Int32Array, UInt8Array, UInt16Array, UInt32Array,
+};
+use arrow_schema::{DataType, Field as ArrowField, Schema};
+use parquet::arrow::ArrowWriter;
+
+use crate::bin::*;
+use crate::itemtbl::item_code_str;
+use crate::datatbls_mgr::DataTblsManager;
+
+/// the arrow type a field of type `ty` is stored as
+pub fn arrow_type(ty: &FieldType) -> DataType {
+    let primitive = |p: &Primitive| match p {
+        Primitive::Int8 => DataType::Int8,
+        Primitive::Int16 => DataType::Int16,
+        Primitive::Int32 => DataType::Int32,
+        Primitive::UInt8 => DataType::UInt8,
+        Primitive::UInt16 | Primitive::StringId => DataType::UInt16,
+        Primitive::UInt32 => DataType::UInt32,
+        Primitive::ItemCode => DataType::Utf8,
+    };
+
+    match ty {
+        FieldType::Primitive(p) => primitive(p),
+        // array elements are plain numbers, see `FieldType::decode`
+        FieldType::Array(p, len) => {
+            let item = match p {
+                Primitive::ItemCode => DataType::UInt32,
+                p => primitive(p),
+            };
+            DataType::FixedSizeList(Arc::new(ArrowField::new("item", item, false)), *len as i32)
+        },
+        FieldType::String(_) => DataType::Utf8,
+    }
+}
+
+/// the arrow columns of field `f` over every record of `rec`, typed by the
+/// schema so an empty table still has them
+fn columns(tbls: &DataTblsManager, rec: &BinRecord, idx: usize, f: &Field) -> Vec<(ArrowField, ArrayRef)> {
+    let values: Vec<&Value> = rec.iter().map(|r| r.at(idx).value).collect();
+    let column = |a: ArrayRef| vec![(ArrowField::new(&f.name, arrow_type(&f.ty), false), a)];
+
+    macro_rules! scalar {
+        ($array:ty, $get:ident) => {
+            column(Arc::new(<$array>::from_iter_values(values.iter().map(|v| v.$get()))))
+        };
+    }
+
+    macro_rules! list {
+        ($array:ty, $variant:ident, $len:expr) => {{
+            let flat = values.iter().flat_map(|v| match v {
+                Value::$variant(a) => a.iter().copied(),
+                v => panic!("type is {v:?}"),
+            });
+            let item = match arrow_type(&f.ty) {
+                DataType::FixedSizeList(item, _) => item,
+                _ => unreachable!(),
+            };
+            column(Arc::new(FixedSizeListArray::new(item, $len as i32, Arc::new(<$array>::from_iter_values(flat)), None)))
+        }};
+    }
+
+    match &f.ty {
+        FieldType::Primitive(Primitive::Int8) => scalar!(Int8Array, i8),
+        FieldType::Primitive(Primitive::Int16) => scalar!(Int16Array, i16),
+        FieldType::Primitive(Primitive::Int32) => scalar!(Int32Array, i32),
+        FieldType::Primitive(Primitive::UInt8) => scalar!(UInt8Array, u8),
+        FieldType::Primitive(Primitive::UInt16) => scalar!(UInt16Array, u16),
+        FieldType::Primitive(Primitive::UInt32) => scalar!(UInt32Array, u32),
+        FieldType::Primitive(Primitive::StringId) => {
+            let ids: Vec<u16> = values.iter().map(|v| v.str_id()).collect();
+            let text = StringArray::from_iter(ids.iter().map(|id| tbls.get_string_by_index(*id)));
+
+            let mut columns = column(Arc::new(UInt16Array::from(ids)));
+            columns.push((ArrowField::new(format!("{}_text", f.name), DataType::Utf8, true), Arc::new(text)));
+            columns
+        },
+        FieldType::Primitive(Primitive::ItemCode) => column(Arc::new(StringArray::from_iter_values(values.iter().map(|v| item_code_str(v.item_code()))))),
+        FieldType::Array(Primitive::Int8, len) => list!(Int8Array, I8Array, *len),
+        FieldType::Array(Primitive::Int16, len) => list!(Int16Array, I16Array, *len),
+        FieldType::Array(Primitive::Int32, len) => list!(Int32Array, I32Array, *len),
+        FieldType::Array(Primitive::UInt8, len) => list!(UInt8Array, U8Array, *len),
+        FieldType::Array(Primitive::UInt16 | Primitive::StringId, len) => list!(UInt16Array, U16Array, *len),
+        FieldType::Array(Primitive::UInt32 | Primitive::ItemCode, len) => list!(UInt32Array, U32Array, *len),
+        FieldType::String(_) => column(Arc::new(StringArray::from_iter_values(values.iter().map(|v| v.str())))),
+    }
+}
+
+/// one column per field of the table's schema, padding is left out
+pub fn to_record_batch(tbls: &DataTblsManager, rec: &BinRecord) -> Result<RecordBatch> {
+    let (fields, arrays): (Vec<ArrowField>, Vec<ArrayRef>) = rec.schema().fields().iter()
+        .enumerate()
+        .filter(|(_, f)| !f.name.starts_with("__pad"))
+        .flat_map(|(idx, f)| columns(tbls, rec, idx, f))
+        .unzip();
+
+    // a table that was never loaded has no fields at all
+    if fields.is_empty() {
+        return Ok(RecordBatch::new_empty(Arc::new(Schema::empty())));
+    }
+
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+}
+
+pub fn write_parquet(tbls: &DataTblsManager, rec: &BinRecord, file_name: &str) -> Result<()> {
+    let batch = to_record_batch(tbls, rec)?;
+
+    let file = std::fs::File::create(file_name)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use crate::fields;
+    use crate::bin::tests::zeroed;
+    use crate::stringtbl::tests::manager;
+
+    #[test]
+    fn maps_fields_onto_arrow_columns() {
+        let mut tbls = DataTblsManager::new();
+        tbls.strtbl = manager(&[("dummy", ""), ("skillname36", "Fire Bolt")], &[], &[]);
+
+        let mut rec = zeroed(&fields::SKILL_DESC, 2);
+        rec.get_mut(1).unwrap().set("str_name", Value::StringId(1));
+        rec.get_mut(1).unwrap().set("desc_line", Value::U8Array((1..=17).collect()));
+
+        let batch = to_record_batch(&tbls, &rec).unwrap();
+        let schema = batch.schema();
+        let ty = |name: &str| schema.field_with_name(name).unwrap().data_type().clone();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(ty("skill_desc"), DataType::UInt16);
+        assert_eq!(ty("skill_page"), DataType::UInt8);
+        assert_eq!(ty("str_name"), DataType::UInt16);
+        assert_eq!(ty("str_name_text"), DataType::Utf8);
+        assert_eq!(ty("dam_calc_1"), DataType::UInt32);
+        assert!(schema.field_with_name("__pad_16").is_err());
+
+        match ty("desc_line") {
+            DataType::FixedSizeList(item, len) => assert_eq!((item.data_type(), len), (&DataType::UInt8, 17)),
+            t => panic!("desc_line is {t:?}"),
+        }
+
+        let lines = batch.column_by_name("desc_line").unwrap().as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+        let line = lines.value(1);
+        let line = line.as_any().downcast_ref::<UInt8Array>().unwrap();
+        assert_eq!(line.values().to_vec(), (1..=17).collect::<Vec<u8>>());
+
+        let text = batch.column_by_name("str_name_text").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(text.value(1), "Fire Bolt");
+    }
+
+    #[test]
+    fn item_codes_are_utf8() {
+        assert_eq!(arrow_type(&FieldType::Primitive(Primitive::ItemCode)), DataType::Utf8);
+        assert_eq!(arrow_type(&FieldType::String(32)), DataType::Utf8);
+    }
+
+    #[test]
+    fn empty_tables_keep_their_columns() {
+        let tbls = DataTblsManager::new();
+        let rec = zeroed(&fields::SKILL_DESC, 0);
+
+        let batch = to_record_batch(&tbls, &rec).unwrap();
+        let schema = batch.schema();
+
+        assert_eq!(batch.num_rows(), 0);
+        assert_eq!(schema.field_with_name("str_name").unwrap().data_type(), &DataType::UInt16);
+        assert_eq!(schema.field_with_name("str_name_text").unwrap().data_type(), &DataType::Utf8);
+        assert!(matches!(schema.field_with_name("desc_line").unwrap().data_type(), DataType::FixedSizeList(_, 17)));
+
+        let path = std::env::temp_dir().join(format!("datatbls-empty-{}.parquet", std::process::id()));
+        let written = write_parquet(&tbls, &rec, path.to_str().unwrap());
+        let size = std::fs::metadata(&path).map(|m| m.len());
+        std::fs::remove_file(&path).unwrap();
+        written.unwrap();
+        assert!(size.unwrap() > 0);
+
+        assert_eq!(to_record_batch(&tbls, &BinRecord::new()).unwrap().num_columns(), 0);
+    }
+}
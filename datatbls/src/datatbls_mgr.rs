@@ -2,63 +2,483 @@
 
 use std::path::Path;
 use std::io::Write;
+use std::sync::Arc;
 use anyhow::Result;
 
 use crate::stringtbl::StringTableManager;
-use crate::itemtbl::ItemTable;
-use crate::bin::{BinRecord, Value};
+use crate::itemtbl::{ItemTable, ItemCatalog, ItemClass, ItemKind, item_code_str};
+use crate::bin::{BinFile, BinRecord, Record, Field, FieldRef, FieldType, Primitive, Schema, Value};
+use crate::fields;
+use crate::calc::{self, CalcCode};
+use crate::skills::SkillCatalog;
+use crate::uniques::{UniqueCatalog, SetCatalog};
+use crate::itemtypes::ItemTypes;
+use crate::runewords::RunewordCatalog;
+use crate::query::{Query, QueryResult};
+use crate::skillcalc::{SkillContext, ProgressionTable};
+use crate::vfs::Vfs;
+
+/// the game's name of an excel bin, e.g. `data\global\excel\weapons.bin`
+pub fn excel_file(file: &str) -> String {
+    format!(r"data\global\excel\{file}")
+}
+
+/// quotes `s` for json. the `\n` that `StringTable::read` puts in place of
+/// newlines is two characters and is written as such
+pub fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// quotes `s` for csv when it contains a separator, quote or line break
+pub fn csv_str(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// escapes `s` for html and svg text
+pub fn html_str(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// a typed value of the spreadsheet exports
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Int(i64),
+    Text(String),
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(v) => write!(f, "{v}"),
+            Self::Text(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// the names `DataTblsManager::table` knows, in load order
+pub static TABLE_NAMES: &[&str] = &[
+    "weapons", "armor", "misc", "itemtypes", "itemstatcost", "properties",
+    "skills", "skilldesc", "uniqueitems", "setitems", "sets", "runes",
+    "missiles", "states",
+];
 
 pub struct DataTblsManager {
     pub strtbl: StringTableManager,
     pub weapon: ItemTable,
     pub armor: ItemTable,
     pub misc: ItemTable,
+    pub items: ItemCatalog,
+    pub item_types: BinRecord,
+    pub item_type_tree: ItemTypes,
+    pub skills: BinRecord,
+    pub skill_desc: BinRecord,
+    pub item_stat_cost: BinRecord,
+    pub properties: BinRecord,
+    pub unique_items: BinRecord,
+    pub set_items: BinRecord,
+    pub sets: BinRecord,
+    pub runes: BinRecord,
+    /// only their id is decoded, they are loaded so references to them resolve
+    pub missiles: BinRecord,
+    pub states: BinRecord,
+    pub skills_code: CalcCode,
+    pub skill_desc_code: CalcCode,
+    pub items_code: CalcCode,
+    pub skill_catalog: SkillCatalog,
+    pub unique_catalog: UniqueCatalog,
+    pub set_catalog: SetCatalog,
+    pub runeword_catalog: RunewordCatalog,
+
+    /// `format_field` appends the name of the referenced row to references
+    pub show_refs: bool,
+}
+
+impl Default for DataTblsManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DataTblsManager {
     pub fn new() -> Self {
         Self {
             strtbl: StringTableManager::new(),
-            weapon: ItemTable::new(1),
-            armor: ItemTable::new(1001),
-            misc: ItemTable::new(2001),
+            weapon: ItemTable::new(ItemKind::Weapon),
+            armor: ItemTable::new(ItemKind::Armor),
+            misc: ItemTable::new(ItemKind::Misc),
+            items: ItemCatalog::new(),
+            item_types: BinRecord::new(),
+            item_type_tree: ItemTypes::new(),
+            skills: BinRecord::new(),
+            skill_desc: BinRecord::new(),
+            item_stat_cost: BinRecord::new(),
+            properties: BinRecord::new(),
+            unique_items: BinRecord::new(),
+            set_items: BinRecord::new(),
+            sets: BinRecord::new(),
+            runes: BinRecord::new(),
+            missiles: BinRecord::new(),
+            states: BinRecord::new(),
+            skills_code: CalcCode::new(calc::SKILL_CALC),
+            skill_desc_code: CalcCode::new(calc::SKILL_CALC),
+            items_code: CalcCode::new(calc::MISC_CALC),
+            skill_catalog: SkillCatalog::new(),
+            unique_catalog: UniqueCatalog::new(),
+            set_catalog: SetCatalog::new(),
+            runeword_catalog: RunewordCatalog::new(),
+            show_refs: false,
         }
     }
 
+    /// loads an extracted `data` directory
     pub fn load<T: AsRef<std::ffi::OsStr>>(&mut self, data_path: T) -> Result<()> {
-        let data_path = Path::new(&data_path);
-        self.strtbl.load(
-            data_path.join(r"LOCAL\lng\CHI\string.tbl"),
-            data_path.join(r"LOCAL\lng\CHI\patchstring.tbl"),
-            data_path.join(r"LOCAL\lng\CHI\expansionstring.tbl"),
+        let mut vfs = Vfs::new();
+        vfs.add_dir(Path::new(&data_path), "data")?;
+        self.load_vfs(&vfs)
+    }
+
+    pub fn load_darkmoon<T: AsRef<std::ffi::OsStr>>(&mut self, data_path: T) -> Result<()> {
+        let mut vfs = Vfs::new();
+        vfs.add_dir(Path::new(&data_path), "data")?;
+        self.load_darkmoon_vfs(&vfs)
+    }
+
+    /// loads the tables the way the game resolves them through `vfs`
+    pub fn load_vfs(&mut self, vfs: &Vfs) -> Result<()> {
+        self.strtbl.load_vfs(
+            vfs,
+            r"data\LOCAL\lng\CHI\string.tbl",
+            r"data\LOCAL\lng\CHI\patchstring.tbl",
+            r"data\LOCAL\lng\CHI\expansionstring.tbl",
             None,
             None,
         )?;
 
-        self.weapon.load(data_path.join(r"global\excel\weapons.bin"))?;
-        self.armor.load(data_path.join(r"global\excel\armor.bin"))?;
-        self.misc.load(data_path.join(r"global\excel\misc.bin"))?;
+        self.load_tables(vfs)
+    }
+
+    pub fn load_darkmoon_vfs(&mut self, vfs: &Vfs) -> Result<()> {
+        self.strtbl.load_vfs(
+            vfs,
+            r"data\LOCAL\lng\CHI\string.tbl",
+            r"data\LOCAL\lng\CHI\patchstring.tbl",
+            r"data\LOCAL\lng\CHI\expansionstring.tbl",
+            Some(r"data\duck\lng\chi\DuckModString.tbl"),
+            Some(r"data\duck\lng\chi\DuckPermString.tbl"),
+        )?;
+
+        self.load_tables(vfs)
+    }
+
+    fn load_tables(&mut self, vfs: &Vfs) -> Result<()> {
+        self.load_items(vfs)?;
+        self.load_stats(vfs)?;
+        self.load_skills(vfs)?;
+        self.load_uniques(vfs)?;
+        self.load_referenced(vfs)?;
+        self.load_calc_codes(vfs)
+    }
+
+    fn read_bin(vfs: &Vfs, file: &str, fields: &[Field]) -> Result<BinRecord> {
+        BinFile::from_bytes(vfs.read(&excel_file(file))?, fields).read()
+    }
+
+    /// a table read only for its rows: `id` is decoded and the rest of each
+    /// record, as big as the file says, is kept as gap bytes
+    fn read_rows(data: Vec<u8>, id: Field) -> Result<BinRecord> {
+        if data.len() < 4 {
+            anyhow::bail!("bin is {} bytes, too short for a record count", data.len());
+        }
+
+        let count = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+        let size = (data.len() - 4).checked_div(count).unwrap_or(id.size());
+
+        if size < id.size() {
+            anyhow::bail!("{count} records of 0x{size:X} bytes can not hold {}", id.name);
+        }
+
+        BinFile::with_schema(data, Arc::new(Schema::with_record_size(&[id], size))).read()
+    }
+
+    fn load_referenced(&mut self, vfs: &Vfs) -> Result<()> {
+        self.missiles = Self::read_rows(vfs.read(&excel_file("missiles.bin"))?, crate::field!("id", u32, 0x00))?;
+        self.states = Self::read_rows(vfs.read(&excel_file("states.bin"))?, crate::field!("id", u16, 0x00))?;
 
         Ok(())
     }
 
-    pub fn load_darkmoon<T: AsRef<std::ffi::OsStr>>(&mut self, data_path: T) -> Result<()> {
-        let data_path = Path::new(&data_path);
-        self.strtbl.load(
-            data_path.join(r"LOCAL\lng\CHI\string.tbl"),
-            data_path.join(r"LOCAL\lng\CHI\patchstring.tbl"),
-            data_path.join(r"LOCAL\lng\CHI\expansionstring.tbl"),
-            Some(data_path.join(r"duck\lng\chi\DuckModString.tbl")),
-            Some(data_path.join(r"duck\lng\chi\DuckPermString.tbl")),
-        )?;
+    fn load_items(&mut self, vfs: &Vfs) -> Result<()> {
+        self.weapon.load_bytes(vfs.read(&excel_file("weapons.bin"))?)?;
+        self.armor.load_bytes(vfs.read(&excel_file("armor.bin"))?)?;
+        self.misc.load_bytes(vfs.read(&excel_file("misc.bin"))?)?;
+
+        self.items = ItemCatalog::build([&mut self.weapon, &mut self.armor, &mut self.misc]);
+
+        self.item_types = Self::read_bin(vfs, "itemtypes.bin", &fields::ITEM_TYPES)?;
+        self.item_type_tree = ItemTypes::build(&self.item_types);
+
+        Ok(())
+    }
+
+    fn load_skills(&mut self, vfs: &Vfs) -> Result<()> {
+        self.skills = Self::read_bin(vfs, "skills.bin", &fields::SKILLS)?;
+        self.skill_desc = Self::read_bin(vfs, "skilldesc.bin", &fields::SKILL_DESC)?;
+        self.skill_catalog = SkillCatalog::build(self);
+
+        Ok(())
+    }
 
-        self.weapon.load(data_path.join(r"global\excel\weapons.bin"))?;
-        self.armor.load(data_path.join(r"global\excel\armor.bin"))?;
-        self.misc.load(data_path.join(r"global\excel\misc.bin"))?;
+    fn load_stats(&mut self, vfs: &Vfs) -> Result<()> {
+        self.item_stat_cost = Self::read_bin(vfs, "itemstatcost.bin", &fields::ITEM_STAT_COST)?;
+        self.properties = Self::read_bin(vfs, "properties.bin", &fields::PROPERTIES)?;
 
         Ok(())
     }
 
+    /// needs the items, stats and skills for the joins and property texts
+    fn load_uniques(&mut self, vfs: &Vfs) -> Result<()> {
+        self.unique_items = Self::read_bin(vfs, "uniqueitems.bin", &fields::UNIQUE_ITEMS)?;
+        self.set_items = Self::read_bin(vfs, "setitems.bin", &fields::SET_ITEMS)?;
+        self.sets = Self::read_bin(vfs, "sets.bin", &fields::SETS)?;
+
+        self.unique_catalog = UniqueCatalog::build(self);
+        self.set_catalog = SetCatalog::build(self);
+
+        self.runes = Self::read_bin(vfs, "runes.bin", &fields::RUNES)?;
+        self.runeword_catalog = RunewordCatalog::build(self);
+
+        Ok(())
+    }
+
+    fn load_calc_codes(&mut self, vfs: &Vfs) -> Result<()> {
+        for (file, code) in [
+            ("skillscode.bin", &mut self.skills_code),
+            ("skilldesccode.bin", &mut self.skill_desc_code),
+            ("itemscode.bin", &mut self.items_code),
+        ] {
+            let name = excel_file(file);
+            if vfs.exists(&name) {
+                *code = CalcCode::from_bytes(vfs.read(&name)?, calc::calc_table(file.trim_end_matches("code.bin")));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the code buffer a `Field::calc` names
+    pub fn calc_code(&self, name: &str) -> Option<&CalcCode> {
+        let code = match name {
+            "skills" => &self.skills_code,
+            "skilldesc" => &self.skill_desc_code,
+            "items" => &self.items_code,
+            _ => return None,
+        };
+
+        if code.is_empty() { None } else { Some(code) }
+    }
+
+    /// the readable calc expressions of a calc field, `None` for empty calcs
+    pub fn decode_calc(&self, field: FieldRef) -> Result<Vec<Option<String>>> {
+        let name = field.calc.unwrap_or_else(|| panic!("{} is not a calc", field.name));
+        let code = match self.calc_code(name) {
+            Some(code) => code,
+            None => anyhow::bail!("calc code of {name} is not loaded"),
+        };
+
+        let mut exprs = Vec::new();
+        for offset in field.calc_offsets() {
+            exprs.push(code.decode(offset)?.map(|e| e.to_string()));
+        }
+
+        Ok(exprs)
+    }
+
+    /// evaluates skill `id` from level 1 to its max level
+    pub fn skill_progression(&self, id: i16, ctx: &SkillContext) -> Option<ProgressionTable> {
+        let skill = self.skill_catalog.get(id)?;
+        Some(ProgressionTable::build(self, &self.skills.records()[skill.row], ctx))
+    }
+
+    /// the loaded table a `Field::reference` names, `None` for tables we do not load
+    pub fn table(&self, name: &str) -> Option<&BinRecord> {
+        match name {
+            "weapons" => Some(self.weapon.records()),
+            "armor" => Some(self.armor.records()),
+            "misc" => Some(self.misc.records()),
+            "skills" => Some(&self.skills),
+            "skilldesc" => Some(&self.skill_desc),
+            "itemstatcost" => Some(&self.item_stat_cost),
+            "properties" => Some(&self.properties),
+            "uniqueitems" => Some(&self.unique_items),
+            "setitems" => Some(&self.set_items),
+            "sets" => Some(&self.sets),
+            "itemtypes" => Some(&self.item_types),
+            "runes" => Some(&self.runes),
+            "missiles" => Some(&self.missiles),
+            "states" => Some(&self.states),
+            _ => None,
+        }
+    }
+
+    /// the rows of `table` matching `filter` with the values of the `columns` expressions,
+    /// without columns the row name is shown
+    pub fn query(&self, table: &str, filter: &str, columns: &[&str]) -> Result<QueryResult> {
+        let records = match self.table(table) {
+            Some(records) => records,
+            None => anyhow::bail!("table {table} is not loaded"),
+        };
+
+        let filter = Query::parse(filter)?;
+        let columns = columns.iter().map(|c| Query::parse(c)).collect::<Result<Vec<_>>>()?;
+
+        let mut result = QueryResult{
+            columns: if columns.is_empty() { vec!["name".to_string()] } else { columns.iter().map(|c| c.text.clone()).collect() },
+            rows: Vec::new(),
+        };
+
+        for (row, record) in records.iter().enumerate() {
+            if !filter.matches(self, record)? {
+                continue;
+            }
+
+            let values = if columns.is_empty() {
+                vec![self.row_name(table, record).unwrap_or_default()]
+            } else {
+                columns.iter().map(|c| c.eval(self, record).map(|v| v.to_string())).collect::<Result<Vec<_>>>()?
+            };
+
+            result.rows.push((row, values));
+        }
+
+        Ok(result)
+    }
+
+    /// every loaded table that has rows, by name
+    pub fn tables(&self) -> Vec<(&'static str, &BinRecord)> {
+        TABLE_NAMES.iter()
+            .filter_map(|name| Some((*name, self.table(name)?)))
+            .filter(|(_, t)| !t.records().is_empty())
+            .collect()
+    }
+
+    /// follows the reference in `field` of `record`, e.g. `resolve(skill, "skill_desc")`
+    pub fn resolve(&self, record: &Record, field: &str) -> Option<&Record> {
+        self.resolve_all(record, field).into_iter().next().flatten()
+    }
+
+    /// like `resolve` but for every element of an array reference
+    pub fn resolve_all(&self, record: &Record, field: &str) -> Vec<Option<&Record>> {
+        let f = record.get(field);
+        let table = f.reference.unwrap_or_else(|| panic!("{field} is not a reference"));
+        let table = self.table(table);
+
+        f.ref_rows().into_iter().map(|row| table?.records().get(row?)).collect()
+    }
+
+    /// a display name for a row of `table`
+    pub fn row_name(&self, table: &str, record: &Record) -> Option<String> {
+        let name = match table {
+            "weapons" | "armor" | "misc" => self.get_string_by_index(record.get("name_str").value.str_id()),
+            "skilldesc" => self.get_string_by_index(record.get("str_name").value.str_id()),
+            "skills" => {
+                let desc = self.resolve(record, "skill_desc")?;
+                self.get_string_by_index(desc.get("str_name").value.str_id())
+            },
+            "itemstatcost" => self.get_string_by_index(record.get("desc_str_pos").value.str_id()),
+            "uniqueitems" | "setitems" | "sets" | "runes" => self.get_string_by_index(record.get("name_str").value.str_id()),
+            "itemtypes" => return Some(item_code_str(record.get("code").value.item_code())),
+            "properties" => {
+                let stat = self.resolve(record, "stat")?;
+                return self.row_name("itemstatcost", stat);
+            },
+            _ => None,
+        };
+
+        name.map(|s| s.trim_end().to_string())
+    }
+
+    fn format_ref(&self, field: FieldRef) -> Option<String> {
+        let table_name = field.reference?;
+        let table = self.table(table_name)?;
+
+        // null references are `-`, rows without a name are shown by number
+        let names: Vec<String> = field.ref_rows().into_iter()
+            .map(|row| match row {
+                Some(row) => table.records().get(row)
+                    .and_then(|r| self.row_name(table_name, r))
+                    .unwrap_or_else(|| row.to_string()),
+                None => "-".to_string(),
+            })
+            .collect();
+
+        Some(names.join(", "))
+    }
+
+    pub fn item_table(&self, kind: ItemKind) -> &ItemTable {
+        match kind {
+            ItemKind::Weapon => &self.weapon,
+            ItemKind::Armor => &self.armor,
+            ItemKind::Misc => &self.misc,
+        }
+    }
+
+    pub fn item_record(&self, class: &ItemClass) -> &Record {
+        &self.item_table(class.kind).records().records()[class.row]
+    }
+
+    pub fn get_item_by_class_id(&self, class_id: u32) -> Option<&Record> {
+        self.items.get(class_id).map(|c| self.item_record(c))
+    }
+
+    pub fn get_item_by_code(&self, code: &str) -> Option<&Record> {
+        self.items.get_by_code_str(code).map(|c| self.item_record(c))
+    }
+
+    /// the itemtypes rows of a weapons/armor/misc record, its type and type2 with all their ancestors
+    pub fn item_types_of(&self, item: &Record) -> Vec<usize> {
+        let mut types = Vec::new();
+
+        for row in item.get("type").ref_rows().into_iter().flatten() {
+            for t in self.item_type_tree.ancestors(row) {
+                if !types.contains(&t) {
+                    types.push(t);
+                }
+            }
+        }
+
+        types
+    }
+
+    /// whether a weapons/armor/misc record is of type `code`, e.g. `is_type(item, "mele")`
+    pub fn is_type(&self, item: &Record, code: &str) -> bool {
+        match self.item_type_tree.get_by_code(code) {
+            Some(t) => self.item_types_of(item).contains(&t),
+            None => false,
+        }
+    }
+
+    pub fn validate_items(&self) -> Result<()> {
+        self.items.validate([&self.weapon, &self.armor, &self.misc])
+    }
+
     pub fn get_string_by_index(&self, index: u16) -> Option<&str> {
         self.strtbl.get_string_by_index(index)
     }
@@ -78,7 +498,7 @@ impl DataTblsManager {
                     continue;
                 }
 
-                lines.push(format!("        '{}': {},", f.name, self.format_value(&f.value)));
+                lines.push(format!("        '{}': {},", f.name, self.format_field(f)));
             }
 
             lines.push("    },".to_string());
@@ -94,6 +514,148 @@ impl DataTblsManager {
         Ok(())
     }
 
+    /// writes `rec` as a tab separated .txt, packed flags are expanded into their boolean columns
+    /// followed by a column of the bits no flag names
+    pub fn dump_txt(&self, rec: &BinRecord, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        for (i, record) in rec.iter().enumerate() {
+            let columns: Vec<(String, String)> = record.iter()
+                .filter(|f| !f.name.starts_with("__pad"))
+                .flat_map(|f| self.txt_columns(f))
+                .collect();
+
+            if i == 0 {
+                lines.push(columns.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>().join("\t"));
+            }
+
+            lines.push(columns.iter().map(|(_, v)| v.as_str()).collect::<Vec<_>>().join("\t"));
+        }
+
+        let output = lines.join("\r\n");
+
+        let mut txt = std::fs::File::create(file_name)?;
+        txt.write_all(output.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// writes `rec` as csv with a header row, see `record_cells` for the columns
+    pub fn dump_csv(&self, rec: &BinRecord, file_name: &str, expand_arrays: bool) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        for (i, record) in rec.iter().enumerate() {
+            let cells = self.record_cells(record, expand_arrays);
+
+            if i == 0 {
+                lines.push(cells.iter().map(|(k, _)| csv_str(k)).collect::<Vec<_>>().join(","));
+            }
+
+            lines.push(cells.iter().map(|(_, v)| csv_str(&v.to_string())).collect::<Vec<_>>().join(","));
+        }
+
+        let mut csv = std::fs::File::create(file_name)?;
+        // the BOM makes Excel read the file as utf-8
+        csv.write_all("\u{FEFF}".as_bytes())?;
+        csv.write_all(lines.join("\r\n").as_bytes())?;
+
+        Ok(())
+    }
+
+    fn txt_columns(&self, f: FieldRef) -> Vec<(String, String)> {
+        if f.flags.is_some() {
+            // bits no flag names stay in a column of the field's own name
+            let mut columns: Vec<_> = f.flag_values().into_iter().map(|(name, set)| (name.to_string(), (set as u8).to_string())).collect();
+            columns.push((f.name.clone(), f.unnamed_bits().to_string()));
+            return columns;
+        }
+
+        fn expand<T: ToString>(name: &str, v: &[T]) -> Vec<(String, String)> {
+            v.iter().enumerate().map(|(i, v)| (format!("{}{}", name, i + 1), v.to_string())).collect()
+        }
+
+        if f.enumeration.is_some() {
+            return vec![(f.name.clone(), f.enum_format())];
+        }
+
+        let value = match &f.value {
+            Value::I8Array(v) => return expand(&f.name, v),
+            Value::I16Array(v) => return expand(&f.name, v),
+            Value::I32Array(v) => return expand(&f.name, v),
+            Value::U8Array(v) => return expand(&f.name, v),
+            Value::U16Array(v) => return expand(&f.name, v),
+            Value::U32Array(v) => return expand(&f.name, v),
+
+            Value::StringId(v) => self.strtbl.get_key_by_index(*v).map(|k| k.to_string()).unwrap_or_else(|| v.to_string()),
+            Value::ItemCode(v) => item_code_str(*v),
+            Value::String(s) => s.clone(),
+            v => v.as_i64().unwrap().to_string(),
+        };
+
+        vec![(f.name.clone(), value)]
+    }
+
+    /// the spreadsheet columns of `record`, arrays become `name1..N` columns when
+    /// `expand_arrays` is set and a comma separated cell otherwise, string ids get
+    /// the resolved text in a `name_text` column next to them
+    pub fn record_cells(&self, record: &Record, expand_arrays: bool) -> Vec<(String, Cell)> {
+        record.iter()
+            .filter(|f| !f.name.starts_with("__pad"))
+            .flat_map(|f| self.field_cells(f, expand_arrays))
+            .collect()
+    }
+
+    fn field_cells(&self, f: FieldRef, expand_arrays: bool) -> Vec<(String, Cell)> {
+        fn array<T: Copy + Into<i64> + ToString>(name: &str, v: &[T], expand: bool) -> Vec<(String, Cell)> {
+            if expand {
+                v.iter().enumerate().map(|(i, v)| (format!("{}{}", name, i + 1), Cell::Int((*v).into()))).collect()
+            } else {
+                vec![(name.to_string(), Cell::Text(v.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")))]
+            }
+        }
+
+        match &f.value {
+            Value::I8Array(v) => array(&f.name, v, expand_arrays),
+            Value::I16Array(v) => array(&f.name, v, expand_arrays),
+            Value::I32Array(v) => array(&f.name, v, expand_arrays),
+            Value::U8Array(v) => array(&f.name, v, expand_arrays),
+            Value::U16Array(v) => array(&f.name, v, expand_arrays),
+            Value::U32Array(v) => array(&f.name, v, expand_arrays),
+            Value::StringId(id) => vec![
+                (f.name.clone(), Cell::Int(*id as i64)),
+                (format!("{}_text", f.name), Cell::Text(self.get_string_by_index(*id).unwrap_or("").to_string())),
+            ],
+            Value::ItemCode(code) => vec![(f.name.clone(), Cell::Text(item_code_str(*code)))],
+            Value::String(s) => vec![(f.name.clone(), Cell::Text(s.clone()))],
+            v => vec![(f.name.clone(), Cell::Int(v.as_i64().unwrap()))],
+        }
+    }
+
+    pub fn format_field(&self, field: FieldRef) -> String {
+        if field.flags.is_some() {
+            return format!("\"{:?} [{}]\"", field.value, field.set_flags().join(", "));
+        }
+
+        if field.enumeration.is_some() {
+            return format!("\"{}\"", field.enum_format());
+        }
+
+        if field.calc.is_some() {
+            if let Ok(exprs) = self.decode_calc(field) {
+                let exprs: Vec<String> = exprs.into_iter().map(|e| e.unwrap_or_default()).collect();
+                return format!("\"{}\"", exprs.join(", "));
+            }
+        }
+
+        if self.show_refs {
+            if let Some(names) = self.format_ref(field) {
+                return format!("\"{:?} -> {}\"", field.value, names);
+            }
+        }
+
+        self.format_value(field.value)
+    }
+
     pub fn format_value(&self, value: &Value) -> String {
         match value {
             Value::Int8(v) => format!("\"Int8: 0x{v:02X} ({v})\""),
@@ -119,10 +681,157 @@ impl DataTblsManager {
                 format!("\"{}\"", String::from_utf8(b.to_vec()).unwrap().trim_end_matches(char::from(0)))
             },
 
-            Value::String(size, s) => {
-                format!("\"{}\"", s.as_ref().unwrap())
+            Value::String(s) => {
+                format!("\"{s}\"")
             },
         }
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bin::tests::zeroed;
+    use crate::stringtbl::tests::manager;
+
+    /// one skilldesc row whose name needs quoting in a csv
+    fn tbls() -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+        tbls.strtbl = manager(&[("dummy", ""), ("skillname36", "Fire, \"Bolt\"")], &[], &[]);
+
+        tbls.skill_desc = zeroed(&fields::SKILL_DESC, 1);
+        let desc = tbls.skill_desc.get_mut(0).unwrap();
+        desc.set("str_name", Value::StringId(1));
+        desc.set("desc_line", Value::U8Array((1..=17).collect()));
+
+        tbls
+    }
+
+    #[test]
+    fn json_str_escapes() {
+        assert_eq!(json_str("plain"), r#""plain""#);
+        assert_eq!(json_str(r"data\global\excel"), r#""data\\global\\excel""#);
+        assert_eq!(json_str("say \"hi\""), r#""say \"hi\"""#);
+        assert_eq!(json_str("a\tb\r\nc\u{1}"), r#""a\tb\r\nc\u0001""#);
+        assert_eq!(json_str("中文ÿc4"), "\"中文ÿc4\"");
+    }
+
+    #[test]
+    fn json_str_keeps_escaped_newlines_as_written() {
+        // StringTable::read turns a newline into `\` `n`
+        assert_eq!(json_str(r"line 1\nline 2"), r#""line 1\\nline 2""#);
+    }
+
+    #[test]
+    fn record_cells_expand_arrays_and_resolve_strings() {
+        let tbls = tbls();
+        let record = &tbls.skill_desc.records()[0];
+
+        let cells = tbls.record_cells(record, true);
+        let cell = |name: &str| cells.iter().find(|(n, _)| n == name).map(|(_, c)| c.clone());
+
+        assert_eq!(cell("str_name"), Some(Cell::Int(1)));
+        assert_eq!(cell("str_name_text"), Some(Cell::Text("Fire, \"Bolt\"".into())));
+        assert_eq!(cell("desc_line1"), Some(Cell::Int(1)));
+        assert_eq!(cell("desc_line17"), Some(Cell::Int(17)));
+        assert_eq!(cell("desc_line"), None);
+        assert_eq!(cell("__pad_16"), None);
+
+        let cells = tbls.record_cells(record, false);
+        let line = cells.iter().find(|(n, _)| n == "desc_line").map(|(_, c)| c.to_string()).unwrap();
+        assert_eq!(line, (1..=17).map(|i| i.to_string()).collect::<Vec<_>>().join(","));
+
+        // patchstring is empty, the id names nothing
+        let mut missing = zeroed(&fields::SKILL_DESC, 1);
+        missing.get_mut(0).unwrap().set("str_name", Value::StringId(10500));
+        let cells = tbls.record_cells(&missing.records()[0], false);
+        assert!(cells.contains(&("str_name_text".to_string(), Cell::Text(String::new()))));
+    }
+
+    #[test]
+    fn dump_csv_quotes_cells_after_a_bom() {
+        let tbls = tbls();
+        let path = std::env::temp_dir().join(format!("datatbls-dump-csv-{}.csv", std::process::id()));
+
+        tbls.dump_csv(&tbls.skill_desc, path.to_str().unwrap(), false).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let csv = csv.strip_prefix('\u{FEFF}').expect("csv starts with a BOM");
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("skill_desc,skill_page,"));
+        assert!(lines[1].contains(r#",1,"Fire, ""Bolt""","#));
+        assert!(lines[1].contains(r#","1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17","#));
+    }
+
+    #[test]
+    fn references_resolve_to_rows_of_the_named_table() {
+        let mut tbls = tbls();
+        tbls.skills = zeroed(&fields::SKILLS, 2);
+        tbls.skills.get_mut(1).unwrap().set("skill_desc", Value::UInt16(u16::MAX));
+        tbls.skills.get_mut(1).unwrap().set("srv_missile", Value::UInt16(1));
+
+        let mut states = 3u32.to_le_bytes().to_vec();
+        states.extend([0, 0, 0xAA, 0xBB, 1, 0, 0xCC, 0xDD, 2, 0, 0xEE, 0xFF]);
+        tbls.states = DataTblsManager::read_rows(states, crate::field!("id", u16, 0x00)).unwrap();
+        assert_eq!(tbls.states.records()[2].get("id").value.u16(), 2);
+        assert_eq!(tbls.states.records()[1].gap_bytes(), [0xCC, 0xDD]);
+
+        let fire_bolt = &tbls.skills.records()[0];
+        let desc = tbls.resolve(fire_bolt, "skill_desc").unwrap();
+        assert!(std::ptr::eq(desc, &tbls.skill_desc.records()[0]));
+        assert_eq!(tbls.row_name("skills", fire_bolt).as_deref(), Some("Fire, \"Bolt\""));
+
+        // 0xFFFF is a null reference, missiles are not loaded here
+        let other = &tbls.skills.records()[1];
+        assert!(tbls.resolve(other, "skill_desc").is_none());
+        assert!(tbls.resolve(other, "srv_missile").is_none());
+        assert_eq!(tbls.row_name("skills", other), None);
+
+        let mut item = zeroed(&fields::ITEMS, 1);
+        item.get_mut(0).unwrap().set("curse_state", Value::U16Array(vec![2, u16::MAX]));
+        let item = &item.records()[0];
+
+        let states = tbls.resolve_all(item, "curse_state");
+        assert_eq!(states.len(), 2);
+        assert!(std::ptr::eq(states[0].unwrap(), &tbls.states.records()[2]));
+        assert!(states[1].is_none());
+
+        assert_eq!(tbls.format_ref(item.get("curse_state")).as_deref(), Some("2, -"));
+        assert_eq!(tbls.format_ref(fire_bolt.get("skill_desc")).as_deref(), Some("Fire, \"Bolt\""));
+        assert_eq!(tbls.format_ref(other.get("srv_missile")).as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn read_rows_rejects_records_smaller_than_the_id() {
+        let id = || crate::field!("id", u32, 0x00);
+
+        let err = DataTblsManager::read_rows(vec![2, 0, 0, 0, 1, 2, 3, 4], id()).err().unwrap();
+        assert_eq!(err.to_string(), "2 records of 0x2 bytes can not hold id");
+        assert!(DataTblsManager::read_rows(0u32.to_le_bytes().to_vec(), id()).unwrap().records().is_empty());
+    }
+
+    #[test]
+    fn dump_txt_writes_a_column_per_flag() {
+        let tbls = tbls();
+        let mut items = zeroed(&fields::ITEMS, 2);
+        items.get_mut(0).unwrap().set("bit_field_1", Value::UInt32(0b0010));
+        items.get_mut(1).unwrap().set("bit_field_1", Value::UInt32(0b1101));
+
+        let path = std::env::temp_dir().join(format!("datatbls-dump-txt-{}.txt", std::process::id()));
+        tbls.dump_txt(&items, path.to_str().unwrap()).unwrap();
+        let txt = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let rows: Vec<Vec<&str>> = txt.split("\r\n").map(|l| l.split('\t').collect()).collect();
+        let column = |row: usize, name: &str| rows[row][rows[0].iter().position(|c| *c == name).unwrap()];
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!([column(1, "nodurability"), column(1, "quivered"), column(1, "bit_field_1")], ["0", "1", "0"]);
+        // bits 2 and 3 have no name and stay in bit_field_1
+        assert_eq!([column(2, "nodurability"), column(2, "quivered"), column(2, "bit_field_1")], ["1", "0", "12"]);
+    }
+}
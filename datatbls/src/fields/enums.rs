@@ -0,0 +1,104 @@
+use crate::bin::Enumeration;
+
+pub static CHAR_CLASS: Enumeration = Enumeration::new("char_class", &[
+    (0,     "ama"),
+    (1,     "sor"),
+    (2,     "nec"),
+    (3,     "pal"),
+    (4,     "bar"),
+    (5,     "dru"),
+    (6,     "ass"),
+]);
+
+/// plrmode.txt
+pub static PLAYER_MODE: Enumeration = Enumeration::new("player_mode", &[
+    (0,     "DT"),
+    (1,     "NU"),
+    (2,     "WL"),
+    (3,     "RN"),
+    (4,     "GH"),
+    (5,     "TN"),
+    (6,     "TW"),
+    (7,     "A1"),
+    (8,     "A2"),
+    (9,     "BL"),
+    (10,    "SC"),
+    (11,    "TH"),
+    (12,    "KK"),
+    (13,    "S1"),
+    (14,    "S2"),
+    (15,    "S3"),
+    (16,    "S4"),
+    (17,    "DD"),
+    (18,    "SQ"),
+    (19,    "KB"),
+]);
+
+/// monmode.txt
+pub static MONSTER_MODE: Enumeration = Enumeration::new("monster_mode", &[
+    (0,     "DT"),
+    (1,     "NU"),
+    (2,     "WL"),
+    (3,     "GH"),
+    (4,     "A1"),
+    (5,     "A2"),
+    (6,     "BL"),
+    (7,     "SC"),
+    (8,     "S1"),
+    (9,     "S2"),
+    (10,    "S3"),
+    (11,    "S4"),
+    (12,    "DD"),
+    (13,    "KB"),
+    (14,    "SQ"),
+    (15,    "RN"),
+]);
+
+/// hitclass.txt
+pub static HIT_CLASS: Enumeration = Enumeration::new("hit_class", &[
+    (0,     "none"),
+    (1,     "hth"),
+    (2,     "1hss"),
+    (3,     "1hsl"),
+    (4,     "2hss"),
+    (5,     "2hsl"),
+    (6,     "1ht"),
+    (7,     "2ht"),
+    (8,     "club"),
+    (9,     "staf"),
+    (10,    "bow"),
+    (11,    "xbow"),
+    (12,    "claw"),
+    (13,    "ovly"),
+]);
+
+/// elemtypes.txt
+pub static ELEM_TYPE: Enumeration = Enumeration::new("elem_type", &[
+    (0,     "none"),
+    (1,     "fire"),
+    (2,     "ltng"),
+    (3,     "mag"),
+    (4,     "cold"),
+    (5,     "pois"),
+    (6,     "life"),
+    (7,     "mana"),
+    (8,     "stam"),
+    (9,     "stun"),
+    (10,    "rand"),
+    (11,    "burn"),
+    (12,    "frze"),
+]);
+
+/// which of weapons.txt, armor.txt and misc.txt an items row came from
+pub static ITEM_SUB_TYPE: Enumeration = Enumeration::new("item_sub_type", &[
+    (0,     "weapon"),
+    (1,     "armor"),
+    (2,     "misc"),
+]);
+
+/// the row of gems.txt mods a socketed gem applies
+pub static GEM_APPLY_TYPE: Enumeration = Enumeration::new("gem_apply_type", &[
+    (0,     "weapon"),
+    (1,     "armor"),
+    (2,     "shield"),
+]);
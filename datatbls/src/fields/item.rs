@@ -1,6 +1,14 @@
 use lazy_static::lazy_static;
 use crate::bin::*;
 use crate::field;
+use super::enums::*;
+
+/// `bit_field_1`, only the bits we know are named. the others are shown as
+/// `bit N` and kept in a `bit_field_1` column by `dump_txt`
+pub static ITEM_FLAGS: &[BitFlag] = &[
+    BitFlag::new("nodurability",                0),
+    BitFlag::new("quivered",                    1),
+];
 
 lazy_static!{
     pub static ref ITEMS: Vec<Field> = vec![
@@ -14,15 +22,15 @@ lazy_static!{
         field!("ultra_code",                ItemCode,       0x8C),
         field!("alternate_gfx",             ItemCode,       0x90),
         field!("pspell",                    u32,            0x94),
-        field!("state",                     u16,            0x98),
-        field!("curse_state",               u16[2],         0x9A),
-        field!("stat",                      u16[3],         0x9E),
-        field!("calc",                      u32[3],         0xA4),
+        field!("state",                     u16,            0x98).with_ref("states"),
+        field!("curse_state",               u16[2],         0x9A).with_ref("states"),
+        field!("stat",                      u16[3],         0x9E).with_ref("itemstatcost"),
+        field!("calc",                      u32[3],         0xA4).with_calc("items"),
         field!("len",                       u32,            0xB0),
         field!("spell_desc",                u8,             0xB4),
         field!("__pad_B5",                  u8,             0xB5),
         field!("spell_desc_str",            u16,            0xB6),
-        field!("spell_desc_calc",           u32,            0xB8),
+        field!("spell_desc_calc",           u32,            0xB8).with_calc("items"),
         field!("better_gem",                u32,            0xBC),
         field!("weap_class",                u32,            0xC0),
         field!("weap_class_2_hand",         u32,            0xC4),
@@ -31,7 +39,7 @@ lazy_static!{
         field!("max_ac",                    i32,            0xD0),
         field!("gamble_cost",               u32,            0xD4),
         field!("speed",                     i32,            0xD8),
-        field!("bit_field_1",               u32,            0xDC),
+        field!("bit_field_1",               u32,            0xDC).with_flags(ITEM_FLAGS),
         field!("cost",                      u32,            0xE0),
         field!("min_stack",                 u32,            0xE4),
         field!("max_stack",                 u32,            0xE8),
@@ -40,7 +48,7 @@ lazy_static!{
         field!("name_str",                  StringId,       0xF4),
         field!("version",                   u16,            0xF6),
         field!("auto_prefix",               u16,            0xF8),
-        field!("missile_type",              u16,            0xFA),
+        field!("missile_type",              u16,            0xFA).with_ref("missiles"),
         field!("rarity",                    u8,             0xFC),
         field!("level",                     u8,             0xFD),
         field!("min_dam",                   u8,             0xFE),
@@ -66,8 +74,8 @@ lazy_static!{
         field!("armor_comp",                u8[6],          0x116),
         field!("2_handed",                  u8,             0x11C),
         field!("useable",                   u8,             0x11D),
-        field!("type",                      i16[2],         0x11E),
-        field!("sub_type",                  u8,             0x122),
+        field!("type",                      i16[2],         0x11E).with_ref("itemtypes"),
+        field!("sub_type",                  u8,             0x122).with_enum(&ITEM_SUB_TYPE),
         field!("unk_123",                   u8,             0x123),
         field!("drop_sound",                u16,            0x124),
         field!("use_sound",                 u16,            0x126),
@@ -91,9 +99,9 @@ lazy_static!{
         field!("transmogrify",              u8,             0x139),
         field!("tmog_min",                  u8,             0x13A),
         field!("tmog_max",                  u8,             0x13B),
-        field!("hit_class",                 u8,             0x13C),
+        field!("hit_class",                 u8,             0x13C).with_enum(&HIT_CLASS),
         field!("1_or_2_handed",             u8,             0x13D),
-        field!("gem_apply_type",            u8,             0x13E),
+        field!("gem_apply_type",            u8,             0x13E).with_enum(&GEM_APPLY_TYPE),
         field!("level_req",                 u8,             0x13F),
         field!("magic_level",               u8,             0x140),
         field!("transform",                 i8,             0x141),
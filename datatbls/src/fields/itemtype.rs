@@ -0,0 +1,42 @@
+use lazy_static::lazy_static;
+use crate::bin::*;
+use crate::field;
+
+lazy_static!{
+    pub static ref ITEM_TYPES: Vec<Field> = vec![
+        field!("code",                      ItemCode,       0x00),
+        field!("equiv",                     u16[2],         0x04).with_ref("itemtypes"),
+        field!("repair",                    u8,             0x08),
+        field!("body",                      u8,             0x09),
+        field!("body_loc_1",                u8,             0x0A),
+        field!("body_loc_2",                u8,             0x0B),
+        field!("shoots",                    u16,            0x0C).with_ref("itemtypes"),
+        field!("quiver",                    u16,            0x0E).with_ref("itemtypes"),
+        field!("throwable",                 u8,             0x10),
+        field!("reload",                    u8,             0x11),
+        field!("re_equip",                  u8,             0x12),
+        field!("auto_stack",                u8,             0x13),
+        field!("magic",                     u8,             0x14),
+        field!("rare",                      u8,             0x15),
+        field!("normal",                    u8,             0x16),
+        field!("charm",                     u8,             0x17),
+        field!("gem",                       u8,             0x18),
+        field!("beltable",                  u8,             0x19),
+        field!("max_sock_1",                u8,             0x1A),
+        field!("max_sock_25",               u8,             0x1B),
+        field!("max_sock_40",               u8,             0x1C),
+        field!("treasure_class",            u8,             0x1D),
+        field!("rarity",                    u8,             0x1E),
+        field!("staff_mods",                u8,             0x1F),
+        field!("cost_formula",              u8,             0x20),
+        field!("class",                     u8,             0x21),
+        field!("store_page",                u8,             0x22),
+        field!("var_inv_gfx",               u8,             0x23),
+        field!("inv_gfx_1",                 str[32],        0x24),
+        field!("inv_gfx_2",                 str[32],        0x44),
+        field!("inv_gfx_3",                 str[32],        0x64),
+        field!("inv_gfx_4",                 str[32],        0x84),
+        field!("inv_gfx_5",                 str[32],        0xA4),
+        field!("inv_gfx_6",                 str[32],        0xC4),
+    ];
+}
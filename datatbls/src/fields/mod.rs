@@ -1,41 +1,34 @@
 mod skill;
 mod item;
-
-pub use skill::{SKILLS, SKILL_DESC};
-pub use item::ITEMS;
+mod stat;
+mod unique;
+mod itemtype;
+mod rune;
+mod enums;
+
+pub use skill::{SKILLS, SKILL_DESC, SKILL_FLAGS_1, SKILL_FLAGS_2, AURA_FILTER_FLAGS};
+pub use item::{ITEMS, ITEM_FLAGS};
+pub use stat::{ITEM_STAT_COST, ITEM_STAT_FLAGS, PROPERTIES};
+pub use unique::{UNIQUE_ITEMS, UNIQUE_ITEM_FLAGS, SET_ITEMS, SETS};
+pub use itemtype::ITEM_TYPES;
+pub use rune::RUNES;
+pub use enums::*;
 
 #[macro_export]
 macro_rules! __parse_arr_type {
-    (u8, $size:expr) => { Value::U8Array(crate::fields::new_vec($size)) };
-    (i16, $size:expr) => { Value::I16Array(crate::fields::new_vec($size)) };
-    (u16, $size:expr) => { Value::U16Array(crate::fields::new_vec($size)) };
-    (u32, $size:expr) => { Value::U32Array(crate::fields::new_vec($size)) };
-    (str, $size:expr) => { Value::String($size, None) };
+    (str, $size:expr) => { FieldType::String($size) };
+    ($type:tt, $size:expr) => { FieldType::Array($crate::__parse_type!($type), $size) };
 }
 
 #[macro_export]
 macro_rules! __parse_type {
-    (u8) => { Value::UInt8 };
-    (u16) => { Value::UInt16 };
-    (u32) => { Value::UInt32 };
-    (i8) => { Value::Int8 };
-    (i16) => { Value::Int16 };
-    (i32) => { Value::Int32 };
-    ($type:ident) => { Value::$type }
-}
-
-#[macro_export]
-macro_rules! __get_default {
-    (str) => { u8::default() };
-    ($_:ident) => { 0 };
-}
-
-fn new_vec<T: std::default::Default>(size: usize) -> Vec<T> {
-    let mut v = Vec::with_capacity(size);
-    for _ in 0..size {
-        v.push(T::default());
-    }
-    v
+    (u8) => { Primitive::UInt8 };
+    (u16) => { Primitive::UInt16 };
+    (u32) => { Primitive::UInt32 };
+    (i8) => { Primitive::Int8 };
+    (i16) => { Primitive::Int16 };
+    (i32) => { Primitive::Int32 };
+    ($type:ident) => { Primitive::$type }
 }
 
 #[macro_export(local_inner_macros)]
@@ -48,13 +41,13 @@ macro_rules! field {
 
     ($name:expr, $type:tt, $offset:expr) => {
         {
-            Field::new($name, __parse_type!($type)(__get_default!($type)), $offset)
+            Field::new($name, FieldType::Primitive(__parse_type!($type)), $offset)
         }
     };
 
     // ($name:expr, str, $size:expr, $offset:expr) => {
     //     {
-    //         Field::new($name, Value::String($size, None), $offset)
+    //         Field::new($name, FieldType::String($size), $offset)
     //     }
     // };
 }
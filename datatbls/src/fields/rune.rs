@@ -0,0 +1,50 @@
+use lazy_static::lazy_static;
+use crate::bin::*;
+use crate::field;
+
+lazy_static!{
+    pub static ref RUNES: Vec<Field> = vec![
+        field!("index",                     str[64],        0x00),
+        field!("rune_name",                 str[64],        0x40),
+        field!("complete",                  u8,             0x80),
+        field!("server",                    u8,             0x81),
+        field!("name_str",                  StringId,       0x82),
+        field!("__pad_84",                  u16,            0x84),
+        field!("itype",                     u16[6],         0x86).with_ref("itemtypes"),
+        field!("etype",                     u16[3],         0x92).with_ref("itemtypes"),
+        field!("rune_1",                    i32,            0x98),
+        field!("rune_2",                    i32,            0x9C),
+        field!("rune_3",                    i32,            0xA0),
+        field!("rune_4",                    i32,            0xA4),
+        field!("rune_5",                    i32,            0xA8),
+        field!("rune_6",                    i32,            0xAC),
+        field!("prop_1",                    i32,            0xB0).with_ref("properties"),
+        field!("par_1",                     i32,            0xB4),
+        field!("min_1",                     i32,            0xB8),
+        field!("max_1",                     i32,            0xBC),
+        field!("prop_2",                    i32,            0xC0).with_ref("properties"),
+        field!("par_2",                     i32,            0xC4),
+        field!("min_2",                     i32,            0xC8),
+        field!("max_2",                     i32,            0xCC),
+        field!("prop_3",                    i32,            0xD0).with_ref("properties"),
+        field!("par_3",                     i32,            0xD4),
+        field!("min_3",                     i32,            0xD8),
+        field!("max_3",                     i32,            0xDC),
+        field!("prop_4",                    i32,            0xE0).with_ref("properties"),
+        field!("par_4",                     i32,            0xE4),
+        field!("min_4",                     i32,            0xE8),
+        field!("max_4",                     i32,            0xEC),
+        field!("prop_5",                    i32,            0xF0).with_ref("properties"),
+        field!("par_5",                     i32,            0xF4),
+        field!("min_5",                     i32,            0xF8),
+        field!("max_5",                     i32,            0xFC),
+        field!("prop_6",                    i32,            0x100).with_ref("properties"),
+        field!("par_6",                     i32,            0x104),
+        field!("min_6",                     i32,            0x108),
+        field!("max_6",                     i32,            0x10C),
+        field!("prop_7",                    i32,            0x110).with_ref("properties"),
+        field!("par_7",                     i32,            0x114),
+        field!("min_7",                     i32,            0x118),
+        field!("max_7",                     i32,            0x11C),
+    ];
+}
@@ -1,68 +1,134 @@
 use lazy_static::lazy_static;
 use crate::bin::*;
 use crate::field;
+use super::enums::*;
+
+pub static SKILL_FLAGS_1: &[BitFlag] = &[
+    BitFlag::new("decquant",                    0),
+    BitFlag::new("lob",                         1),
+    BitFlag::new("progressive",                 2),
+    BitFlag::new("finishing",                   3),
+    BitFlag::new("passive",                     4),
+    BitFlag::new("aura",                        5),
+    BitFlag::new("periodic",                    6),
+    BitFlag::new("prgstack",                    7),
+    BitFlag::new("InTown",                      8),
+    BitFlag::new("Kick",                        9),
+    BitFlag::new("InGame",                      10),
+    BitFlag::new("repeat",                      11),
+    BitFlag::new("stsuccessonly",               12),
+    BitFlag::new("stsounddelay",                13),
+    BitFlag::new("weaponsnd",                   14),
+    BitFlag::new("immediate",                   15),
+    BitFlag::new("noammo",                      16),
+    BitFlag::new("enhanceable",                 17),
+    BitFlag::new("durability",                  18),
+    BitFlag::new("UseAttackRate",               19),
+    BitFlag::new("TargetableOnly",              20),
+    BitFlag::new("SearchEnemyXY",               21),
+    BitFlag::new("SearchEnemyNear",             22),
+    BitFlag::new("SearchOpenXY",                23),
+    BitFlag::new("TargetCorpse",                24),
+    BitFlag::new("TargetPet",                   25),
+    BitFlag::new("TargetAlly",                  26),
+    BitFlag::new("TargetItem",                  27),
+    BitFlag::new("AttackNoMana",                28),
+    BitFlag::new("ItemTgtDo",                   29),
+    BitFlag::new("leftskill",                   30),
+    BitFlag::new("interrupt",                   31),
+];
+
+pub static SKILL_FLAGS_2: &[BitFlag] = &[
+    BitFlag::new("TgtPlaceCheck",               0),
+    BitFlag::new("ItemCheckStart",              1),
+    BitFlag::new("ItemCltCheckStart",           2),
+    BitFlag::new("general",                     3),
+    BitFlag::new("scroll",                      4),
+    BitFlag::new("UseManaOnDo",                 5),
+    BitFlag::new("warp",                        6),
+];
+
+pub static AURA_FILTER_FLAGS: &[BitFlag] = &[
+    BitFlag::new("FindPlayers",                 0),
+    BitFlag::new("FindMonsters",                1),
+    BitFlag::new("FindOnlyUndead",              2),
+    BitFlag::new("FindMissiles",                3),
+    BitFlag::new("FindObjects",                 4),
+    BitFlag::new("FindItems",                   5),
+    BitFlag::new("FindAttackable",              7),
+    BitFlag::new("NotInsideTowns",              8),
+    BitFlag::new("UseLineOfSight",              9),
+    BitFlag::new("FindSelectable",              10),
+    BitFlag::new("FindCorpses",                 12),
+    BitFlag::new("NotInsideTowns2",             13),
+    BitFlag::new("IgnoreBoss",                  14),
+    BitFlag::new("IgnoreAllies",                15),
+    BitFlag::new("IgnoreNPC",                   16),
+    BitFlag::new("IgnorePrimeEvil",             18),
+    BitFlag::new("IgnoreJustHitUnits",          19),
+];
 
 lazy_static!{
     pub static ref SKILLS: Vec<Field> = vec![
         field!("skill_id",                  i16,            0x00),
         field!("__pad_02",                  u16,            0x02),
-        field!("flags1",                    u32,            0x04),
-        field!("flags2",                    u32,            0x08),
-        field!("char_class",                i8,             0x0C),
+        field!("flags1",                    u32,            0x04).with_flags(SKILL_FLAGS_1),
+        field!("flags2",                    u32,            0x08).with_flags(SKILL_FLAGS_2),
+        field!("char_class",                i8,             0x0C).with_enum(&CHAR_CLASS),
         field!("__pad_0D",                  u8,             0x0D),
         field!("__pad_0E",                  u8,             0x0E),
         field!("__pad_0F",                  u8,             0x0F),
-        field!("anim",                      u8,             0x10),
-        field!("mon_anim",                  u8,             0x11),
+        field!("anim",                      u8,             0x10).with_enum(&PLAYER_MODE),
+        field!("mon_anim",                  u8,             0x11).with_enum(&MONSTER_MODE),
         field!("seq_trans",                 u8,             0x12),
         field!("seq_num",                   u8,             0x13),
         field!("range",                     u8,             0x14),
         field!("select_proc",               u8,             0x15),
         field!("seq_input",                 u8,             0x16),
         field!("__pad_17",                  u8,             0x17),
-        field!("itype_a_1",                 i16,            0x18),
-        field!("itype_a_2",                 i16,            0x1A),
-        field!("itype_a_3",                 i16,            0x1C),
-        field!("itype_b_1",                 i16,            0x1E),
-        field!("itype_b_2",                 i16,            0x20),
-        field!("itype_b_3",                 i16,            0x22),
-        field!("etype_a_1",                 i16,            0x24),
-        field!("etype_a_2",                 i16,            0x26),
-        field!("etype_b_1",                 i16,            0x28),
-        field!("etype_b_2",                 i16,            0x2A),
+        field!("itype_a_1",                 i16,            0x18).with_ref("itemtypes"),
+        field!("itype_a_2",                 i16,            0x1A).with_ref("itemtypes"),
+        field!("itype_a_3",                 i16,            0x1C).with_ref("itemtypes"),
+        field!("itype_b_1",                 i16,            0x1E).with_ref("itemtypes"),
+        field!("itype_b_2",                 i16,            0x20).with_ref("itemtypes"),
+        field!("itype_b_3",                 i16,            0x22).with_ref("itemtypes"),
+        field!("etype_a_1",                 i16,            0x24).with_ref("itemtypes"),
+        field!("etype_a_2",                 i16,            0x26).with_ref("itemtypes"),
+        field!("etype_b_1",                 i16,            0x28).with_ref("itemtypes"),
+        field!("etype_b_2",                 i16,            0x2A).with_ref("itemtypes"),
         field!("srv_start_func",            i16,            0x2C),
         field!("srv_do_func",               u16,            0x2E),
         field!("prg_func_1",                u16,            0x30),
         field!("prg_func_2",                u16,            0x32),
         field!("prg_func_3",                u16,            0x34),
         field!("__pad_36",                  u16,            0x36),
-        field!("prg_calc_1",                u32,            0x38),
-        field!("prg_calc_2",                u32,            0x3C),
-        field!("prg_calc_3",                u32,            0x40),
+        field!("prg_calc_1",                u32,            0x38).with_calc("skills"),
+        field!("prg_calc_2",                u32,            0x3C).with_calc("skills"),
+        field!("prg_calc_3",                u32,            0x40).with_calc("skills"),
         field!("prg_damange",               u8,             0x44),
         field!("__pad_45",                  u8,             0x45),
-        field!("srv_missile",               u16,            0x46),
-        field!("srv_missile_a",             u16,            0x48),
-        field!("srv_missile_b",             u16,            0x4A),
-        field!("srv_missile_c",             u16,            0x4C),
+        field!("srv_missile",               u16,            0x46).with_ref("missiles"),
+        field!("srv_missile_a",             u16,            0x48).with_ref("missiles"),
+        field!("srv_missile_b",             u16,            0x4A).with_ref("missiles"),
+        field!("srv_missile_c",             u16,            0x4C).with_ref("missiles"),
         field!("srv_overlay",               i16,            0x4E),
-        field!("aura_filter",               u32,            0x50),
-        field!("arua_stat_1",               i16,            0x54),
-        field!("arua_stat_2",               i16,            0x56),
-        field!("arua_stat_3",               i16,            0x58),
-        field!("arua_stat_4",               i16,            0x5A),
-        field!("arua_stat_5",               i16,            0x5C),
-        field!("arua_stat_6",               i16,            0x5E),
-        field!("arua_len_calc",             u32,            0x60),
-        field!("arua_range_calc",           u32,            0x64),
-        field!("arua_stat_calc_1",          i32,            0x68),
-        field!("arua_stat_calc_2",          i32,            0x6C),
-        field!("arua_stat_calc_3",          i32,            0x70),
-        field!("arua_stat_calc_4",          i32,            0x74),
-        field!("arua_stat_calc_5",          i32,            0x78),
-        field!("arua_stat_calc_6",          i32,            0x7C),
-        field!("arua_state",                i16,            0x80),
-        field!("arua_target_state",         i16,            0x82),
+        field!("aura_filter",               u32,            0x50).with_flags(AURA_FILTER_FLAGS),
+        field!("arua_stat_1",               i16,            0x54).with_ref("itemstatcost"),
+        field!("arua_stat_2",               i16,            0x56).with_ref("itemstatcost"),
+        field!("arua_stat_3",               i16,            0x58).with_ref("itemstatcost"),
+        field!("arua_stat_4",               i16,            0x5A).with_ref("itemstatcost"),
+        field!("arua_stat_5",               i16,            0x5C).with_ref("itemstatcost"),
+        field!("arua_stat_6",               i16,            0x5E).with_ref("itemstatcost"),
+        field!("arua_len_calc",             u32,            0x60).with_calc("skills"),
+        field!("arua_range_calc",           u32,            0x64).with_calc("skills"),
+        field!("arua_stat_calc_1",          i32,            0x68).with_calc("skills"),
+        field!("arua_stat_calc_2",          i32,            0x6C).with_calc("skills"),
+        field!("arua_stat_calc_3",          i32,            0x70).with_calc("skills"),
+        field!("arua_stat_calc_4",          i32,            0x74).with_calc("skills"),
+        field!("arua_stat_calc_5",          i32,            0x78).with_calc("skills"),
+        field!("arua_stat_calc_6",          i32,            0x7C).with_calc("skills"),
+        field!("arua_state",                i16,            0x80).with_ref("states"),
+        field!("arua_target_state",         i16,            0x82).with_ref("states"),
         field!("arua_event_1",              i16,            0x84),
         field!("arua_event_2",              i16,            0x86),
         field!("arua_event_3",              i16,            0x88),
@@ -71,19 +137,19 @@ lazy_static!{
         field!("arua_event_func_3",         i16,            0x8E),
         field!("arua_tgt_event",            u16,            0x90),
         field!("arua_tgt_event_func",       u16,            0x92),
-        field!("passive_state",             i16,            0x94),
-        field!("passive_itype",             i16,            0x96),
-        field!("passive_stat_1",            i16,            0x98),
-        field!("passive_stat_2",            i16,            0x9A),
-        field!("passive_stat_3",            i16,            0x9C),
-        field!("passive_stat_4",            i16,            0x9E),
-        field!("passive_stat_5",            i16,            0xA0),
+        field!("passive_state",             i16,            0x94).with_ref("states"),
+        field!("passive_itype",             i16,            0x96).with_ref("itemtypes"),
+        field!("passive_stat_1",            i16,            0x98).with_ref("itemstatcost"),
+        field!("passive_stat_2",            i16,            0x9A).with_ref("itemstatcost"),
+        field!("passive_stat_3",            i16,            0x9C).with_ref("itemstatcost"),
+        field!("passive_stat_4",            i16,            0x9E).with_ref("itemstatcost"),
+        field!("passive_stat_5",            i16,            0xA0).with_ref("itemstatcost"),
         field!("__pad_A2",                  u16,            0xA2),
-        field!("passive_calc_1",            u32,            0xA4),
-        field!("passive_calc_2",            u32,            0xA8),
-        field!("passive_calc_3",            u32,            0xAC),
-        field!("passive_calc_4",            u32,            0xB0),
-        field!("passive_calc_5",            u32,            0xB4),
+        field!("passive_calc_1",            u32,            0xA4).with_calc("skills"),
+        field!("passive_calc_2",            u32,            0xA8).with_calc("skills"),
+        field!("passive_calc_3",            u32,            0xAC).with_calc("skills"),
+        field!("passive_calc_4",            u32,            0xB0).with_calc("skills"),
+        field!("passive_calc_5",            u32,            0xB4).with_calc("skills"),
         field!("passive_event",             u16,            0xB8),
         field!("passive_event_func",        u16,            0xBA),
         field!("summon",                    u16,            0xBC),
@@ -96,18 +162,18 @@ lazy_static!{
         field!("sum_skill_4",               u16,            0xCA),
         field!("sum_skill_5",               u16,            0xCC),
         field!("__pad_CE",                  u16,            0xCE),
-        field!("sum_sk_calc_1",             u32,            0xD0),
-        field!("sum_sk_calc_2",             u32,            0xD4),
-        field!("sum_sk_calc_3",             u32,            0xD8),
-        field!("sum_sk_calc_4",             u32,            0xDC),
-        field!("sum_sk_calc_5",             u32,            0xE0),
+        field!("sum_sk_calc_1",             u32,            0xD0).with_calc("skills"),
+        field!("sum_sk_calc_2",             u32,            0xD4).with_calc("skills"),
+        field!("sum_sk_calc_3",             u32,            0xD8).with_calc("skills"),
+        field!("sum_sk_calc_4",             u32,            0xDC).with_calc("skills"),
+        field!("sum_sk_calc_5",             u32,            0xE0).with_calc("skills"),
         field!("sum_umod",                  i16,            0xE4),
         field!("sum_overlay",               i16,            0xE6),
-        field!("clt_missile",               i16,            0xE8),
-        field!("clt_missile_a",             u16,            0xEA),
-        field!("clt_missile_b",             u16,            0xEC),
-        field!("clt_missile_c",             u16,            0xEE),
-        field!("clt_missile_d",             u16,            0xF0),
+        field!("clt_missile",               i16,            0xE8).with_ref("missiles"),
+        field!("clt_missile_a",             u16,            0xEA).with_ref("missiles"),
+        field!("clt_missile_b",             u16,            0xEC).with_ref("missiles"),
+        field!("clt_missile_c",             u16,            0xEE).with_ref("missiles"),
+        field!("clt_missile_d",             u16,            0xF0).with_ref("missiles"),
         field!("clt_st_func",               u16,            0xF2),
         field!("clt_do_func",               u16,            0xF4),
         field!("ctl_prg_func_1",            u16,            0xF6),
@@ -125,9 +191,9 @@ lazy_static!{
         field!("prg_sound",                 u16,            0x10E),
         field!("ctl_overlay_a",             u16,            0x110),
         field!("ctl_overlay_b",             u16,            0x112),
-        field!("ctl_calc_1",                i32,            0x114),
-        field!("ctl_calc_2",                i32,            0x118),
-        field!("ctl_calc_3",                i32,            0x11C),
+        field!("ctl_calc_1",                i32,            0x114).with_calc("skills"),
+        field!("ctl_calc_2",                i32,            0x118).with_calc("skills"),
+        field!("ctl_calc_3",                i32,            0x11C).with_calc("skills"),
         field!("item_target",               u8,             0x120),
         field!("__pad_121",                 u8,             0x121),
         field!("item_cast_sound",           u16,            0x122),
@@ -138,10 +204,10 @@ lazy_static!{
         field!("result_flags",              u16,            0x12E),
         field!("hit_flags",                 u32,            0x130),
         field!("hit_class",                 u32,            0x134),
-        field!("calc_1",                    u32,            0x138),
-        field!("calc_2",                    u32,            0x13C),
-        field!("calc_3",                    u32,            0x140),
-        field!("calc_4",                    u32,            0x144),
+        field!("calc_1",                    u32,            0x138).with_calc("skills"),
+        field!("calc_2",                    u32,            0x13C).with_calc("skills"),
+        field!("calc_3",                    u32,            0x140).with_calc("skills"),
+        field!("calc_4",                    u32,            0x144).with_calc("skills"),
         field!("param_1",                   i32,            0x148),
         field!("param_2",                   i32,            0x14C),
         field!("param_3",                   i32,            0x150),
@@ -161,9 +227,9 @@ lazy_static!{
         field!("req_dex",                   u16,            0x178),
         field!("req_int",                   u16,            0x17A),
         field!("req_vit",                   u16,            0x17C),
-        field!("req_skill_1",               i16,            0x17E),
-        field!("req_skill_2",               i16,            0x180),
-        field!("req_skill_3",               i16,            0x182),
+        field!("req_skill_1",               i16,            0x17E).with_ref("skills"),
+        field!("req_skill_2",               i16,            0x180).with_ref("skills"),
+        field!("req_skill_3",               i16,            0x182).with_ref("skills"),
         field!("start_mana",                i16,            0x184),
         field!("min_mana",                  u16,            0x186),
         field!("mana_shift",                u8,             0x188),
@@ -173,11 +239,11 @@ lazy_static!{
         field!("attack_rank",               u8,             0x18E),
         field!("line_of_sight",             u8,             0x18F),
         field!("delay",                     u32,            0x190),
-        field!("skill_desc",                u16,            0x194),
+        field!("skill_desc",                u16,            0x194).with_ref("skilldesc"),
         field!("__pad_196",                 u16,            0x196),
         field!("to_hit",                    u32,            0x198),
         field!("lev_to_hit",                u32,            0x19C),
-        field!("to_hit_calc",               u32,            0x1A0),
+        field!("to_hit_calc",               u32,            0x1A0).with_calc("skills"),
         field!("to_hit_shift",              u8,             0x1A4),
         field!("src_dam",                   u8,             0x1A5),
         field!("__pad_1A6",                 u16,            0x1A6),
@@ -193,8 +259,8 @@ lazy_static!{
         field!("max_lvl_dam_3",             u32,            0x1CC),
         field!("max_lvl_dam_4",             u32,            0x1D0),
         field!("max_lvl_dam_5",             u32,            0x1D4),
-        field!("dmg_sym_per_calc",          u32,            0x1D8),
-        field!("e_type",                    u8,             0x1DC),
+        field!("dmg_sym_per_calc",          u32,            0x1D8).with_calc("skills"),
+        field!("e_type",                    u8,             0x1DC).with_enum(&ELEM_TYPE),
         field!("__pad_1DD",                 u8,             0x1DD),
         field!("__pad_1DE",                 u16,            0x1DE),
         field!("e_min",                     u32,            0x1E0),
@@ -209,17 +275,17 @@ lazy_static!{
         field!("e_max_lev_3",               u32,            0x204),
         field!("e_max_lev_4",               u32,            0x208),
         field!("e_max_lev_5",               u32,            0x20C),
-        field!("e_dmg_sym_per_calc",        u32,            0x210),
+        field!("e_dmg_sym_per_calc",        u32,            0x210).with_calc("skills"),
         field!("e_len",                     u32,            0x214),
         field!("e_lev_len_1",               u32,            0x218),
         field!("e_lev_len_2",               u32,            0x21C),
         field!("e_lev_len_3",               u32,            0x220),
-        field!("e_len_sym_per_calc",        u32,            0x224),
+        field!("e_len_sym_per_calc",        u32,            0x224).with_calc("skills"),
         field!("restrict",                  u8,             0x228),
         field!("__pad_229",                 u8,             0x229),
-        field!("state_1",                   i16,            0x22A),
-        field!("state_2",                   i16,            0x22C),
-        field!("state_3",                   i16,            0x22E),
+        field!("state_1",                   i16,            0x22A).with_ref("states"),
+        field!("state_2",                   i16,            0x22C).with_ref("states"),
+        field!("state_3",                   i16,            0x22E).with_ref("states"),
         field!("ai_type",                   u8,             0x230),
         field!("__pad_231",                 u8,             0x231),
         field!("ai_bonus",                  u16,            0x232),
@@ -235,34 +301,34 @@ lazy_static!{
         field!("list_row",                  u8,             0x05),
         field!("list_pool",                 u8,             0x06),
         field!("icon_cel",                  u8,             0x07),
-        field!("str_name",                  u16,            0x08),
-        field!("str_short",                 u16,            0x0A),
-        field!("str_long",                  u16,            0x0C),
-        field!("str_alt",                   u16,            0x0E),
-        field!("str_mana",                  u16,            0x10),
+        field!("str_name",                  StringId,       0x08),
+        field!("str_short",                 StringId,       0x0A),
+        field!("str_long",                  StringId,       0x0C),
+        field!("str_alt",                   StringId,       0x0E),
+        field!("str_mana",                  StringId,       0x10),
         field!("desc_dam",                  u16,            0x12),
         field!("desc_att",                  u16,            0x14),
         field!("__pad_16",                  u16,            0x16),
-        field!("dam_calc_1",                u32,            0x18),
-        field!("dam_calc_2",                u32,            0x1C),
-        field!("prg_dam_elem_1",            u8,             0x20),
-        field!("prg_dam_elem_2",            u8,             0x21),
-        field!("prg_dam_elem_3",            u8,             0x22),
+        field!("dam_calc_1",                u32,            0x18).with_calc("skilldesc"),
+        field!("dam_calc_2",                u32,            0x1C).with_calc("skilldesc"),
+        field!("prg_dam_elem_1",            u8,             0x20).with_enum(&ELEM_TYPE),
+        field!("prg_dam_elem_2",            u8,             0x21).with_enum(&ELEM_TYPE),
+        field!("prg_dam_elem_3",            u8,             0x22).with_enum(&ELEM_TYPE),
         field!("__pad_23",                  u8,             0x23),
-        field!("prog_dmg_min_1",            u32,            0x24),
-        field!("prog_dmg_min_2",            u32,            0x28),
-        field!("prog_dmg_min_3",            u32,            0x2C),
-        field!("prog_dmg_max_1",            u32,            0x30),
-        field!("prog_dmg_max_2",            u32,            0x34),
-        field!("prog_dmg_max_3",            u32,            0x38),
-        field!("desc_missile_1",            u16,            0x3C),
-        field!("desc_missile_2",            u16,            0x3E),
-        field!("desc_missile_3",            u16,            0x40),
+        field!("prog_dmg_min_1",            u32,            0x24).with_calc("skilldesc"),
+        field!("prog_dmg_min_2",            u32,            0x28).with_calc("skilldesc"),
+        field!("prog_dmg_min_3",            u32,            0x2C).with_calc("skilldesc"),
+        field!("prog_dmg_max_1",            u32,            0x30).with_calc("skilldesc"),
+        field!("prog_dmg_max_2",            u32,            0x34).with_calc("skilldesc"),
+        field!("prog_dmg_max_3",            u32,            0x38).with_calc("skilldesc"),
+        field!("desc_missile_1",            u16,            0x3C).with_ref("missiles"),
+        field!("desc_missile_2",            u16,            0x3E).with_ref("missiles"),
+        field!("desc_missile_3",            u16,            0x40).with_ref("missiles"),
         field!("desc_line",                 u8[17],         0x42),
         field!("__pad_53",                  u8,             0x53),
         field!("desc_text_a",               u16[17],        0x54),
         field!("desc_text_b",               u16[17],        0x76),
-        field!("desc_calc_a",               u32[17],        0x98),
-        field!("desc_calc_b",               u32[17],        0xDC),
+        field!("desc_calc_a",               u32[17],        0x98).with_calc("skilldesc"),
+        field!("desc_calc_b",               u32[17],        0xDC).with_calc("skilldesc"),
     ];
 }
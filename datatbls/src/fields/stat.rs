@@ -0,0 +1,74 @@
+use lazy_static::lazy_static;
+use crate::bin::*;
+use crate::field;
+
+pub static ITEM_STAT_FLAGS: &[BitFlag] = &[
+    BitFlag::new("Send Other",                  0),
+    BitFlag::new("Signed",                      1),
+    BitFlag::new("Damagerelated",               2),
+    BitFlag::new("itemspecific",                3),
+    BitFlag::new("direct",                      4),
+    BitFlag::new("updateanimrate",              9),
+    BitFlag::new("fmin",                        10),
+    BitFlag::new("fcallback",                   11),
+    BitFlag::new("Saved",                       12),
+    BitFlag::new("CSvSigned",                   13),
+];
+
+lazy_static!{
+    pub static ref ITEM_STAT_COST: Vec<Field> = vec![
+        field!("stat_id",                   u32,            0x00),
+        field!("flags",                     u32,            0x04).with_flags(ITEM_STAT_FLAGS),
+        field!("send_bits",                 u8,             0x08),
+        field!("send_param_bits",           u8,             0x09),
+        field!("csv_bits",                  u8,             0x0A),
+        field!("csv_param",                 u8,             0x0B),
+        field!("divide",                    u32,            0x0C),
+        field!("multiply",                  u32,            0x10),
+        field!("add",                       u32,            0x14),
+        field!("val_shift",                 u8,             0x18),
+        field!("save_bits",                 u8,             0x19),
+        field!("save_bits_109",             u8,             0x1A),
+        field!("__pad_1B",                  u8,             0x1B),
+        field!("save_add",                  i32,            0x1C),
+        field!("save_add_109",              i32,            0x20),
+        field!("save_param_bits",           u32,            0x24),
+        field!("__pad_28",                  u32,            0x28),
+        field!("min_accr",                  u32,            0x2C),
+        field!("encode",                    u8,             0x30),
+        field!("__pad_31",                  u8,             0x31),
+        field!("max_stat",                  u16,            0x32).with_ref("itemstatcost"),
+        field!("desc_priority",             u16,            0x34),
+        field!("desc_func",                 u8,             0x36),
+        field!("desc_val",                  u8,             0x37),
+        field!("desc_str_pos",              StringId,       0x38),
+        field!("desc_str_neg",              StringId,       0x3A),
+        field!("desc_str_2",                StringId,       0x3C),
+        field!("dgrp",                      u16,            0x3E),
+        field!("dgrp_func",                 u8,             0x40),
+        field!("dgrp_val",                  u8,             0x41),
+        field!("dgrp_str_pos",              StringId,       0x42),
+        field!("dgrp_str_neg",              StringId,       0x44),
+        field!("dgrp_str_2",                StringId,       0x46),
+        field!("item_event",                u16[2],         0x48),
+        field!("item_event_func",           u16[2],         0x4C),
+        field!("keep_zero",                 u8,             0x50),
+        field!("unk_51",                    u8[3],          0x51),
+        field!("op",                        u8,             0x54),
+        field!("op_param",                  u8,             0x55),
+        field!("op_base",                   u16,            0x56).with_ref("itemstatcost"),
+        field!("op_stat",                   u16[3],         0x58).with_ref("itemstatcost"),
+        field!("unk_5E",                    u8[226],        0x5E),
+        field!("stuff",                     u32,            0x140),
+    ];
+
+    pub static ref PROPERTIES: Vec<Field> = vec![
+        field!("prop",                      u16,            0x00),
+        field!("set",                       u8[7],          0x02),
+        field!("__pad_09",                  u8,             0x09),
+        field!("val",                       u16[7],         0x0A),
+        field!("func",                      u8[7],          0x18),
+        field!("__pad_1F",                  u8,             0x1F),
+        field!("stat",                      u16[7],         0x20).with_ref("itemstatcost"),
+    ];
+}
@@ -0,0 +1,258 @@
+use lazy_static::lazy_static;
+use crate::bin::*;
+use crate::field;
+
+pub static UNIQUE_ITEM_FLAGS: &[BitFlag] = &[
+    BitFlag::new("enabled",                     0),
+    BitFlag::new("nolimit",                     1),
+    BitFlag::new("carry1",                      2),
+    BitFlag::new("ladder",                      3),
+];
+
+lazy_static!{
+    pub static ref UNIQUE_ITEMS: Vec<Field> = vec![
+        field!("unique_id",                 u16,            0x00),
+        field!("index",                     str[32],        0x02),
+        field!("name_str",                  StringId,       0x22),
+        field!("version",                   u16,            0x24),
+        field!("__pad_26",                  u16,            0x26),
+        field!("code",                      ItemCode,       0x28),
+        field!("flags",                     u32,            0x2C).with_flags(UNIQUE_ITEM_FLAGS),
+        field!("rarity",                    u16,            0x30),
+        field!("__pad_32",                  u16,            0x32),
+        field!("lvl",                       u16,            0x34),
+        field!("lvl_req",                   u16,            0x36),
+        field!("chr_transform",             u8,             0x38),
+        field!("inv_transform",             u8,             0x39),
+        field!("flippy_file",               str[32],        0x3A),
+        field!("inv_file",                  str[32],        0x5A),
+        field!("__pad_7A",                  u16,            0x7A),
+        field!("cost_mult",                 u32,            0x7C),
+        field!("cost_add",                  u32,            0x80),
+        field!("drop_sound",                u16,            0x84),
+        field!("use_sound",                 u16,            0x86),
+        field!("drop_sfx_frame",            u32,            0x88),
+        field!("prop_1",                    i32,            0x8C).with_ref("properties"),
+        field!("par_1",                     i32,            0x90),
+        field!("min_1",                     i32,            0x94),
+        field!("max_1",                     i32,            0x98),
+        field!("prop_2",                    i32,            0x9C).with_ref("properties"),
+        field!("par_2",                     i32,            0xA0),
+        field!("min_2",                     i32,            0xA4),
+        field!("max_2",                     i32,            0xA8),
+        field!("prop_3",                    i32,            0xAC).with_ref("properties"),
+        field!("par_3",                     i32,            0xB0),
+        field!("min_3",                     i32,            0xB4),
+        field!("max_3",                     i32,            0xB8),
+        field!("prop_4",                    i32,            0xBC).with_ref("properties"),
+        field!("par_4",                     i32,            0xC0),
+        field!("min_4",                     i32,            0xC4),
+        field!("max_4",                     i32,            0xC8),
+        field!("prop_5",                    i32,            0xCC).with_ref("properties"),
+        field!("par_5",                     i32,            0xD0),
+        field!("min_5",                     i32,            0xD4),
+        field!("max_5",                     i32,            0xD8),
+        field!("prop_6",                    i32,            0xDC).with_ref("properties"),
+        field!("par_6",                     i32,            0xE0),
+        field!("min_6",                     i32,            0xE4),
+        field!("max_6",                     i32,            0xE8),
+        field!("prop_7",                    i32,            0xEC).with_ref("properties"),
+        field!("par_7",                     i32,            0xF0),
+        field!("min_7",                     i32,            0xF4),
+        field!("max_7",                     i32,            0xF8),
+        field!("prop_8",                    i32,            0xFC).with_ref("properties"),
+        field!("par_8",                     i32,            0x100),
+        field!("min_8",                     i32,            0x104),
+        field!("max_8",                     i32,            0x108),
+        field!("prop_9",                    i32,            0x10C).with_ref("properties"),
+        field!("par_9",                     i32,            0x110),
+        field!("min_9",                     i32,            0x114),
+        field!("max_9",                     i32,            0x118),
+        field!("prop_10",                   i32,            0x11C).with_ref("properties"),
+        field!("par_10",                    i32,            0x120),
+        field!("min_10",                    i32,            0x124),
+        field!("max_10",                    i32,            0x128),
+        field!("prop_11",                   i32,            0x12C).with_ref("properties"),
+        field!("par_11",                    i32,            0x130),
+        field!("min_11",                    i32,            0x134),
+        field!("max_11",                    i32,            0x138),
+        field!("prop_12",                   i32,            0x13C).with_ref("properties"),
+        field!("par_12",                    i32,            0x140),
+        field!("min_12",                    i32,            0x144),
+        field!("max_12",                    i32,            0x148),
+    ];
+
+    pub static ref SET_ITEMS: Vec<Field> = vec![
+        field!("set_item_id",               u16,            0x00),
+        field!("index",                     str[32],        0x02),
+        field!("version",                   u16,            0x22),
+        field!("name_str",                  StringId,       0x24),
+        field!("__pad_26",                  u16,            0x26),
+        field!("code",                      ItemCode,       0x28),
+        field!("set",                       i16,            0x2C).with_ref("sets"),
+        field!("set_items",                 i16,            0x2E),
+        field!("lvl",                       u16,            0x30),
+        field!("lvl_req",                   u16,            0x32),
+        field!("rarity",                    u32,            0x34),
+        field!("cost_mult",                 u32,            0x38),
+        field!("cost_add",                  u32,            0x3C),
+        field!("chr_transform",             u8,             0x40),
+        field!("inv_transform",             u8,             0x41),
+        field!("flippy_file",               str[32],        0x42),
+        field!("inv_file",                  str[32],        0x62),
+        field!("drop_sound",                u16,            0x82),
+        field!("use_sound",                 u16,            0x84),
+        field!("drop_sfx_frame",            u8,             0x86),
+        field!("add_func",                  u8,             0x87),
+        field!("prop_1",                    i32,            0x88).with_ref("properties"),
+        field!("par_1",                     i32,            0x8C),
+        field!("min_1",                     i32,            0x90),
+        field!("max_1",                     i32,            0x94),
+        field!("prop_2",                    i32,            0x98).with_ref("properties"),
+        field!("par_2",                     i32,            0x9C),
+        field!("min_2",                     i32,            0xA0),
+        field!("max_2",                     i32,            0xA4),
+        field!("prop_3",                    i32,            0xA8).with_ref("properties"),
+        field!("par_3",                     i32,            0xAC),
+        field!("min_3",                     i32,            0xB0),
+        field!("max_3",                     i32,            0xB4),
+        field!("prop_4",                    i32,            0xB8).with_ref("properties"),
+        field!("par_4",                     i32,            0xBC),
+        field!("min_4",                     i32,            0xC0),
+        field!("max_4",                     i32,            0xC4),
+        field!("prop_5",                    i32,            0xC8).with_ref("properties"),
+        field!("par_5",                     i32,            0xCC),
+        field!("min_5",                     i32,            0xD0),
+        field!("max_5",                     i32,            0xD4),
+        field!("prop_6",                    i32,            0xD8).with_ref("properties"),
+        field!("par_6",                     i32,            0xDC),
+        field!("min_6",                     i32,            0xE0),
+        field!("max_6",                     i32,            0xE4),
+        field!("prop_7",                    i32,            0xE8).with_ref("properties"),
+        field!("par_7",                     i32,            0xEC),
+        field!("min_7",                     i32,            0xF0),
+        field!("max_7",                     i32,            0xF4),
+        field!("prop_8",                    i32,            0xF8).with_ref("properties"),
+        field!("par_8",                     i32,            0xFC),
+        field!("min_8",                     i32,            0x100),
+        field!("max_8",                     i32,            0x104),
+        field!("prop_9",                    i32,            0x108).with_ref("properties"),
+        field!("par_9",                     i32,            0x10C),
+        field!("min_9",                     i32,            0x110),
+        field!("max_9",                     i32,            0x114),
+        field!("aprop_1a",                  i32,            0x118).with_ref("properties"),
+        field!("apar_1a",                   i32,            0x11C),
+        field!("amin_1a",                   i32,            0x120),
+        field!("amax_1a",                   i32,            0x124),
+        field!("aprop_1b",                  i32,            0x128).with_ref("properties"),
+        field!("apar_1b",                   i32,            0x12C),
+        field!("amin_1b",                   i32,            0x130),
+        field!("amax_1b",                   i32,            0x134),
+        field!("aprop_2a",                  i32,            0x138).with_ref("properties"),
+        field!("apar_2a",                   i32,            0x13C),
+        field!("amin_2a",                   i32,            0x140),
+        field!("amax_2a",                   i32,            0x144),
+        field!("aprop_2b",                  i32,            0x148).with_ref("properties"),
+        field!("apar_2b",                   i32,            0x14C),
+        field!("amin_2b",                   i32,            0x150),
+        field!("amax_2b",                   i32,            0x154),
+        field!("aprop_3a",                  i32,            0x158).with_ref("properties"),
+        field!("apar_3a",                   i32,            0x15C),
+        field!("amin_3a",                   i32,            0x160),
+        field!("amax_3a",                   i32,            0x164),
+        field!("aprop_3b",                  i32,            0x168).with_ref("properties"),
+        field!("apar_3b",                   i32,            0x16C),
+        field!("amin_3b",                   i32,            0x170),
+        field!("amax_3b",                   i32,            0x174),
+        field!("aprop_4a",                  i32,            0x178).with_ref("properties"),
+        field!("apar_4a",                   i32,            0x17C),
+        field!("amin_4a",                   i32,            0x180),
+        field!("amax_4a",                   i32,            0x184),
+        field!("aprop_4b",                  i32,            0x188).with_ref("properties"),
+        field!("apar_4b",                   i32,            0x18C),
+        field!("amin_4b",                   i32,            0x190),
+        field!("amax_4b",                   i32,            0x194),
+        field!("aprop_5a",                  i32,            0x198).with_ref("properties"),
+        field!("apar_5a",                   i32,            0x19C),
+        field!("amin_5a",                   i32,            0x1A0),
+        field!("amax_5a",                   i32,            0x1A4),
+        field!("aprop_5b",                  i32,            0x1A8).with_ref("properties"),
+        field!("apar_5b",                   i32,            0x1AC),
+        field!("amin_5b",                   i32,            0x1B0),
+        field!("amax_5b",                   i32,            0x1B4),
+    ];
+
+    pub static ref SETS: Vec<Field> = vec![
+        field!("set_id",                    u16,            0x00),
+        field!("name_str",                  StringId,       0x02),
+        field!("version",                   u16,            0x04),
+        field!("__pad_06",                  u16,            0x06),
+        field!("set_items",                 i32,            0x08),
+        field!("p_code_2a",                 i32,            0x0C).with_ref("properties"),
+        field!("p_param_2a",                i32,            0x10),
+        field!("p_min_2a",                  i32,            0x14),
+        field!("p_max_2a",                  i32,            0x18),
+        field!("p_code_2b",                 i32,            0x1C).with_ref("properties"),
+        field!("p_param_2b",                i32,            0x20),
+        field!("p_min_2b",                  i32,            0x24),
+        field!("p_max_2b",                  i32,            0x28),
+        field!("p_code_3a",                 i32,            0x2C).with_ref("properties"),
+        field!("p_param_3a",                i32,            0x30),
+        field!("p_min_3a",                  i32,            0x34),
+        field!("p_max_3a",                  i32,            0x38),
+        field!("p_code_3b",                 i32,            0x3C).with_ref("properties"),
+        field!("p_param_3b",                i32,            0x40),
+        field!("p_min_3b",                  i32,            0x44),
+        field!("p_max_3b",                  i32,            0x48),
+        field!("p_code_4a",                 i32,            0x4C).with_ref("properties"),
+        field!("p_param_4a",                i32,            0x50),
+        field!("p_min_4a",                  i32,            0x54),
+        field!("p_max_4a",                  i32,            0x58),
+        field!("p_code_4b",                 i32,            0x5C).with_ref("properties"),
+        field!("p_param_4b",                i32,            0x60),
+        field!("p_min_4b",                  i32,            0x64),
+        field!("p_max_4b",                  i32,            0x68),
+        field!("p_code_5a",                 i32,            0x6C).with_ref("properties"),
+        field!("p_param_5a",                i32,            0x70),
+        field!("p_min_5a",                  i32,            0x74),
+        field!("p_max_5a",                  i32,            0x78),
+        field!("p_code_5b",                 i32,            0x7C).with_ref("properties"),
+        field!("p_param_5b",                i32,            0x80),
+        field!("p_min_5b",                  i32,            0x84),
+        field!("p_max_5b",                  i32,            0x88),
+        field!("f_code_1",                  i32,            0x8C).with_ref("properties"),
+        field!("f_param_1",                 i32,            0x90),
+        field!("f_min_1",                   i32,            0x94),
+        field!("f_max_1",                   i32,            0x98),
+        field!("f_code_2",                  i32,            0x9C).with_ref("properties"),
+        field!("f_param_2",                 i32,            0xA0),
+        field!("f_min_2",                   i32,            0xA4),
+        field!("f_max_2",                   i32,            0xA8),
+        field!("f_code_3",                  i32,            0xAC).with_ref("properties"),
+        field!("f_param_3",                 i32,            0xB0),
+        field!("f_min_3",                   i32,            0xB4),
+        field!("f_max_3",                   i32,            0xB8),
+        field!("f_code_4",                  i32,            0xBC).with_ref("properties"),
+        field!("f_param_4",                 i32,            0xC0),
+        field!("f_min_4",                   i32,            0xC4),
+        field!("f_max_4",                   i32,            0xC8),
+        field!("f_code_5",                  i32,            0xCC).with_ref("properties"),
+        field!("f_param_5",                 i32,            0xD0),
+        field!("f_min_5",                   i32,            0xD4),
+        field!("f_max_5",                   i32,            0xD8),
+        field!("f_code_6",                  i32,            0xDC).with_ref("properties"),
+        field!("f_param_6",                 i32,            0xE0),
+        field!("f_min_6",                   i32,            0xE4),
+        field!("f_max_6",                   i32,            0xE8),
+        field!("f_code_7",                  i32,            0xEC).with_ref("properties"),
+        field!("f_param_7",                 i32,            0xF0),
+        field!("f_min_7",                   i32,            0xF4),
+        field!("f_max_7",                   i32,            0xF8),
+        field!("f_code_8",                  i32,            0xFC).with_ref("properties"),
+        field!("f_param_8",                 i32,            0x100),
+        field!("f_min_8",                   i32,            0x104),
+        field!("f_max_8",                   i32,            0x108),
+        field!("set_item_ptr",              u32[6],         0x10C),
+        field!("unk_124",                   u32,            0x124),
+    ];
+}
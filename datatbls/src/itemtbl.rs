@@ -1,36 +1,327 @@
 #![allow(unused)]
 
 use std::path::Path;
+use std::collections::HashMap;
 use std::io::{Seek, SeekFrom, BufReader, BufRead};
 use ml::io::{File, ReadExt, LittleEndian};
-use anyhow::Result;
+use anyhow::{Result, bail};
 use crate::bin::*;
 use crate::fields;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    Weapon,
+    Armor,
+    Misc,
+}
+
+impl ItemKind {
+    pub const ALL: [ItemKind; 3] = [ItemKind::Weapon, ItemKind::Armor, ItemKind::Misc];
+
+    /// the name `DataTblsManager::table` knows the kind's table by
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            ItemKind::Weapon => "weapons",
+            ItemKind::Armor => "armor",
+            ItemKind::Misc => "misc",
+        }
+    }
+}
+
+/// one entry of the item catalog: the game numbers items contiguously
+/// through weapons.bin, armor.bin and misc.bin in that order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemClass {
+    pub class_id    : u32,
+    pub kind        : ItemKind,
+    pub row         : usize,
+    pub code        : u32,
+}
+
+/// converts `"rin"` to the space padded code stored in the bins
+pub fn item_code(code: &str) -> u32 {
+    let mut b = [b' '; 4];
+    for (i, c) in code.bytes().take(4).enumerate() {
+        b[i] = c;
+    }
+    u32::from_le_bytes(b)
+}
+
+pub fn item_code_str(code: u32) -> String {
+    let b = code.to_le_bytes();
+    String::from_utf8_lossy(&b).trim_end_matches(|c| c == ' ' || c == char::from(0)).to_string()
+}
+
+fn normalize_code(code: u32) -> u32 {
+    let mut b = code.to_le_bytes();
+    for c in b.iter_mut() {
+        if *c == 0 {
+            *c = b' ';
+        }
+    }
+    u32::from_le_bytes(b)
+}
+
 pub struct ItemTable {
+    kind        : ItemKind,
     start_index : u32,
     records     : BinRecord,
 }
 
 impl ItemTable {
-    pub fn new(start_index: u32) -> Self {
+    pub fn new(kind: ItemKind) -> Self {
         Self{
-            start_index,
+            kind,
+            start_index: 0,
             records: BinRecord::new(),
         }
     }
 
     pub fn load<T: AsRef<Path>>(&mut self, path: T) -> Result<&BinRecord> {
         let p = path.as_ref().as_os_str().to_str().unwrap().to_string();
-        self.records = BinFile::open(path, &*fields::ITEMS)?.read()?;
+        self.records = BinFile::open(path, &fields::ITEMS)?.read()?;
         Ok(&self.records)
     }
 
+    /// like `load` with the bin already in memory
+    pub fn load_bytes(&mut self, data: Vec<u8>) -> Result<&BinRecord> {
+        self.records = BinFile::from_bytes(data, &fields::ITEMS).read()?;
+        Ok(&self.records)
+    }
+
+    pub fn kind(&self) -> ItemKind {
+        self.kind
+    }
+
+    /// class id of the first row, assigned by `ItemCatalog::build`
     pub fn start_index(&self) -> u32 {
         self.start_index
     }
 
+    pub fn len(&self) -> usize {
+        self.records.records().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn records(&self) -> &BinRecord {
         &self.records
     }
 }
+
+pub struct ItemCatalog {
+    classes : Vec<ItemClass>,
+    by_code : HashMap<u32, u32>,
+}
+
+impl Default for ItemCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ItemCatalog {
+    pub fn new() -> Self {
+        Self {
+            classes : Vec::new(),
+            by_code : HashMap::new(),
+        }
+    }
+
+    /// numbers the rows of `tables` (weapons, armor, misc) and records each
+    /// table's first class id in its `start_index`.
+    pub fn build(tables: [&mut ItemTable; 3]) -> Self {
+        let mut catalog = Self::new();
+
+        for table in tables {
+            table.start_index = catalog.classes.len() as u32;
+
+            for (row, item) in table.records().iter().enumerate() {
+                let class_id = catalog.classes.len() as u32;
+                let code = normalize_code(item.get("code").value.item_code());
+
+                catalog.classes.push(ItemClass{
+                    class_id,
+                    kind: table.kind,
+                    row,
+                    code,
+                });
+
+                // the game keeps the first item when codes collide
+                catalog.by_code.entry(code).or_insert(class_id);
+            }
+        }
+
+        catalog
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ItemClass> {
+        self.classes.iter()
+    }
+
+    pub fn get(&self, class_id: u32) -> Option<&ItemClass> {
+        self.classes.get(class_id as usize)
+    }
+
+    pub fn get_by_row(&self, kind: ItemKind, row: usize) -> Option<&ItemClass> {
+        let start = self.classes.iter().position(|c| c.kind == kind)?;
+        self.classes.get(start + row).filter(|c| c.kind == kind)
+    }
+
+    pub fn get_by_code(&self, code: u32) -> Option<&ItemClass> {
+        let class_id = self.by_code.get(&normalize_code(code))?;
+        self.get(*class_id)
+    }
+
+    pub fn get_by_code_str(&self, code: &str) -> Option<&ItemClass> {
+        self.get_by_code(item_code(code))
+    }
+
+    /// checks that class ids run contiguously through weapons -> armor -> misc
+    /// and that every mapping round trips.
+    pub fn validate(&self, tables: [&ItemTable; 3]) -> Result<()> {
+        let mut expect = 0u32;
+
+        for table in tables {
+            if table.start_index != expect {
+                bail!("{:?} start index is {}, expect {}", table.kind, table.start_index, expect);
+            }
+
+            for (row, item) in table.records().iter().enumerate() {
+                let class = self.get(expect).ok_or_else(|| anyhow::anyhow!("class id {expect} not exists"))?;
+
+                if class.kind != table.kind || class.row != row {
+                    bail!("class id {expect} maps to {:?}[{}], expect {:?}[{}]", class.kind, class.row, table.kind, row);
+                }
+
+                if self.get_by_row(table.kind, row).map(|c| c.class_id) != Some(expect) {
+                    bail!("{:?}[{}] does not map back to class id {}", table.kind, row, expect);
+                }
+
+                let code = normalize_code(item.get("code").value.item_code());
+                let by_code = self.get_by_code(code).ok_or_else(|| anyhow::anyhow!("code '{}' not exists", item_code_str(code)))?;
+
+                if by_code.code != code || by_code.class_id > expect {
+                    bail!("code '{}' maps to class id {}, expect <= {}", item_code_str(code), by_code.class_id, expect);
+                }
+
+                expect += 1;
+            }
+        }
+
+        if expect as usize != self.classes.len() {
+            bail!("catalog has {} classes, expect {}", self.classes.len(), expect);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// an items layout bin with one zeroed record per code
+    fn items_bin(codes: &[&str]) -> Vec<u8> {
+        let schema = Schema::new(&fields::ITEMS);
+        let offset = fields::ITEMS.iter().find(|f| f.name == "code").unwrap().offset as usize;
+
+        let mut b = (codes.len() as u32).to_le_bytes().to_vec();
+        for code in codes {
+            let mut rec = vec![0; schema.record_size()];
+            rec[offset..offset + 4].copy_from_slice(&item_code(code).to_le_bytes());
+            b.extend(rec);
+        }
+
+        b
+    }
+
+    fn tables() -> [ItemTable; 3] {
+        let mut weapon = ItemTable::new(ItemKind::Weapon);
+        let mut armor = ItemTable::new(ItemKind::Armor);
+        let mut misc = ItemTable::new(ItemKind::Misc);
+
+        weapon.load_bytes(items_bin(&["hax", "axe"])).unwrap();
+        armor.load_bytes(items_bin(&["cap", "skp", "hlm"])).unwrap();
+        misc.load_bytes(items_bin(&["rin", "amu"])).unwrap();
+
+        [weapon, armor, misc]
+    }
+
+    #[test]
+    fn numbers_weapons_then_armor_then_misc() {
+        let [mut weapon, mut armor, mut misc] = tables();
+        let catalog = ItemCatalog::build([&mut weapon, &mut armor, &mut misc]);
+
+        assert_eq!(catalog.len(), 7);
+        assert_eq!([weapon.start_index(), armor.start_index(), misc.start_index()], [0, 2, 5]);
+        catalog.validate([&weapon, &armor, &misc]).unwrap();
+
+        let expect = [
+            (ItemKind::Weapon, 0, "hax"), (ItemKind::Weapon, 1, "axe"),
+            (ItemKind::Armor, 0, "cap"), (ItemKind::Armor, 1, "skp"), (ItemKind::Armor, 2, "hlm"),
+            (ItemKind::Misc, 0, "rin"), (ItemKind::Misc, 1, "amu"),
+        ];
+
+        for (class_id, (kind, row, code)) in expect.into_iter().enumerate() {
+            let class = catalog.get(class_id as u32).unwrap();
+            assert_eq!((class.kind, class.row, class.code), (kind, row, item_code(code)));
+            assert_eq!(catalog.get_by_row(kind, row), Some(class));
+            assert_eq!(catalog.get_by_code_str(code), Some(class));
+        }
+
+        assert_eq!(catalog.get(7), None);
+        assert_eq!(catalog.get_by_row(ItemKind::Weapon, 2), None);
+    }
+
+    #[test]
+    fn codes_are_nul_or_space_padded() {
+        let [mut weapon, mut armor, mut misc] = tables();
+        let catalog = ItemCatalog::build([&mut weapon, &mut armor, &mut misc]);
+
+        let nul_padded = u32::from_le_bytes(*b"rin\0");
+        assert_eq!(catalog.get_by_code(nul_padded).map(|c| c.class_id), Some(5));
+        assert_eq!(item_code_str(nul_padded), "rin");
+    }
+
+    #[test]
+    fn first_item_keeps_a_colliding_code() {
+        let mut weapon = ItemTable::new(ItemKind::Weapon);
+        let mut armor = ItemTable::new(ItemKind::Armor);
+        let mut misc = ItemTable::new(ItemKind::Misc);
+
+        weapon.load_bytes(items_bin(&["dup"])).unwrap();
+        armor.load_bytes(items_bin(&[])).unwrap();
+        misc.load_bytes(items_bin(&["dup"])).unwrap();
+
+        let catalog = ItemCatalog::build([&mut weapon, &mut armor, &mut misc]);
+
+        assert_eq!(misc.start_index(), 1);
+        assert_eq!(catalog.get_by_code_str("dup").map(|c| c.class_id), Some(0));
+        catalog.validate([&weapon, &armor, &misc]).unwrap();
+    }
+
+    #[test]
+    fn validate_reports_a_broken_numbering() {
+        let [mut weapon, mut armor, mut misc] = tables();
+        let catalog = ItemCatalog::build([&mut weapon, &mut armor, &mut misc]);
+
+        armor.start_index = 3;
+        let err = catalog.validate([&weapon, &armor, &misc]).unwrap_err();
+        assert_eq!(err.to_string(), "Armor start index is 3, expect 2");
+
+        // tables given out of order
+        armor.start_index = 2;
+        assert!(catalog.validate([&armor, &weapon, &misc]).is_err());
+    }
+}
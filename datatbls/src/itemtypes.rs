@@ -0,0 +1,186 @@
+#![allow(unused)]
+
+//! the itemtypes.bin hierarchy, every type has up to two parents in `equiv`.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use anyhow::Result;
+
+use crate::bin::*;
+use crate::itemtbl::{item_code, item_code_str};
+
+pub struct ItemTypes {
+    codes   : Vec<u32>,
+    parents : Vec<Vec<usize>>,
+    by_code : HashMap<u32, usize>,
+}
+
+impl Default for ItemTypes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ItemTypes {
+    pub fn new() -> Self {
+        Self {
+            codes   : Vec::new(),
+            parents : Vec::new(),
+            by_code : HashMap::new(),
+        }
+    }
+
+    pub fn build(types: &BinRecord) -> Self {
+        let mut tree = Self::new();
+
+        for (row, record) in types.iter().enumerate() {
+            let code = record.get("code").value.item_code();
+            let parents = record.get("equiv").ref_rows().into_iter().flatten().filter(|p| *p != row).collect();
+
+            tree.codes.push(code);
+            tree.parents.push(parents);
+            tree.by_code.entry(code).or_insert(row);
+        }
+
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    pub fn code(&self, row: usize) -> Option<String> {
+        self.codes.get(row).map(|c| item_code_str(*c))
+    }
+
+    /// the row of type `code`, e.g. `"weap"`
+    pub fn get_by_code(&self, code: &str) -> Option<usize> {
+        self.by_code.get(&item_code(code)).copied()
+    }
+
+    /// the direct parents of `row`
+    pub fn parents(&self, row: usize) -> &[usize] {
+        self.parents.get(row).map(|p| p.as_slice()).unwrap_or(&[])
+    }
+
+    /// `row` and every type it descends from, nearest first
+    pub fn ancestors(&self, row: usize) -> Vec<usize> {
+        let mut ancestors = Vec::new();
+        let mut queue = VecDeque::from([row]);
+
+        while let Some(row) = queue.pop_front() {
+            if row >= self.len() || ancestors.contains(&row) {
+                continue;
+            }
+
+            ancestors.push(row);
+            queue.extend(self.parents[row].iter().copied());
+        }
+
+        ancestors
+    }
+
+    /// whether `row` is `ancestor` or descends from it through `equiv`
+    pub fn is_a(&self, row: usize, ancestor: usize) -> bool {
+        self.ancestors(row).contains(&ancestor)
+    }
+
+    /// like `is_a` with the ancestor given by code, false for unknown codes
+    pub fn is_a_code(&self, row: usize, code: &str) -> bool {
+        self.get_by_code(code).map(|a| self.is_a(row, a)).unwrap_or(false)
+    }
+
+    /// the hierarchy as a graphviz digraph, edges point from a type to its parents
+    pub fn to_dot(&self) -> String {
+        let mut lines = Vec::<String>::new();
+
+        lines.push("digraph itemtypes {".into());
+        lines.push("    rankdir=BT;".into());
+        lines.push("    node [shape=box];".into());
+
+        for row in 0..self.len() {
+            lines.push(format!("    t{row} [label=\"{}\"];", self.code(row).unwrap().replace('"', "\\\"")));
+        }
+
+        for (row, parents) in self.parents.iter().enumerate() {
+            for parent in parents.iter() {
+                lines.push(format!("    t{row} -> t{parent};"));
+            }
+        }
+
+        lines.push("}".into());
+
+        lines.join("\n")
+    }
+
+    pub fn dump_dot(&self, file_name: &str) -> Result<()> {
+        let mut dot = std::fs::File::create(file_name)?;
+        dot.write_all(self.to_dot().as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields;
+    use crate::bin::tests::zeroed;
+
+    const NONE: u16 = u16::MAX;
+
+    fn tree(types: &[(&str, [u16; 2])]) -> ItemTypes {
+        let mut bin = zeroed(&fields::ITEM_TYPES, types.len());
+
+        for (row, (code, equiv)) in types.iter().enumerate() {
+            let t = bin.get_mut(row).unwrap();
+            t.set("code", Value::ItemCode(item_code(code)));
+            t.set("equiv", Value::U16Array(equiv.to_vec()));
+        }
+
+        ItemTypes::build(&bin)
+    }
+
+    #[test]
+    fn ancestors_follow_both_parents_nearest_first() {
+        // jave is both a mele weapon and a throwing weapon, both are weap
+        let tree = tree(&[
+            ("weap", [NONE, NONE]),
+            ("mele", [0, NONE]),
+            ("thro", [0, NONE]),
+            ("jave", [1, 2]),
+        ]);
+
+        assert_eq!(tree.parents(3), [1, 2]);
+        assert_eq!(tree.ancestors(3), [3, 1, 2, 0]);
+        assert!(tree.is_a_code(3, "thro"));
+        assert!(!tree.is_a_code(1, "thro"));
+        assert!(!tree.is_a_code(3, "armo"));
+    }
+
+    #[test]
+    fn ancestors_stop_at_cycles() {
+        // a lists b, b lists a back, c lists itself
+        let tree = tree(&[
+            ("a", [1, NONE]),
+            ("b", [0, NONE]),
+            ("c", [2, 0]),
+        ]);
+
+        assert_eq!(tree.ancestors(0), [0, 1]);
+        assert_eq!(tree.ancestors(2), [2, 0, 1]);
+        assert_eq!(tree.parents(2), [0]);
+    }
+
+    #[test]
+    fn unknown_rows_have_no_ancestors() {
+        let tree = tree(&[("weap", [5, NONE])]);
+
+        assert_eq!(tree.ancestors(0), [0]);
+        assert!(tree.ancestors(1).is_empty());
+    }
+}
@@ -0,0 +1,692 @@
+#![allow(unused)]
+
+//! infers the layout of a .bin from the .txt it was compiled from, for mods
+//! whose tables no longer match `fields`.
+//!
+//! the record size comes from the record count at the start of the bin. every
+//! column is then searched for across the records, the most distinct columns
+//! first, and takes the first free offset where each row reads back as its
+//! cell. numbers are tried as the narrowest type holding them and widened
+//! later, text as an item code, a nul terminated string, or a number each
+//! distinct text maps to (a reference or a string id). 0/1 columns left over
+//! are searched bit by bit for packed flags. the result is a draft, columns
+//! with few distinct values may land on a byte that only happens to match.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use anyhow::{Result, bail};
+
+use crate::bin::{FieldType, Primitive};
+use crate::itemtbl::item_code;
+
+/// a column or a set of packed flags placed in the record
+pub struct Placement {
+    pub name    : String,
+    pub ty      : FieldType,
+    pub offset  : usize,
+    /// the column of each bit of a flag field
+    pub flags   : Vec<(String, u32)>,
+    pub note    : Option<String>,
+}
+
+impl Placement {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.ty.size()
+    }
+}
+
+/// a run of bytes no column explains
+pub struct Unexplained {
+    pub range   : Range<usize>,
+    /// the bytes of every record are zero
+    pub zero    : bool,
+    /// the bytes are the same in every record
+    pub constant: bool,
+}
+
+pub struct Layout {
+    pub record_size : usize,
+    /// the records the rows were matched against
+    pub records     : usize,
+    /// sorted by offset, covering every byte together with `unexplained`
+    pub placed      : Vec<Placement>,
+    /// columns with the reason they could not be placed
+    pub unplaced    : Vec<(String, String)>,
+    pub unexplained : Vec<Unexplained>,
+    /// how the rows were matched to the records
+    pub notes       : Vec<String>,
+}
+
+/// the cells of a column
+enum Cells {
+    /// every cell is empty or an integer, empty cells read as 0
+    Numbers(Vec<i64>),
+    Text(Vec<String>),
+}
+
+impl Cells {
+    fn parse(cells: Vec<&str>) -> Cells {
+        let numbers: Option<Vec<i64>> = cells.iter()
+            .map(|c| if c.is_empty() { Some(0) } else { c.parse().ok() })
+            .collect();
+
+        match numbers {
+            Some(numbers) => Cells::Numbers(numbers),
+            None => Cells::Text(cells.into_iter().map(|c| c.to_string()).collect()),
+        }
+    }
+
+    fn distinct(&self) -> usize {
+        match self {
+            Cells::Numbers(v) => v.iter().collect::<std::collections::HashSet<_>>().len(),
+            Cells::Text(v) => v.iter().collect::<std::collections::HashSet<_>>().len(),
+        }
+    }
+}
+
+struct Column {
+    name    : String,
+    cells   : Cells,
+}
+
+/// the rust identifier a column header becomes, e.g. `MinDam` -> `mindam`, `*eol` -> `eol`
+pub fn field_name(header: &str) -> String {
+    let mut name = String::new();
+
+    for c in header.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+
+    let name = name.trim_end_matches('_').to_string();
+    match name.chars().next() {
+        None => "col".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        _ => name,
+    }
+}
+
+/// a scalar of type `p` at `offset`, read as a number
+fn read(rec: &[u8], offset: usize, p: Primitive) -> i64 {
+    FieldType::Primitive(p).decode(&rec[offset..offset + p.size()]).as_i64().unwrap()
+}
+
+/// the narrowest integer type holding every value
+fn narrowest(values: &[i64]) -> Option<Primitive> {
+    let min = values.iter().copied().min()?;
+    let max = values.iter().copied().max()?;
+
+    let p = match (min, max) {
+        (0.., ..=0xFF) => Primitive::UInt8,
+        (0.., ..=0xFFFF) => Primitive::UInt16,
+        (0.., ..=0xFFFF_FFFF) => Primitive::UInt32,
+        (-0x80.., ..=0x7F) => Primitive::Int8,
+        (-0x8000.., ..=0x7FFF) => Primitive::Int16,
+        (-0x8000_0000.., ..=0x7FFF_FFFF) => Primitive::Int32,
+        _ => return None,
+    };
+
+    Some(p)
+}
+
+/// the type of the same signedness twice as wide
+fn wider(p: Primitive) -> Option<Primitive> {
+    match p {
+        Primitive::UInt8 => Some(Primitive::UInt16),
+        Primitive::UInt16 => Some(Primitive::UInt32),
+        Primitive::Int8 => Some(Primitive::Int16),
+        Primitive::Int16 => Some(Primitive::Int32),
+        _ => None,
+    }
+}
+
+/// the record bytes each row is matched against, with the bookkeeping of
+/// which bytes are taken
+struct Search<'a> {
+    records : Vec<&'a [u8]>,
+    size    : usize,
+    taken   : Vec<bool>,
+}
+
+impl Search<'_> {
+    fn is_free(&self, range: Range<usize>) -> bool {
+        range.end <= self.size && self.taken[range].iter().all(|t| !t)
+    }
+
+    fn take(&mut self, range: Range<usize>) {
+        self.taken[range].iter_mut().for_each(|t| *t = true);
+    }
+
+    /// the free offsets of a `size` byte field where `matches` holds, naturally
+    /// aligned offsets first
+    fn candidates(&self, size: usize, matches: impl Fn(usize) -> bool) -> Vec<usize> {
+        if size > self.size {
+            return Vec::new();
+        }
+
+        let mut found: Vec<usize> = (0..=self.size - size)
+            .filter(|&o| self.is_free(o..o + size))
+            .filter(|&o| matches(o))
+            .collect();
+
+        found.sort_by_key(|o| (!o.is_multiple_of(size), *o));
+        found
+    }
+
+    /// whether `matches` holds for every record and the row it was compiled from
+    fn every_row(&self, matches: impl Fn(usize, &[u8]) -> bool) -> bool {
+        self.records.iter().enumerate().all(|(row, rec)| matches(row, rec))
+    }
+
+    fn numbers(&self, values: &[i64]) -> Option<(FieldType, Vec<usize>)> {
+        let p = narrowest(values)?;
+        let found = self.candidates(p.size(), |o| self.every_row(|row, rec| read(rec, o, p) == values[row]));
+
+        Some((FieldType::Primitive(p), found))
+    }
+
+    fn item_codes(&self, text: &[String]) -> Vec<usize> {
+        if text.iter().any(|t| t.len() > 4) {
+            return Vec::new();
+        }
+
+        let codes: Vec<u32> = text.iter().map(|t| item_code(t)).collect();
+
+        self.candidates(4, |o| self.every_row(|row, rec| {
+            let code = read(rec, o, Primitive::UInt32) as u32;
+            let code = u32::from_le_bytes(code.to_le_bytes().map(|b| if b == 0 { b' ' } else { b }));
+            code == codes[row]
+        }))
+    }
+
+    /// offsets where each row's text starts, followed by a nul
+    fn strings(&self, text: &[String]) -> Vec<usize> {
+        let len = text.iter().map(|t| t.len()).max().unwrap_or(0);
+
+        self.candidates(len + 1, |o| self.every_row(|row, rec| {
+            let t = text[row].as_bytes();
+            rec[o..].starts_with(t) && rec[o + t.len()] == 0
+        }))
+    }
+
+    /// offsets of a number that is the same for every row with the same text
+    /// and differs between texts
+    fn mapped(&self, text: &[String], p: Primitive) -> Vec<usize> {
+        self.candidates(p.size(), |o| {
+            let mut by_text = HashMap::new();
+            let mut by_value = HashMap::new();
+
+            self.records.iter().zip(text).all(|(rec, t)| {
+                let v = read(rec, o, p);
+                *by_text.entry(t.as_str()).or_insert(v) == v && *by_value.entry(v).or_insert(t.as_str()) == t.as_str()
+            })
+        })
+    }
+}
+
+/// `first` and how many other offsets matched
+fn ambiguity(found: &[usize]) -> Option<String> {
+    match found.len() {
+        0 | 1 => None,
+        n => {
+            let others: Vec<String> = found[1..].iter().take(3).map(|o| format!("0x{o:02X}")).collect();
+            let more = if n > 4 { ", ..." } else { "" };
+            Some(format!("also matches {}{more}", others.join(", ")))
+        },
+    }
+}
+
+/// splits the .txt into columns of the rows that were compiled into records,
+/// with the number of those rows
+fn columns(txt: &str, count: usize, notes: &mut Vec<String>) -> Result<(Vec<Column>, usize)> {
+    let mut lines = txt.lines().map(|l| l.trim_end_matches('\r')).filter(|l| !l.is_empty());
+
+    let header: Vec<&str> = match lines.next() {
+        Some(header) => header.split('\t').collect(),
+        None => bail!("txt is empty"),
+    };
+
+    let mut rows: Vec<Vec<&str>> = lines.map(|l| l.split('\t').collect()).collect();
+
+    if rows.len() != count {
+        let before = rows.len();
+        rows.retain(|r| !r[0].eq_ignore_ascii_case("expansion"));
+
+        if rows.len() != before {
+            notes.push(format!("skipped {} `Expansion` rows", before - rows.len()));
+        }
+    }
+
+    if rows.len() != count {
+        notes.push(format!("txt has {} rows for {count} records, matched the first {}", rows.len(), rows.len().min(count)));
+        rows.truncate(count);
+    }
+
+    if rows.is_empty() {
+        bail!("txt has no rows to match the records against");
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    let columns = header.iter().enumerate().map(|(i, h)| {
+        let mut name = field_name(h);
+        let n = seen.entry(name.clone()).or_insert(0);
+        *n += 1;
+        if *n > 1 {
+            name = format!("{name}_{n}");
+        }
+
+        let cells = rows.iter().map(|r| r.get(i).map_or("", |c| c.trim())).collect();
+        Column{name, cells: Cells::parse(cells)}
+    });
+
+    Ok((columns.collect(), rows.len()))
+}
+
+/// infers the layout of `bin`'s records from the `txt` it was compiled from
+pub fn infer(bin: &[u8], txt: &str) -> Result<Layout> {
+    if bin.len() < 4 {
+        bail!("bin is {} bytes, too short for a record count", bin.len());
+    }
+
+    let count = u32::from_le_bytes(bin[..4].try_into().unwrap()) as usize;
+    let body = &bin[4..];
+
+    if count == 0 {
+        bail!("bin has no records");
+    }
+
+    if body.is_empty() {
+        bail!("bin has {count} records but no record bytes");
+    }
+
+    if !body.len().is_multiple_of(count) {
+        bail!("{} bytes do not split into {count} records", body.len());
+    }
+
+    let size = body.len() / count;
+    let mut notes = Vec::new();
+    let (mut columns, rows) = columns(txt, count, &mut notes)?;
+
+    let mut search = Search{
+        records : body.chunks_exact(size).take(rows).collect(),
+        size,
+        taken   : vec![false; size],
+    };
+
+    // the most distinct columns are the least likely to match by chance
+    columns.sort_by_key(|c| std::cmp::Reverse(c.cells.distinct()));
+
+    let mut placed = Vec::new();
+    let mut unplaced = Vec::new();
+    let mut booleans = Vec::new();
+    // placements of number columns, which may be widened
+    let mut numeric = Vec::new();
+
+    for column in columns.iter() {
+        let distinct = column.cells.distinct();
+
+        let (ty, found, note) = match &column.cells {
+            Cells::Numbers(values) => match search.numbers(values) {
+                Some((ty, found)) => (ty, found, None),
+                None => {
+                    unplaced.push((column.name.clone(), "values do not fit in 32 bits".to_string()));
+                    continue;
+                },
+            },
+            Cells::Text(text) => {
+                let codes = search.item_codes(text);
+                let strings = if codes.is_empty() { search.strings(text) } else { Vec::new() };
+
+                if !codes.is_empty() {
+                    (FieldType::Primitive(Primitive::ItemCode), codes, None)
+                } else if !strings.is_empty() {
+                    let len = text.iter().map(|t| t.len()).max().unwrap_or(0);
+                    (FieldType::String(len + 1), strings, None)
+                } else {
+                    let note = Some("each text maps to one value, a reference or a string id".to_string());
+                    let mut mapped = (FieldType::Primitive(Primitive::UInt16), Vec::new(), note);
+
+                    if distinct > 1 {
+                        for p in [Primitive::UInt16, Primitive::UInt8, Primitive::UInt32] {
+                            let found = search.mapped(text, p);
+                            if !found.is_empty() {
+                                mapped.0 = FieldType::Primitive(p);
+                                mapped.1 = found;
+                                break;
+                            }
+                        }
+                    }
+
+                    mapped
+                }
+            },
+        };
+
+        let is_boolean = matches!(&column.cells, Cells::Numbers(v) if distinct == 2 && v.iter().all(|v| *v == 0 || *v == 1));
+
+        // a constant matches wherever the bytes are, unless that is a single place
+        if found.is_empty() || (distinct == 1 && found.len() > 1) {
+            if is_boolean {
+                booleans.push(column);
+                continue;
+            }
+
+            let reason = match (&column.cells, found.len()) {
+                (Cells::Numbers(v), 0) => format!("no offset reads back {} of every row", FieldType::Primitive(narrowest(v).unwrap())),
+                (Cells::Text(_), 0) if distinct == 1 => "the same text in every row".to_string(),
+                (Cells::Text(_), 0) => "no item code, string or number matches the text".to_string(),
+                (Cells::Numbers(v), n) => format!("{} in every row, matches {n} offsets", v[0]),
+                (Cells::Text(_), n) => format!("the same text in every row, matches {n} offsets"),
+            };
+
+            unplaced.push((column.name.clone(), reason));
+            continue;
+        }
+
+        let offset = found[0];
+        let placement = Placement{
+            name    : column.name.clone(),
+            ty,
+            offset,
+            flags   : Vec::new(),
+            note    : [note, ambiguity(&found)].into_iter().flatten().reduce(|a, b| format!("{a}, {b}")),
+        };
+
+        search.take(placement.range());
+
+        if let Cells::Numbers(values) = &column.cells {
+            numeric.push((placed.len(), values));
+        }
+
+        placed.push(placement);
+    }
+
+    // 0/1 columns with no byte of their own, as bits of free bytes
+    let mut bits: Vec<(usize, u32, String)> = Vec::new();
+
+    for column in booleans {
+        let Cells::Numbers(values) = &column.cells else { unreachable!() };
+
+        let found = (0..size)
+            .filter(|&o| search.is_free(o..o + 1))
+            .flat_map(|o| (0..8).map(move |b| (o, b)))
+            .find(|&(o, b)| {
+                !bits.iter().any(|(bo, bb, _)| *bo == o && *bb == b)
+                    && search.records.iter().zip(values.iter()).all(|(rec, v)| (rec[o] >> b & 1) as i64 == *v)
+            });
+
+        match found {
+            Some((o, b)) => bits.push((o, b, column.name.clone())),
+            None => unplaced.push((column.name.clone(), "no byte or bit matches the 0/1 values".to_string())),
+        }
+    }
+
+    // a flag field is the aligned dword of its bits when nothing else is in it
+    bits.sort_by_key(|(o, b, _)| (*o, *b));
+    let mut flag_fields: Vec<Placement> = Vec::new();
+
+    for (o, b, name) in bits {
+        let word = o & !3;
+        let (offset, ty) = if search.is_free(word..word + 4) {
+            (word, FieldType::Primitive(Primitive::UInt32))
+        } else {
+            (o, FieldType::Primitive(Primitive::UInt8))
+        };
+
+        let bit = (o - offset) as u32 * 8 + b;
+
+        match flag_fields.iter_mut().find(|f| f.offset == offset) {
+            Some(f) => f.flags.push((name, bit)),
+            None => flag_fields.push(Placement{
+                name    : format!("flags_{offset:02X}"),
+                ty,
+                offset,
+                flags   : vec![(name, bit)],
+                note    : None,
+            }),
+        }
+    }
+
+    for f in flag_fields {
+        search.take(f.range());
+        placed.push(f);
+    }
+
+    // numbers narrower than their field leave its upper bytes behind
+    for (i, values) in numeric {
+        let FieldType::Primitive(mut p) = placed[i].ty else { unreachable!() };
+        let offset = placed[i].offset;
+
+        while let Some(w) = wider(p) {
+            let grown = offset + p.size()..offset + w.size();
+            let fits = offset.is_multiple_of(w.size())
+                && search.is_free(grown.clone())
+                && search.records.iter().zip(values.iter()).all(|(rec, v)| read(rec, offset, w) == *v);
+
+            if !fits {
+                break;
+            }
+
+            search.take(grown);
+            p = w;
+        }
+
+        placed[i].ty = FieldType::Primitive(p);
+    }
+
+    // strings run up to the next taken byte
+    for f in placed.iter_mut() {
+        if let FieldType::String(len) = f.ty {
+            let end = (f.offset + len..size).find(|o| search.taken[*o]).unwrap_or(size);
+            search.take(f.offset + len..end);
+            f.ty = FieldType::String(end - f.offset);
+        }
+    }
+
+    placed.sort_by_key(|f| f.offset);
+
+    let mut unexplained = Vec::new();
+    let mut o = 0;
+
+    while o < size {
+        if search.taken[o] {
+            o += 1;
+            continue;
+        }
+
+        let end = (o..size).find(|o| search.taken[*o]).unwrap_or(size);
+        let first = search.records.first().map_or(&[][..], |r| &r[o..end]);
+
+        unexplained.push(Unexplained{
+            range   : o..end,
+            zero    : search.records.iter().all(|r| r[o..end].iter().all(|b| *b == 0)),
+            constant: search.records.iter().all(|r| &r[o..end] == first),
+        });
+
+        o = end;
+    }
+
+    Ok(Layout{
+        record_size : size,
+        records     : search.records.len(),
+        placed,
+        unplaced,
+        unexplained,
+        notes,
+    })
+}
+
+impl Layout {
+    /// the draft schema named `name`, in the format of `fields`. unexplained
+    /// bytes become `__pad_` fields when always zero and `unk_` fields otherwise
+    pub fn to_rust(&self, name: &str) -> String {
+        let mut out = String::new();
+        let mut lines = Vec::new();
+        let mut fields: Vec<(usize, String)> = Vec::new();
+
+        for f in self.placed.iter() {
+            let mut line = format!("{:<29}{:<16}0x{:02X})", format!("\"{}\",", f.name), format!("{},", f.ty), f.offset);
+
+            if !f.flags.is_empty() {
+                let flags = format!("{name}_FLAGS_{:02X}", f.offset);
+                out += &format!("pub static {flags}: &[BitFlag] = &[\n");
+                for (column, bit) in f.flags.iter() {
+                    out += &format!("    BitFlag::new({:<31}{bit}),\n", format!("\"{column}\","));
+                }
+                out += "];\n\n";
+                line += &format!(".with_flags({flags})");
+            }
+
+            line += ",";
+            if let Some(note) = &f.note {
+                line += &format!(" // {note}");
+            }
+
+            fields.push((f.offset, line));
+        }
+
+        for u in self.unexplained.iter() {
+            let prefix = if u.zero { "__pad" } else { "unk" };
+            let len = u.range.len();
+            let ty = if len == 1 { "u8".to_string() } else { format!("u8[{len}]") };
+
+            fields.push((u.range.start, format!("{:<29}{:<16}0x{:02X}),", format!("\"{prefix}_{:02X}\",", u.range.start), format!("{ty},"), u.range.start)));
+        }
+
+        fields.sort_by_key(|(offset, _)| *offset);
+        lines.extend(fields.into_iter().map(|(_, line)| format!("        field!({line}")));
+
+        out += "lazy_static!{\n";
+        out += &format!("    pub static ref {name}: Vec<Field> = vec![\n");
+        for line in lines {
+            out += &line;
+            out += "\n";
+        }
+        out += "    ];\n}\n";
+
+        out
+    }
+
+    /// what the draft leaves open, one finding per line
+    pub fn report(&self) -> Vec<String> {
+        let mut report = vec![format!("{} records of 0x{:X} bytes", self.records, self.record_size)];
+
+        report.extend(self.notes.iter().cloned());
+
+        for (column, reason) in self.unplaced.iter() {
+            report.push(format!("column {column} not placed: {reason}"));
+        }
+
+        for u in self.unexplained.iter() {
+            let what = match (u.zero, u.constant) {
+                (true, _) => "always zero",
+                (false, true) => "the same in every record",
+                (false, false) => "varies",
+            };
+            report.push(format!("bytes 0x{:02X}..0x{:02X} unexplained, {what}", u.range.start, u.range.end));
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::bin::{Field, Record, Schema, Value};
+    use crate::field;
+
+    /// the layout `infer` should find: an item code, a string, two numbers,
+    /// two flags packed in a dword and a mapped text, with a zero byte at 0x0E
+    /// and a constant word at 0x16 no column explains
+    fn fields() -> Vec<Field> {
+        vec![
+            field!("code", ItemCode, 0x00),
+            field!("name", str[8], 0x04),
+            field!("level", u16, 0x0C),
+            field!("mindam", u8, 0x0F),
+            field!("flags", u32, 0x10),
+            field!("type", u16, 0x14),
+        ]
+    }
+
+    const TXT: &str = "\
+name\tcode\tlevel\tmindam\ttwohanded\tthrowable\ttype\tversion\t*comment\r
+Hatchet\thax\t300\t3\t1\t1\taxe\t0\tx\r
+Expansion\r
+Cleaver\taxe\t1200\t17\t0\t1\tswor\t0\tx\r
+Twin Ax\t2ax\t45\t250\t1\t0\taxe\t0\tx\r
+Axe\tmpi\t7000\t9\t0\t0\tbow\t0\tx\r
+";
+
+    /// the bin `TXT` compiles to with `fields`
+    fn bin() -> Vec<u8> {
+        let schema = Arc::new(Schema::with_record_size(&fields(), 0x18));
+        let rows: [(&str, &str, u16, u8, u32, u16); 4] = [
+            ("hax", "Hatchet", 300, 3, 0b1001, 5),
+            ("axe", "Cleaver", 1200, 17, 0b1000, 9),
+            ("2ax", "Twin Ax", 45, 250, 0b0001, 5),
+            ("mpi", "Axe", 7000, 9, 0b0000, 12),
+        ];
+
+        let mut b = (rows.len() as u32).to_le_bytes().to_vec();
+
+        for (code, name, level, mindam, flags, ty) in rows {
+            let values = vec![
+                Value::ItemCode(item_code(code)),
+                Value::String(name.to_string()),
+                Value::UInt16(level),
+                Value::UInt8(mindam),
+                Value::UInt32(flags),
+                Value::UInt16(ty),
+            ];
+
+            b.extend(Record::with_gaps(schema.clone(), values, vec![0, 0xCD, 0xAB]).to_bytes().unwrap());
+        }
+
+        b
+    }
+
+    #[test]
+    fn finds_the_fields_a_bin_was_built_from() {
+        let layout = infer(&bin(), TXT).unwrap();
+
+        assert_eq!(layout.record_size, 0x18);
+        assert_eq!(layout.records, 4);
+        assert_eq!(layout.notes, ["skipped 1 `Expansion` rows"]);
+
+        let placed: Vec<(&str, FieldType, usize)> = layout.placed.iter().map(|p| (p.name.as_str(), p.ty, p.offset)).collect();
+        let fields = fields();
+        let expect: Vec<(&str, FieldType, usize)> = fields.iter().map(|f| (f.name.as_str(), f.ty, f.offset as usize)).collect();
+        assert_eq!(placed[..4], expect[..4]);
+        assert_eq!(placed[4], ("flags_10", FieldType::Primitive(Primitive::UInt32), 0x10));
+        assert_eq!(placed[5], expect[5]);
+
+        assert_eq!(layout.placed[4].flags, [("twohanded".to_string(), 0), ("throwable".to_string(), 3)]);
+        assert!(layout.placed[5].note.as_deref().unwrap().starts_with("each text maps to one value"));
+
+        let unplaced: Vec<&str> = layout.unplaced.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(unplaced, ["version", "comment"]);
+        assert!(layout.unplaced[0].1.starts_with("0 in every row, matches"));
+        assert_eq!(layout.unplaced[1].1, "the same text in every row");
+
+        let unexplained: Vec<(Range<usize>, bool, bool)> = layout.unexplained.iter().map(|u| (u.range.clone(), u.zero, u.constant)).collect();
+        assert_eq!(unexplained, [(0x0E..0x0F, true, true), (0x16..0x18, false, true)]);
+    }
+
+    #[test]
+    fn a_header_without_rows_is_an_error() {
+        let err = |bin: &[u8], txt: &str| infer(bin, txt).err().unwrap().to_string();
+
+        assert_eq!(err(&bin(), "name\tcode\r\n"), "txt has no rows to match the records against");
+        assert_eq!(err(&bin(), "name\tcode\r\nExpansion\r\n"), "txt has no rows to match the records against");
+        assert_eq!(err(&bin(), ""), "txt is empty");
+        assert_eq!(err(&0u32.to_le_bytes(), TXT), "bin has no records");
+        assert_eq!(err(&1u32.to_le_bytes(), TXT), "bin has 1 records but no record bytes");
+    }
+}
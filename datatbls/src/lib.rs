@@ -6,5 +6,23 @@ pub mod stringtbl;
 pub mod fields;
 pub mod parser;
 pub mod datatbls_mgr;
-
-mod itemtbl;
+pub mod itemtbl;
+pub mod calc;
+pub mod skills;
+pub mod skillcalc;
+pub mod skilltip;
+pub mod skilltree;
+pub mod statdesc;
+pub mod uniques;
+pub mod itemtypes;
+pub mod runewords;
+pub mod query;
+pub mod mpq;
+pub mod vfs;
+pub mod layout;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+#[cfg(feature = "parquet")]
+pub mod columnar;
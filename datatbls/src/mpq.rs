@@ -0,0 +1,1074 @@
+#![allow(unused)]
+
+//! a reader for the mpq archives the game ships its data in (d2data.mpq,
+//! d2exp.mpq, patch_d2.mpq and mod archives): hash and block tables, file
+//! name hashing, encrypted tables and files, and pkware implode, zlib and
+//! bzip2 compressed sectors. only the v0/v1 header fields are used, which is
+//! all the diablo 2 archives have. `MpqWriter` builds archives of the same
+//! kind from edited files.
+
+use std::path::Path;
+use std::io::{Read, Write, Seek, SeekFrom};
+use std::collections::HashSet;
+use anyhow::{Result, bail, anyhow};
+use lazy_static::lazy_static;
+
+const MPQ_SIGNATURE         : u32 = 0x1A51504D; // MPQ\x1A
+const MPQ_USER_DATA         : u32 = 0x1B51504D; // MPQ\x1B
+
+const HASH_ENTRY_EMPTY      : u32 = 0xFFFFFFFF;
+const HASH_ENTRY_DELETED    : u32 = 0xFFFFFFFE;
+
+pub const FILE_IMPLODE      : u32 = 0x00000100;
+pub const FILE_COMPRESS     : u32 = 0x00000200;
+pub const FILE_ENCRYPTED    : u32 = 0x00010000;
+pub const FILE_FIX_KEY      : u32 = 0x00020000;
+pub const FILE_SINGLE_UNIT  : u32 = 0x01000000;
+pub const FILE_DELETE_MARKER: u32 = 0x02000000;
+pub const FILE_SECTOR_CRC   : u32 = 0x04000000;
+pub const FILE_EXISTS       : u32 = 0x80000000;
+
+pub const COMPRESSION_HUFFMAN       : u8 = 0x01;
+pub const COMPRESSION_ZLIB          : u8 = 0x02;
+pub const COMPRESSION_PKWARE        : u8 = 0x08;
+pub const COMPRESSION_BZIP2         : u8 = 0x10;
+pub const COMPRESSION_ADPCM_MONO    : u8 = 0x40;
+pub const COMPRESSION_ADPCM_STEREO  : u8 = 0x80;
+
+pub const HASH_TABLE_OFFSET : u32 = 0;
+pub const HASH_NAME_A       : u32 = 1;
+pub const HASH_NAME_B       : u32 = 2;
+pub const HASH_FILE_KEY     : u32 = 3;
+
+lazy_static! {
+    static ref CRYPT_TABLE: [u32; 0x500] = {
+        let mut table = [0u32; 0x500];
+        let mut seed: u32 = 0x00100001;
+
+        for i in 0..0x100 {
+            for j in 0..5 {
+                seed = (seed * 125 + 3) % 0x2AAAAB;
+                let hi = (seed & 0xFFFF) << 16;
+                seed = (seed * 125 + 3) % 0x2AAAAB;
+                let lo = seed & 0xFFFF;
+
+                table[i + j * 0x100] = hi | lo;
+            }
+        }
+
+        table
+    };
+}
+
+/// the storm hash of `name` for one of the `HASH_*` purposes, names are
+/// case and separator insensitive
+pub fn hash_string(name: &str, hash_type: u32) -> u32 {
+    let mut seed1: u32 = 0x7FED7FED;
+    let mut seed2: u32 = 0xEEEEEEEE;
+
+    for b in name.bytes() {
+        let ch = match b {
+            b'/' => b'\\',
+            b => b.to_ascii_uppercase(),
+        } as u32;
+
+        seed1 = CRYPT_TABLE[(hash_type * 0x100 + ch) as usize] ^ seed1.wrapping_add(seed2);
+        seed2 = ch.wrapping_add(seed1).wrapping_add(seed2).wrapping_add(seed2 << 5).wrapping_add(3);
+    }
+
+    seed1
+}
+
+pub fn decrypt_block(data: &mut [u32], mut key: u32) {
+    let mut seed: u32 = 0xEEEEEEEE;
+
+    for v in data.iter_mut() {
+        seed = seed.wrapping_add(CRYPT_TABLE[0x400 + (key & 0xFF) as usize]);
+        let ch = *v ^ key.wrapping_add(seed);
+
+        key = ((!key << 0x15).wrapping_add(0x11111111)) | (key >> 0x0B);
+        seed = ch.wrapping_add(seed).wrapping_add(seed << 5).wrapping_add(3);
+        *v = ch;
+    }
+}
+
+pub fn encrypt_block(data: &mut [u32], mut key: u32) {
+    let mut seed: u32 = 0xEEEEEEEE;
+
+    for v in data.iter_mut() {
+        seed = seed.wrapping_add(CRYPT_TABLE[0x400 + (key & 0xFF) as usize]);
+        let ch = *v ^ key.wrapping_add(seed);
+
+        key = ((!key << 0x15).wrapping_add(0x11111111)) | (key >> 0x0B);
+        seed = v.wrapping_add(seed).wrapping_add(seed << 5).wrapping_add(3);
+        *v = ch;
+    }
+}
+
+/// decrypts the whole dwords of `data` in place, a trailing partial dword is
+/// stored in the clear
+pub fn decrypt_bytes(data: &mut [u8], key: u32) {
+    let mut words = to_words(data);
+    decrypt_block(&mut words, key);
+    from_words(&words, data);
+}
+
+pub fn encrypt_bytes(data: &mut [u8], key: u32) {
+    let mut words = to_words(data);
+    encrypt_block(&mut words, key);
+    from_words(&words, data);
+}
+
+fn to_words(data: &[u8]) -> Vec<u32> {
+    data.chunks_exact(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+}
+
+fn from_words(words: &[u32], data: &mut [u8]) {
+    for (chunk, w) in data.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+}
+
+/// the key a file is encrypted with, derived from its name without the path
+pub fn file_key(name: &str, offset: u32, size: u32, flags: u32) -> u32 {
+    let base = name.rsplit(['\\', '/']).next().unwrap_or(name);
+    let key = hash_string(base, HASH_FILE_KEY);
+
+    if flags & FILE_FIX_KEY != 0 {
+        key.wrapping_add(offset) ^ size
+    } else {
+        key
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MpqHeader {
+    pub header_size         : u32,
+    pub archive_size        : u32,
+    pub format_version      : u16,
+    pub sector_size_shift   : u16,
+    pub hash_table_offset   : u32,
+    pub block_table_offset  : u32,
+    pub hash_table_entries  : u32,
+    pub block_table_entries : u32,
+}
+
+impl MpqHeader {
+    pub fn sector_size(&self) -> usize {
+        512 << self.sector_size_shift
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HashEntry {
+    pub name_a      : u32,
+    pub name_b      : u32,
+    pub locale      : u16,
+    pub platform    : u16,
+    pub block_index : u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlockEntry {
+    pub offset          : u32,
+    pub packed_size     : u32,
+    pub unpacked_size   : u32,
+    pub flags           : u32,
+}
+
+pub struct MpqArchive {
+    file        : std::fs::File,
+    /// where the mpq header sits in the file, table and block offsets are relative to it
+    base        : u64,
+    header      : MpqHeader,
+    hashes      : Vec<HashEntry>,
+    blocks      : Vec<BlockEntry>,
+    /// names known besides the archive's own (listfile)
+    listfile    : Vec<String>,
+}
+
+impl MpqArchive {
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<MpqArchive> {
+        let mut file = std::fs::File::open(path)?;
+        let (base, header) = Self::find_header(&mut file)?;
+        let len = file.metadata()?.len();
+
+        for (name, offset, entries) in [("hash", header.hash_table_offset, header.hash_table_entries), ("block", header.block_table_offset, header.block_table_entries)] {
+            if base + offset as u64 + entries as u64 * 16 > len {
+                bail!("{name} table of {entries} entries at 0x{offset:X} does not fit in the {len} byte file");
+            }
+        }
+
+        let mut hash_data = read_at(&mut file, base + header.hash_table_offset as u64, header.hash_table_entries as usize * 16)?;
+        decrypt_bytes(&mut hash_data, hash_string("(hash table)", HASH_FILE_KEY));
+
+        let mut block_data = read_at(&mut file, base + header.block_table_offset as u64, header.block_table_entries as usize * 16)?;
+        decrypt_bytes(&mut block_data, hash_string("(block table)", HASH_FILE_KEY));
+
+        let hashes = to_words(&hash_data).chunks_exact(4).map(|w| HashEntry{
+            name_a      : w[0],
+            name_b      : w[1],
+            locale      : (w[2] & 0xFFFF) as u16,
+            platform    : (w[2] >> 16) as u16,
+            block_index : w[3],
+        }).collect();
+
+        let blocks = to_words(&block_data).chunks_exact(4).map(|w| BlockEntry{
+            offset          : w[0],
+            packed_size     : w[1],
+            unpacked_size   : w[2],
+            flags           : w[3],
+        }).collect();
+
+        Ok(MpqArchive{
+            file,
+            base,
+            header,
+            hashes,
+            blocks,
+            listfile: Vec::new(),
+        })
+    }
+
+    /// the header is searched on 512 byte boundaries, archives can be appended
+    /// to other files or be preceded by a user data block
+    fn find_header(file: &mut std::fs::File) -> Result<(u64, MpqHeader)> {
+        let len = file.metadata()?.len();
+        let mut pos = 0u64;
+
+        while pos + 32 <= len {
+            let buf = read_at(file, pos, 32)?;
+            let w = to_words(&buf);
+
+            match w[0] {
+                MPQ_SIGNATURE => {
+                    let header = MpqHeader{
+                        header_size         : w[1],
+                        archive_size        : w[2],
+                        format_version      : (w[3] & 0xFFFF) as u16,
+                        sector_size_shift   : (w[3] >> 16) as u16,
+                        hash_table_offset   : w[4],
+                        block_table_offset  : w[5],
+                        hash_table_entries  : w[6],
+                        block_table_entries : w[7],
+                    };
+
+                    return Ok((pos, header));
+                }
+                MPQ_USER_DATA => {
+                    // the header follows the user data, an offset of 0 would find it again
+                    if w[2] == 0 {
+                        bail!("mpq user data at 0x{pos:X} has no header offset");
+                    }
+
+                    pos += w[2] as u64;
+                    continue;
+                }
+                _ => (),
+            }
+
+            pos += 0x200;
+        }
+
+        bail!("no mpq header found")
+    }
+
+    pub fn header(&self) -> &MpqHeader {
+        &self.header
+    }
+
+    pub fn blocks(&self) -> &[BlockEntry] {
+        &self.blocks
+    }
+
+    /// the hash table entries of `name`, every locale
+    pub fn find_entries(&self, name: &str) -> Vec<&HashEntry> {
+        let mut entries = Vec::new();
+
+        if self.hashes.is_empty() {
+            return entries;
+        }
+
+        let mask = self.hashes.len() - 1;
+        let start = hash_string(name, HASH_TABLE_OFFSET) as usize & mask;
+        let name_a = hash_string(name, HASH_NAME_A);
+        let name_b = hash_string(name, HASH_NAME_B);
+
+        for i in 0..self.hashes.len() {
+            let entry = &self.hashes[(start + i) & mask];
+
+            if entry.block_index == HASH_ENTRY_EMPTY {
+                break;
+            }
+
+            if entry.block_index != HASH_ENTRY_DELETED && entry.name_a == name_a && entry.name_b == name_b {
+                entries.push(entry);
+            }
+        }
+
+        entries
+    }
+
+    /// the block of `name`, the neutral locale is preferred
+    pub fn find_block(&self, name: &str) -> Option<&BlockEntry> {
+        let entries = self.find_entries(name);
+        let entry = entries.iter().find(|e| e.locale == 0).or(entries.first())?;
+        let block = self.blocks.get(entry.block_index as usize)?;
+
+        if block.flags & FILE_EXISTS == 0 || block.flags & FILE_DELETE_MARKER != 0 {
+            return None;
+        }
+
+        Some(block)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.find_block(name).is_some()
+    }
+
+    pub fn read_file(&self, name: &str) -> Result<Vec<u8>> {
+        let block = *self.find_block(name).ok_or_else(|| anyhow!("{name} not exists in archive"))?;
+        let offset = self.base + block.offset as u64;
+        let size = block.unpacked_size as usize;
+
+        let key = match block.flags & FILE_ENCRYPTED {
+            0 => None,
+            _ => Some(file_key(name, block.offset, block.unpacked_size, block.flags)),
+        };
+
+        let mut raw = read_at(&mut &self.file, offset, block.packed_size as usize)?;
+
+        if block.flags & FILE_SINGLE_UNIT != 0 {
+            if let Some(key) = key {
+                decrypt_bytes(&mut raw, key);
+            }
+
+            return decompress_sector(&raw, size, block.flags);
+        }
+
+        let sector_size = self.header.sector_size();
+        let sector_count = size.div_ceil(sector_size);
+
+        let offsets: Vec<usize> = if block.flags & (FILE_COMPRESS | FILE_IMPLODE) != 0 {
+            let count = sector_count + 1;
+
+            if raw.len() < count * 4 {
+                bail!("{name}: sector table truncated");
+            }
+
+            let mut table = raw[..count * 4].to_vec();
+
+            if let Some(key) = key {
+                decrypt_bytes(&mut table, key.wrapping_sub(1));
+            }
+
+            to_words(&table).into_iter().map(|o| o as usize).collect()
+        } else {
+            (0..=sector_count).map(|i| (i * sector_size).min(size)).collect()
+        };
+
+        let mut data = Vec::with_capacity(size);
+
+        for i in 0..sector_count {
+            let (start, end) = (offsets[i], offsets[i + 1]);
+
+            if start > end || end > raw.len() {
+                bail!("{name}: sector {i} out of bounds");
+            }
+
+            let mut sector = raw[start..end].to_vec();
+
+            if let Some(key) = key {
+                decrypt_bytes(&mut sector, key.wrapping_add(i as u32));
+            }
+
+            let expect = sector_size.min(size - i * sector_size);
+            data.extend(decompress_sector(&sector, expect, block.flags)?);
+        }
+
+        Ok(data)
+    }
+
+    /// names to try besides the ones in the archive's (listfile)
+    pub fn add_listfile<S: AsRef<str>>(&mut self, names: &[S]) {
+        self.listfile.extend(names.iter().map(|s| s.as_ref().to_string()));
+    }
+
+    /// the names of the listfile entries that exist in the archive
+    pub fn list_files(&self) -> Vec<String> {
+        let mut names: Vec<String> = match self.read_file("(listfile)") {
+            Ok(data) => String::from_utf8_lossy(&data)
+                .split([';', '\r', '\n'])
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        names.extend(self.listfile.iter().cloned());
+
+        let mut seen = HashSet::new();
+        names.retain(|n| self.contains(n) && seen.insert(n.to_ascii_uppercase().replace('/', "\\")));
+        names
+    }
+}
+
+/// how `MpqWriter` stores a file
+#[derive(Debug, Clone, Copy)]
+pub struct FileOptions {
+    /// zlib per sector, sectors that do not shrink are stored as is
+    pub compress    : bool,
+    pub encrypt     : bool,
+    /// the key also depends on where the file is in the archive
+    pub fix_key     : bool,
+}
+
+impl Default for FileOptions {
+    fn default() -> Self {
+        Self {
+            compress    : true,
+            encrypt     : false,
+            fix_key     : false,
+        }
+    }
+}
+
+struct PendingFile {
+    name    : String,
+    data    : Vec<u8>,
+    options : FileOptions,
+}
+
+/// builds a v0 archive, the format the game reads, with a (listfile) and
+/// an (attributes) holding the crc32 and time of every file
+pub struct MpqWriter {
+    files               : Vec<PendingFile>,
+    sector_size_shift   : u16,
+}
+
+impl Default for MpqWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MpqWriter {
+    pub fn new() -> Self {
+        Self {
+            files               : Vec::new(),
+            sector_size_shift   : 3,
+        }
+    }
+
+    /// starts from the files of `mpq` its listfile knows, stored like they were.
+    /// unlisted files can not be named and are dropped
+    pub fn from_archive(mpq: &MpqArchive) -> Result<MpqWriter> {
+        let mut writer = Self::new();
+        writer.sector_size_shift = mpq.header().sector_size_shift;
+
+        for name in mpq.list_files() {
+            if ["(listfile)", "(attributes)", "(signature)"].contains(&name.as_str()) {
+                continue;
+            }
+
+            let flags = mpq.find_block(&name).map(|b| b.flags).unwrap_or(0);
+            let options = FileOptions{
+                compress    : flags & (FILE_COMPRESS | FILE_IMPLODE) != 0,
+                encrypt     : flags & FILE_ENCRYPTED != 0,
+                fix_key     : flags & FILE_FIX_KEY != 0,
+            };
+
+            let data = mpq.read_file(&name)?;
+            writer.add_file(&name, data, options);
+        }
+
+        Ok(writer)
+    }
+
+    /// sectors are 512 << `shift` bytes
+    pub fn set_sector_size_shift(&mut self, shift: u16) {
+        self.sector_size_shift = shift;
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// adds `name` or replaces the file of the same name
+    pub fn add_file(&mut self, name: &str, data: Vec<u8>, options: FileOptions) {
+        let name = name.replace('/', "\\");
+        let file = PendingFile{name, data, options};
+
+        match self.files.iter().position(|f| same_name(&f.name, &file.name)) {
+            Some(i) => self.files[i] = file,
+            None => self.files.push(file),
+        }
+    }
+
+    /// adds every file under `root` named as if `root` were the `mount`
+    /// directory, e.g. an edited `data` dir mounted as `"data"`
+    pub fn add_dir<T: AsRef<Path>>(&mut self, root: T, mount: &str, options: FileOptions) -> Result<()> {
+        let mount = mount.replace('/', "\\").trim_matches('\\').to_string();
+        let mut dirs = vec![(root.as_ref().to_path_buf(), mount)];
+
+        while let Some((dir, prefix)) = dirs.pop() {
+            let mut entries = std::fs::read_dir(&dir)?.collect::<std::io::Result<Vec<_>>>()?;
+            entries.sort_by_key(|e| e.file_name());
+
+            for entry in entries {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let name = if prefix.is_empty() { name } else { format!("{prefix}\\{name}") };
+
+                if entry.file_type()?.is_dir() {
+                    dirs.push((entry.path(), name));
+                } else {
+                    self.add_file(&name, std::fs::read(entry.path())?, options);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_file(&mut self, name: &str) -> bool {
+        let len = self.files.len();
+        self.files.retain(|f| !same_name(&f.name, name));
+        self.files.len() != len
+    }
+
+    pub fn write<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        let sector_size = 512usize << self.sector_size_shift;
+
+        let listfile = self.files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>().join("\r\n").into_bytes();
+        let mut names: Vec<&str> = self.files.iter().map(|f| f.name.as_str()).collect();
+        names.push("(listfile)");
+        names.push("(attributes)");
+
+        let mut datas: Vec<&[u8]> = self.files.iter().map(|f| f.data.as_slice()).collect();
+        datas.push(&listfile);
+
+        let attributes = attributes(&datas, names.len());
+        datas.push(&attributes);
+
+        let mut options: Vec<FileOptions> = self.files.iter().map(|f| f.options).collect();
+        options.extend([FileOptions::default(); 2]);
+
+        // header, file data, hash table, block table
+        let mut out = vec![0u8; 32];
+        let mut blocks = Vec::with_capacity(names.len());
+
+        for ((name, data), options) in names.iter().zip(datas.iter()).zip(options.iter()) {
+            let offset = out.len() as u32;
+            let (body, flags) = encode_file(name, data, *options, offset, sector_size);
+
+            blocks.push(BlockEntry{
+                offset,
+                packed_size     : body.len() as u32,
+                unpacked_size   : data.len() as u32,
+                flags,
+            });
+
+            out.extend(body);
+        }
+
+        let hash_size = (names.len() * 4 / 3 + 1).next_power_of_two().max(16);
+        let mut hashes = vec![HASH_ENTRY_EMPTY; hash_size * 4];
+
+        for (block, name) in names.iter().enumerate() {
+            let mut i = hash_string(name, HASH_TABLE_OFFSET) as usize & (hash_size - 1);
+
+            while hashes[i * 4 + 3] != HASH_ENTRY_EMPTY {
+                i = (i + 1) & (hash_size - 1);
+            }
+
+            hashes[i * 4] = hash_string(name, HASH_NAME_A);
+            hashes[i * 4 + 1] = hash_string(name, HASH_NAME_B);
+            hashes[i * 4 + 2] = 0;
+            hashes[i * 4 + 3] = block as u32;
+        }
+
+        let mut block_words: Vec<u32> = blocks.iter()
+            .flat_map(|b| [b.offset, b.packed_size, b.unpacked_size, b.flags])
+            .collect();
+
+        encrypt_block(&mut hashes, hash_string("(hash table)", HASH_FILE_KEY));
+        encrypt_block(&mut block_words, hash_string("(block table)", HASH_FILE_KEY));
+
+        let hash_table_offset = out.len() as u32;
+        out.extend(hashes.iter().flat_map(|w| w.to_le_bytes()));
+
+        let block_table_offset = out.len() as u32;
+        out.extend(block_words.iter().flat_map(|w| w.to_le_bytes()));
+
+        let header = [
+            MPQ_SIGNATURE,
+            32,
+            out.len() as u32,
+            (self.sector_size_shift as u32) << 16,
+            hash_table_offset,
+            block_table_offset,
+            hash_size as u32,
+            blocks.len() as u32,
+        ];
+
+        for (i, w) in header.iter().enumerate() {
+            out[i * 4..i * 4 + 4].copy_from_slice(&w.to_le_bytes());
+        }
+
+        std::fs::write(path, out)?;
+
+        Ok(())
+    }
+}
+
+/// files are the same to an archive when their hashes are
+fn same_name(a: &str, b: &str) -> bool {
+    hash_string(a, HASH_NAME_A) == hash_string(b, HASH_NAME_A) && hash_string(a, HASH_NAME_B) == hash_string(b, HASH_NAME_B)
+}
+
+/// the sectors of a file with their offset table when compressed, encrypted
+/// with the key of the file at `offset`
+fn encode_file(name: &str, data: &[u8], options: FileOptions, offset: u32, sector_size: usize) -> (Vec<u8>, u32) {
+    let mut flags = FILE_EXISTS;
+
+    if data.is_empty() {
+        return (Vec::new(), flags);
+    }
+
+    if options.compress {
+        flags |= FILE_COMPRESS;
+    }
+
+    if options.encrypt {
+        flags |= FILE_ENCRYPTED;
+
+        if options.fix_key {
+            flags |= FILE_FIX_KEY;
+        }
+    }
+
+    let key = file_key(name, offset, data.len() as u32, flags);
+    let mut sectors: Vec<Vec<u8>> = data.chunks(sector_size)
+        .map(|s| if options.compress { compress_sector(s) } else { s.to_vec() })
+        .collect();
+
+    let mut body = Vec::new();
+
+    if options.compress {
+        let mut pos = (sectors.len() as u32 + 1) * 4;
+        let mut table = vec![pos];
+
+        for s in sectors.iter() {
+            pos += s.len() as u32;
+            table.push(pos);
+        }
+
+        if options.encrypt {
+            encrypt_block(&mut table, key.wrapping_sub(1));
+        }
+
+        body.extend(table.iter().flat_map(|w| w.to_le_bytes()));
+    }
+
+    for (i, s) in sectors.iter_mut().enumerate() {
+        if options.encrypt {
+            encrypt_bytes(s, key.wrapping_add(i as u32));
+        }
+
+        body.extend_from_slice(s);
+    }
+
+    (body, flags)
+}
+
+fn compress_sector(data: &[u8]) -> Vec<u8> {
+    let mut zlib = flate2::write::ZlibEncoder::new(vec![COMPRESSION_ZLIB], flate2::Compression::best());
+
+    match zlib.write_all(data).and_then(|_| zlib.finish()) {
+        Ok(out) if out.len() < data.len() => out,
+        _ => data.to_vec(),
+    }
+}
+
+/// (attributes) v100 with crc32 and file time, the entry of (attributes)
+/// itself is left zero
+fn attributes(datas: &[&[u8]], block_count: usize) -> Vec<u8> {
+    const ATTRIBUTES_CRC32: u32 = 0x1;
+    const ATTRIBUTES_FILETIME: u32 = 0x2;
+
+    // 100ns intervals since 1601-01-01
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos() as u64 / 100).unwrap_or(0);
+    let filetime = now + 116444736000000000;
+
+    let mut out = Vec::new();
+    out.extend(100u32.to_le_bytes());
+    out.extend((ATTRIBUTES_CRC32 | ATTRIBUTES_FILETIME).to_le_bytes());
+
+    for i in 0..block_count {
+        out.extend(datas.get(i).map(|d| crc32fast::hash(d)).unwrap_or(0).to_le_bytes());
+    }
+
+    for i in 0..block_count {
+        out.extend(if i < datas.len() { filetime } else { 0 }.to_le_bytes());
+    }
+
+    out
+}
+
+fn read_at<R: Read + Seek>(r: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    r.seek(SeekFrom::Start(offset))?;
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// a sector no smaller than its unpacked size is stored as is
+fn decompress_sector(data: &[u8], expect: usize, flags: u32) -> Result<Vec<u8>> {
+    if data.len() >= expect {
+        return Ok(data[..expect].to_vec());
+    }
+
+    if flags & FILE_IMPLODE != 0 {
+        return explode(data);
+    }
+
+    if flags & FILE_COMPRESS != 0 {
+        return decompress(data);
+    }
+
+    bail!("sector is {} bytes, expect {}", data.len(), expect)
+}
+
+/// a compressed sector: the first byte is the mask of the compressions
+/// applied, undone in the reverse order storm applies them
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let (&mask, rest) = data.split_first().ok_or_else(|| anyhow!("empty sector"))?;
+    let mut buf = rest.to_vec();
+
+    if mask & COMPRESSION_BZIP2 != 0 {
+        let mut out = Vec::new();
+        bzip2::read::BzDecoder::new(buf.as_slice()).read_to_end(&mut out)?;
+        buf = out;
+    }
+
+    if mask & COMPRESSION_PKWARE != 0 {
+        buf = explode(&buf)?;
+    }
+
+    if mask & COMPRESSION_ZLIB != 0 {
+        let mut out = Vec::new();
+        flate2::read::ZlibDecoder::new(buf.as_slice()).read_to_end(&mut out)?;
+        buf = out;
+    }
+
+    let unsupported = mask & !(COMPRESSION_BZIP2 | COMPRESSION_PKWARE | COMPRESSION_ZLIB);
+
+    if unsupported != 0 {
+        bail!("unsupported compression 0x{unsupported:02X}");
+    }
+
+    Ok(buf)
+}
+
+// pkware data compression library "implode", after Mark Adler's blast.c
+
+const MAX_BITS: usize = 13;
+
+const LIT_LEN: [u8; 98] = [
+    11, 124, 8, 7, 28, 7, 188, 13, 76, 4, 10, 8, 12, 10, 12, 10, 8, 23, 8,
+    9, 7, 6, 7, 8, 7, 6, 55, 8, 23, 24, 12, 11, 7, 9, 11, 12, 6, 7, 22, 5,
+    7, 24, 6, 11, 9, 6, 7, 22, 7, 11, 38, 7, 9, 8, 25, 11, 8, 11, 9, 12,
+    8, 12, 5, 38, 5, 38, 5, 11, 7, 5, 6, 21, 6, 10, 53, 8, 7, 24, 10, 27,
+    44, 253, 253, 253, 252, 252, 252, 13, 12, 45, 12, 45, 12, 61, 12, 45,
+    44, 173,
+];
+const LEN_LEN: [u8; 6] = [2, 35, 36, 53, 38, 23];
+const DIST_LEN: [u8; 7] = [2, 20, 53, 230, 247, 151, 248];
+
+const LEN_BASE: [u16; 16] = [3, 2, 4, 5, 6, 7, 8, 9, 10, 12, 16, 24, 40, 72, 136, 264];
+const LEN_EXTRA: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+struct Huffman {
+    count   : [u16; MAX_BITS + 1],
+    symbol  : Vec<u16>,
+}
+
+impl Huffman {
+    /// a canonical code from the compact form: each byte is a bit length in
+    /// the low nibble and a repeat count - 1 in the high one
+    fn new(rep: &[u8]) -> Self {
+        let lengths: Vec<usize> = rep.iter()
+            .flat_map(|b| std::iter::repeat_n((b & 15) as usize, (b >> 4) as usize + 1))
+            .collect();
+
+        let mut count = [0u16; MAX_BITS + 1];
+        for len in lengths.iter() {
+            count[*len] += 1;
+        }
+
+        let mut offs = [0usize; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offs[len + 1] = offs[len] + count[len] as usize;
+        }
+
+        let mut symbol = vec![0u16; lengths.len()];
+        for (sym, len) in lengths.iter().enumerate().filter(|(_, len)| **len != 0) {
+            symbol[offs[*len]] = sym as u16;
+            offs[*len] += 1;
+        }
+
+        Self{count, symbol}
+    }
+}
+
+struct BitReader<'a> {
+    data    : &'a [u8],
+    pos     : usize,
+    buf     : u32,
+    cnt     : u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn bits(&mut self, need: u32) -> Result<u32> {
+        while self.cnt < need {
+            let b = *self.data.get(self.pos).ok_or_else(|| anyhow!("imploded data truncated"))?;
+            self.buf |= (b as u32) << self.cnt;
+            self.pos += 1;
+            self.cnt += 8;
+        }
+
+        let v = self.buf & ((1u32 << need) - 1);
+        self.buf >>= need;
+        self.cnt -= need;
+
+        Ok(v)
+    }
+
+    /// codes are stored bit inverted
+    fn decode(&mut self, h: &Huffman) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+
+        for len in 1..=MAX_BITS {
+            code |= (self.bits(1)? ^ 1) as i32;
+            let count = h.count[len] as i32;
+
+            if code < first + count {
+                return Ok(h.symbol[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        bail!("invalid huffman code")
+    }
+}
+
+lazy_static! {
+    static ref LIT_CODE: Huffman = Huffman::new(&LIT_LEN);
+    static ref LEN_CODE: Huffman = Huffman::new(&LEN_LEN);
+    static ref DIST_CODE: Huffman = Huffman::new(&DIST_LEN);
+}
+
+pub fn explode(data: &[u8]) -> Result<Vec<u8>> {
+    let mut r = BitReader{data, pos: 0, buf: 0, cnt: 0};
+
+    let lit = r.bits(8)?;
+    let dict = r.bits(8)?;
+
+    if lit > 1 {
+        bail!("invalid literal mode {lit}");
+    }
+
+    if !(4..=6).contains(&dict) {
+        bail!("invalid dictionary size {dict}");
+    }
+
+    let mut out = Vec::new();
+
+    loop {
+        if r.bits(1)? == 1 {
+            let symbol = r.decode(&LEN_CODE)? as usize;
+            let len = LEN_BASE[symbol] as usize + r.bits(LEN_EXTRA[symbol] as u32)? as usize;
+
+            // end of stream
+            if len == 519 {
+                break;
+            }
+
+            let shift = if len == 2 { 2 } else { dict };
+            let dist = ((r.decode(&DIST_CODE)? as usize) << shift) + r.bits(shift)? as usize + 1;
+
+            if dist > out.len() {
+                bail!("distance {dist} too far back");
+            }
+
+            // copies can overlap their own output
+            for _ in 0..len {
+                out.push(out[out.len() - dist]);
+            }
+        } else {
+            let symbol = if lit == 1 { r.decode(&LIT_CODE)? as u32 } else { r.bits(8)? };
+            out.push(symbol as u8);
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a v0 archive built outside this crate: a stored (listfile) and
+    /// readme.txt, a zlib compressed and encrypted file over two sectors and
+    /// blast.c's imploded example
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/small.mpq");
+
+    fn fixture_text() -> Vec<u8> {
+        (0..300).flat_map(|i| format!("line {i} of the compressed and encrypted file\r\n").into_bytes()).collect()
+    }
+
+    #[test]
+    fn hashes_the_table_keys_like_storm() {
+        // MPQ_KEY_HASH_TABLE and MPQ_KEY_BLOCK_TABLE of StormLib
+        assert_eq!(hash_string("(hash table)", HASH_FILE_KEY), 0xC3AF3770);
+        assert_eq!(hash_string("(block table)", HASH_FILE_KEY), 0xEC83B3A3);
+
+        assert_eq!(hash_string("data/global/excel/test.txt", HASH_NAME_A), hash_string(r"DATA\Global\Excel\TEST.TXT", HASH_NAME_A));
+    }
+
+    #[test]
+    fn decrypts_what_it_encrypts() {
+        let plain: Vec<u32> = (0..64).map(|i| i * 0x01010101).collect();
+        let key = hash_string("(hash table)", HASH_FILE_KEY);
+
+        let mut data = plain.clone();
+        encrypt_block(&mut data, key);
+        assert_ne!(data, plain);
+        decrypt_block(&mut data, key);
+        assert_eq!(data, plain);
+
+        // the trailing partial dword stays in the clear
+        let mut bytes = vec![0xAB; 7];
+        encrypt_bytes(&mut bytes, key);
+        assert_eq!(bytes[4..], [0xAB; 3]);
+        decrypt_bytes(&mut bytes, key);
+        assert_eq!(bytes, [0xAB; 7]);
+    }
+
+    #[test]
+    fn fix_key_adds_offset_and_size() {
+        let key = hash_string("test.txt", HASH_FILE_KEY);
+
+        assert_eq!(file_key(r"data\global\excel\test.txt", 0x20, 100, FILE_ENCRYPTED), key);
+        assert_eq!(file_key("data/test.txt", 0x20, 100, FILE_ENCRYPTED | FILE_FIX_KEY), key.wrapping_add(0x20) ^ 100);
+    }
+
+    #[test]
+    fn explodes_the_blast_example() {
+        assert_eq!(explode(&[0x00, 0x04, 0x82, 0x24, 0x25, 0x8F, 0x80, 0x7F]).unwrap(), b"AIAIAIAIAIAIA");
+    }
+
+    #[test]
+    fn reads_the_fixture_archive() {
+        let mpq = MpqArchive::open(FIXTURE).unwrap();
+
+        assert_eq!(mpq.header().sector_size(), 4096);
+        assert_eq!(mpq.list_files(), ["readme.txt", r"data\global\excel\test.txt", "blast.bin"]);
+
+        assert_eq!(mpq.read_file("README.TXT").unwrap(), b"a stored file\r\n");
+        assert_eq!(mpq.read_file("data/global/excel/test.txt").unwrap(), fixture_text());
+        assert_eq!(mpq.read_file("blast.bin").unwrap(), b"AIAIAIAIAIAIA");
+
+        let block = mpq.find_block(r"data\global\excel\test.txt").unwrap();
+        assert_eq!(block.flags, FILE_EXISTS | FILE_COMPRESS | FILE_ENCRYPTED);
+
+        assert!(!mpq.contains("missing.txt"));
+        assert_eq!(mpq.read_file("missing.txt").unwrap_err().to_string(), "missing.txt not exists in archive");
+    }
+
+    #[test]
+    fn rejects_corrupt_headers() {
+        let path = std::env::temp_dir().join(format!("datatbls-corrupt-{}.mpq", std::process::id()));
+        let open = |data: &[u8]| {
+            std::fs::write(&path, data).unwrap();
+            let err = MpqArchive::open(&path).err().unwrap().to_string();
+            std::fs::remove_file(&path).unwrap();
+            err
+        };
+
+        let mut user_data = vec![0; 64];
+        user_data[..4].copy_from_slice(&MPQ_USER_DATA.to_le_bytes());
+        assert_eq!(open(&user_data), "mpq user data at 0x0 has no header offset");
+
+        let mut mpq = std::fs::read(FIXTURE).unwrap();
+        mpq[24..28].copy_from_slice(&0x1000_0000u32.to_le_bytes());
+        assert_eq!(open(&mpq), "hash table of 268435456 entries at 0x430 does not fit in the 1392 byte file");
+
+        let mut mpq = std::fs::read(FIXTURE).unwrap();
+        mpq[20..24].copy_from_slice(&0x600u32.to_le_bytes());
+        assert!(open(&mpq).starts_with("block table of"));
+    }
+
+    #[test]
+    fn reads_back_what_the_writer_wrote() {
+        let stored = FileOptions{compress: false, encrypt: false, fix_key: false};
+        let compressed = FileOptions{compress: true, encrypt: false, fix_key: false};
+        let encrypted = FileOptions{compress: true, encrypt: true, fix_key: false};
+        let fix_key = FileOptions{compress: false, encrypt: true, fix_key: true};
+
+        let mut writer = MpqWriter::default();
+        assert!(writer.is_empty());
+
+        // 512 byte sectors so the text spans a few
+        writer.set_sector_size_shift(0);
+        writer.add_file("readme.txt", b"a stored file\r\n".to_vec(), stored);
+        writer.add_file("data/global/excel/test.txt", fixture_text(), compressed);
+        writer.add_file(r"data\global\excel\secret.txt", fixture_text(), encrypted);
+        writer.add_file("fixed.bin", (0..=255u8).cycle().take(1500).collect(), fix_key);
+        writer.add_file("empty.txt", Vec::new(), compressed);
+        writer.add_file("README.TXT", b"replaced\r\n".to_vec(), stored);
+        assert_eq!(writer.len(), 5);
+
+        let path = std::env::temp_dir().join(format!("datatbls-mpq-{}.mpq", std::process::id()));
+        writer.write(&path).unwrap();
+        let mpq = MpqArchive::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mpq = mpq.unwrap();
+
+        assert_eq!(mpq.header().sector_size(), 512);
+        // a replaced file keeps its slot but takes the new name
+        assert_eq!(mpq.list_files(), ["README.TXT", r"data\global\excel\test.txt", r"data\global\excel\secret.txt", "fixed.bin", "empty.txt"]);
+
+        assert_eq!(mpq.read_file("readme.txt").unwrap(), b"replaced\r\n");
+        assert_eq!(mpq.read_file(r"data\global\excel\test.txt").unwrap(), fixture_text());
+        assert_eq!(mpq.read_file("data/global/excel/secret.txt").unwrap(), fixture_text());
+        assert_eq!(mpq.read_file("fixed.bin").unwrap(), (0..=255u8).cycle().take(1500).collect::<Vec<_>>());
+        assert_eq!(mpq.read_file("empty.txt").unwrap(), b"");
+
+        let flags = |name: &str| mpq.find_block(name).unwrap().flags;
+        assert_eq!(flags("readme.txt"), FILE_EXISTS);
+        assert_eq!(flags("data/global/excel/test.txt"), FILE_EXISTS | FILE_COMPRESS);
+        assert_eq!(flags("data/global/excel/secret.txt"), FILE_EXISTS | FILE_COMPRESS | FILE_ENCRYPTED);
+        assert_eq!(flags("fixed.bin"), FILE_EXISTS | FILE_ENCRYPTED | FILE_FIX_KEY);
+
+        let compressed = mpq.find_block("data/global/excel/test.txt").unwrap();
+        assert!(compressed.packed_size < compressed.unpacked_size);
+
+        let listfile = mpq.read_file("(listfile)").unwrap();
+        assert_eq!(listfile, b"README.TXT\r\ndata\\global\\excel\\test.txt\r\ndata\\global\\excel\\secret.txt\r\nfixed.bin\r\nempty.txt");
+
+        // version 100, crc32 and file time of the 7 blocks
+        let attributes = mpq.read_file("(attributes)").unwrap();
+        assert_eq!(attributes.len(), 8 + 7 * 4 + 7 * 8);
+        assert_eq!(attributes[..8], [100, 0, 0, 0, 3, 0, 0, 0]);
+        assert_eq!(attributes[12..16], crc32fast::hash(&fixture_text()).to_le_bytes());
+        assert_eq!(attributes[32..36], [0; 4]);
+    }
+}
@@ -10,6 +10,9 @@ use {
         fields,
         stringtbl::*,
         datatbls_mgr::*,
+        skillcalc,
+        skilltree,
+        uniques,
     },
 
     anyhow::Result,
@@ -21,8 +24,13 @@ fn game_file(f: &str) -> String {
     [GAME_PATH, f].concat()
 }
 
+/// the data directory `run` loads
+pub fn default_data_path() -> String {
+    game_file(r"data")
+}
+
 fn test_string_table() -> Result<()> {
-    let mut patchstring = StringTable::open(&game_file(r"DATA\LOCAL\lng\CHI\expansionstring.tbl"))?;
+    let mut patchstring = StringTable::open(game_file(r"DATA\LOCAL\lng\CHI\expansionstring.tbl"))?;
 
     let kvs = patchstring.read()?;
 
@@ -36,44 +44,59 @@ fn test_string_table() -> Result<()> {
 fn dump_item_id(tbls: &DataTblsManager) -> Result<()> {
     let mut item_id_lines = Vec::new();
 
-    let mut classid = -1;
+    for class in tbls.items.iter() {
+        let item = tbls.item_record(class);
 
-    for items in vec![&tbls.weapon, &tbls.armor, &tbls.misc] {
-        let start_index = items.start_index() as usize;
+        let name = tbls.get_string_by_index(item.get("name_str").value.str_id()).unwrap();
+        let name = name.trim_end();
 
-        for (index, item) in items.records().iter().enumerate() {
-            classid += 1;
+        if name.is_empty() {
+            panic!("wtf");
+            continue;
+        }
 
-            let name = tbls.get_string_by_index(item.get("name_str").value.str_id()).unwrap();
-            let name = name.trim_end();
+        item_id_lines.push(format!("{:>4} {}", class.class_id, name));
+    }
 
-            if name.is_empty() {
-                panic!("wtf");
-                continue;
-            }
+    std::fs::File::create(game_file(r"data\物品ID.txt"))?.write_all(item_id_lines.join("\n").as_bytes())?;
 
-            item_id_lines.push(format!("{:>4} {}", classid, name));
+    Ok(())
+}
+
+fn dump_skill_progression(tbls: &DataTblsManager) -> Result<()> {
+    let ctx = skillcalc::SkillContext::default();
+
+    for skill in tbls.skill_catalog.iter() {
+        if skill.name.is_none() || skill.char_class < 0 {
+            continue;
         }
-    }
 
-    std::fs::File::create(&game_file(r"data\物品ID.txt"))?.write_all(item_id_lines.join("\n").as_bytes())?;
+        let table = tbls.skill_progression(skill.id, &ctx).unwrap();
+        table.dump_csv(&game_file(&format!(r"data\progression\{}.csv", skill.id)))?;
+    }
 
     Ok(())
 }
 
 pub fn run() -> Result<()> {
-    Field::validate_fields_offset(&*fields::SKILLS);
-    Field::validate_fields_offset(&*fields::SKILL_DESC);
-    Field::validate_fields_offset(&*fields::ITEMS);
+    Schema::validate(&fields::SKILLS);
+    Schema::validate(&fields::SKILL_DESC);
+    Schema::validate(&fields::ITEMS);
+    Schema::validate(&fields::ITEM_STAT_COST);
+    Schema::validate(&fields::PROPERTIES);
+    Schema::validate(&fields::UNIQUE_ITEMS);
+    Schema::validate(&fields::SET_ITEMS);
+    Schema::validate(&fields::SETS);
+    Schema::validate(&fields::ITEM_TYPES);
+    Schema::validate(&fields::RUNES);
 
     let mut tbls = DataTblsManager::new();
 
     // tbls.load(r"D:\Game\Diablo II 暗月\MPQDumped\DATA\")?;
-    tbls.load(&game_file(r"data"))?;
+    tbls.load(game_file(r"data"))?;
+    tbls.validate_items()?;
 
-    for id in vec![26011] {
-        println!("`{}`", tbls.get_string_by_index(id).unwrap());
-    }
+    println!("`{}`", tbls.get_string_by_index(26011).unwrap());
     // dump_item_id(&tbls)?;
     // return Ok(());
 
@@ -85,101 +108,40 @@ pub fn run() -> Result<()> {
 
     // panic!("{:#?}", fields::SKILL_DESC[fields::SKILL_DESC.len() - 1].value);
 
-    let mut bin_skills = BinFile::open(&game_file(r"DATA\Global\EXCEL\skills.bin"), &*fields::SKILLS)?;
-    let skills = bin_skills.read()?;
+    let skills = &tbls.skills;
+    let skill_desc = &tbls.skill_desc;
 
-    let mut bin_skill_desc = BinFile::open(&game_file(r"DATA\Global\EXCEL\skilldesc.bin"), &*fields::SKILL_DESC)?;
-    let skill_desc = bin_skill_desc.read()?;
+    tbls.dump_fields(skills, &game_file(r"data\skills.py")).unwrap();
+    tbls.dump_fields(skill_desc, &game_file(r"data\skill_desc.py")).unwrap();
+    tbls.dump_txt(skills, &game_file(r"data\skills.txt"))?;
 
-    tbls.dump_fields(&skills, &game_file(r"data\skills.py")).unwrap();
-    tbls.dump_fields(&skill_desc, &game_file(r"data\skill_desc.py")).unwrap();
+    tbls.skill_catalog.dump_json(&game_file(r"data\skills2.py"))?;
+    std::fs::create_dir_all(game_file(r"data\progression"))?;
+    dump_skill_progression(&tbls)?;
+    skilltree::dump_html(&tbls.skill_catalog, &game_file(r"data\skilltree.html"))?;
 
-    let mut m: HashMap<i8, Vec<(&Record, &Record)>> = HashMap::new();
+    tbls.unique_catalog.dump_json(&game_file(r"data\uniques.json"))?;
+    tbls.set_catalog.dump_json(&game_file(r"data\sets.json"))?;
+    uniques::dump_html(&tbls.unique_catalog, &tbls.set_catalog, &game_file(r"data\items.html"))?;
+    tbls.runeword_catalog.dump_json(&tbls, &game_file(r"data\runewords.json"))?;
+    tbls.item_type_tree.dump_dot(&game_file(r"data\itemtypes.dot"))?;
 
-    for skill in skills.iter() {
-        let desc = skill.get("skill_desc").value.u16();
+    #[cfg(feature = "sqlite")]
+    crate::sqlite::export(&tbls, game_file(r"data\tables.sqlite"))?;
 
-        if desc == u16::MAX {
-            continue;
-        }
+    tbls.dump_csv(skills, &game_file(r"data\skills.csv"), true)?;
 
-        let char_class = skill.get("char_class").value.i8();
-        let desc = &skill_desc.records()[desc as usize];
-        let str_name = desc.get("str_name").value.u16();
+    #[cfg(feature = "xlsx")]
+    crate::xlsx::dump_default_workbook(&tbls, &game_file(r"data\tables.xlsx"))?;
 
-        // println!("str_name: {str_name}");
+    #[cfg(feature = "parquet")]
+    {
+        std::fs::create_dir_all(game_file(r"data\parquet"))?;
 
-        let skill_name = tbls.get_string_by_index(str_name);
-
-        if skill_name.is_none() {
-            continue;
+        for (name, rec) in tbls.tables() {
+            crate::columnar::write_parquet(&tbls, rec, &game_file(&format!(r"data\parquet\{name}.parquet")))?;
         }
-
-        m.entry(char_class).or_insert_with(Vec::new).push((skill, desc));
     }
 
-    let mut lines = Vec::<String>::new();
-
-    lines.push("{".into());
-
-    for (char_class, v) in m.iter() {
-        let char_class = match char_class {
-            0 => "Ama",
-            1 => "Sor",
-            2 => "Nec",
-            3 => "Pal",
-            4 => "Bar",
-            5 => "Dru",
-            6 => "Ass",
-            -1 => "255",
-            _ => todo!("wtf"),
-        };
-
-        lines.push(format!("  \"{char_class}\": ["));
-
-        for (skill, desc) in v.iter() {
-            let name        = desc.get("str_name").value.u16();
-            let str_long    = desc.get("str_long").value.u16();
-            let req_level   = skill.get("req_level").value.u16();
-            let max_lvl     = skill.get("max_lvl").value.u16();
-            let skill_id    = skill.get("skill_id").value.i16();
-
-            let name        = tbls.get_string_by_index(name);
-            let str_long    = tbls.get_string_by_index(str_long);
-
-            if name.is_none() || str_long.is_none() {
-                continue;
-            }
-
-            if name.unwrap() == "ÿc2致命毒龙卷" {
-                println!("idx: {}", desc.get("str_name").value.u16());
-            }
-
-            let ls = vec![
-                "    {".to_string(),
-                format!("      \"name\": \"{}\",", name.unwrap()),
-                format!("      \"str_long\": \"{}\",", str_long.unwrap()),
-                format!("      \"req_level\": {req_level},"),
-                format!("      \"max_lvl\": {max_lvl},"),
-                format!("      \"id\": {skill_id},"),
-                format!("      \"char_class\": \"{char_class}\","),
-                "    },".to_string(),
-            ];
-
-            lines.extend(ls);
-        }
-
-        lines.push("  ],".into());
-    }
-
-    lines.push("}".into());
-
-    let output = lines.join("\n");
-
-    let mut py = std::fs::File::create(&game_file(r"data\skills2.py"))?;
-    py.write_all(output.as_bytes())?;
-
-    // println!("{:#?}", m);
-
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,620 @@
+#![allow(unused)]
+
+//! a small filter language over the fields of a record, e.g.
+//!
+//! ```text
+//! req_str > 100 and not has(flags, "nodurability")
+//! char_class == 3 && req_level > 30
+//! name(name_str) contains "sword" or type[1] == 45
+//! ```
+//!
+//! Operands are numbers, quoted strings and field names, array fields are
+//! indexed with `field[i]`. `name(field)` resolves string ids, references,
+//! enums and item codes to their text, `has(field, "flag")` tests a named flag
+//! and `len(field)` is the length of an array. `contains` compares case
+//! insensitively, keywords can also be written `&&`, `||` and `!`.
+//!
+//! An index past the end of an array or a `name()` without a text is null.
+//! Null only equals null: comparing it to anything else is false except for
+//! `!=`, so `type[5] != 3` holds for a two element `type`.
+
+use anyhow::{Result, bail};
+
+use crate::bin::*;
+use crate::itemtbl::item_code_str;
+use crate::datatbls_mgr::DataTblsManager;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl QueryValue {
+    pub fn is_true(&self) -> bool {
+        match self {
+            Self::Null => false,
+            Self::Bool(b) => *b,
+            Self::Int(v) => *v != 0,
+            Self::Str(s) => !s.is_empty(),
+        }
+    }
+
+    fn compare(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => Some(a.cmp(b)),
+            (Self::Str(a), Self::Str(b)) => Some(a.cmp(b)),
+            (Self::Bool(a), Self::Bool(b)) => Some(a.cmp(b)),
+            (Self::Null, Self::Null) => Some(std::cmp::Ordering::Equal),
+            (Self::Null, _) | (_, Self::Null) => None,
+            (a, b) => Some(a.to_string().cmp(&b.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for QueryValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => Ok(()),
+            Self::Bool(b) => write!(f, "{}", *b as u8),
+            Self::Int(v) => write!(f, "{v}"),
+            Self::Str(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum QueryExpr {
+    Const(QueryValue),
+    Field(String, Option<usize>),
+    Call(String, Vec<QueryExpr>),
+    Not(Box<QueryExpr>),
+    And(Box<QueryExpr>, Box<QueryExpr>),
+    Or(Box<QueryExpr>, Box<QueryExpr>),
+    Cmp(CmpOp, Box<QueryExpr>, Box<QueryExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i64),
+    Str(String),
+    Op(&'static str),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    const OPS: [&str; 15] = ["==", "!=", "<=", ">=", "&&", "||", "<", ">", "=", "!", "(", ")", "[", "]", ","];
+
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..].iter().position(|e| *e == c).map(|p| i + 1 + p);
+            let end = match end {
+                Some(end) => end,
+                None => bail!("unterminated string at {i}"),
+            };
+            tokens.push(Token::Str(chars[i + 1..end].iter().collect()));
+            i = end + 1;
+        } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).map(|d| d.is_ascii_digit()).unwrap_or(false)) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+
+            let text: String = chars[start..i].iter().collect();
+            let (negative, digits) = match text.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, text.as_str()),
+            };
+            let value = match digits.strip_prefix("0x") {
+                Some(hex) => i64::from_str_radix(hex, 16),
+                None => digits.parse::<i64>(),
+            };
+            let value = match value {
+                Ok(value) => value,
+                Err(_) => bail!("bad number `{text}`"),
+            };
+            tokens.push(Token::Int(if negative { -value } else { value }));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = match OPS.iter().find(|op| rest.starts_with(**op)) {
+                Some(op) => *op,
+                None => bail!("unexpected `{c}` at {i}"),
+            };
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens  : Vec<Token>,
+    pos     : usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// consumes the operator or keyword `word` when it is next
+    fn eat(&mut self, word: &str) -> bool {
+        let found = match self.peek() {
+            Some(Token::Op(op)) => *op == word,
+            Some(Token::Ident(ident)) => ident.eq_ignore_ascii_case(word),
+            _ => false,
+        };
+
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, op: &str) -> Result<()> {
+        if !self.eat(op) {
+            bail!("expected `{op}`, found {:?}", self.peek());
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<QueryExpr> {
+        let mut left = self.and()?;
+        while self.eat("or") || self.eat("||") {
+            left = QueryExpr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<QueryExpr> {
+        let mut left = self.not()?;
+        while self.eat("and") || self.eat("&&") {
+            left = QueryExpr::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<QueryExpr> {
+        if self.eat("not") || self.eat("!") {
+            return Ok(QueryExpr::Not(Box::new(self.not()?)));
+        }
+        self.cmp()
+    }
+
+    fn cmp(&mut self) -> Result<QueryExpr> {
+        let left = self.operand()?;
+
+        let op = [
+            ("==", CmpOp::Eq), ("=", CmpOp::Eq), ("!=", CmpOp::Ne), ("<=", CmpOp::Le), (">=", CmpOp::Ge),
+            ("<", CmpOp::Lt), (">", CmpOp::Gt), ("contains", CmpOp::Contains),
+        ].into_iter().find(|(word, _)| self.eat(word));
+
+        match op {
+            Some((_, op)) => Ok(QueryExpr::Cmp(op, Box::new(left), Box::new(self.operand()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn operand(&mut self) -> Result<QueryExpr> {
+        match self.next() {
+            Some(Token::Int(v)) => Ok(QueryExpr::Const(QueryValue::Int(v))),
+            Some(Token::Str(s)) => Ok(QueryExpr::Const(QueryValue::Str(s))),
+            Some(Token::Op("(")) => {
+                let expr = self.or()?;
+                self.expect(")")?;
+                Ok(expr)
+            },
+            Some(Token::Ident(name)) if self.eat("(") => {
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.or()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(QueryExpr::Call(name.to_lowercase(), args))
+            },
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("true") || name.eq_ignore_ascii_case("false") => {
+                Ok(QueryExpr::Const(QueryValue::Bool(name.eq_ignore_ascii_case("true"))))
+            },
+            Some(Token::Ident(name)) => {
+                let index = if self.eat("[") {
+                    let index = match self.next() {
+                        Some(Token::Int(i)) if i >= 0 => i as usize,
+                        t => bail!("expected an index, found {t:?}"),
+                    };
+                    self.expect("]")?;
+                    Some(index)
+                } else {
+                    None
+                };
+                Ok(QueryExpr::Field(name, index))
+            },
+            t => bail!("expected an operand, found {t:?}"),
+        }
+    }
+}
+
+/// a parsed filter or column expression
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub text: String,
+    expr    : QueryExpr,
+}
+
+/// the matched rows of `DataTblsManager::query`
+pub struct QueryResult {
+    pub columns : Vec<String>,
+    /// row index and the column values
+    pub rows    : Vec<(usize, Vec<String>)>,
+}
+
+impl QueryResult {
+    /// tab separated with a header line, the row index is the first column
+    pub fn to_txt(&self) -> String {
+        let mut lines = Vec::<String>::new();
+
+        lines.push(std::iter::once("row").chain(self.columns.iter().map(|c| c.as_str())).collect::<Vec<_>>().join("\t"));
+
+        for (row, values) in self.rows.iter() {
+            lines.push(std::iter::once(row.to_string()).chain(values.iter().cloned()).collect::<Vec<_>>().join("\t"));
+        }
+
+        lines.join("\r\n")
+    }
+}
+
+fn field<'r>(record: &'r Record, name: &str) -> Result<FieldRef<'r>> {
+    match record.find(name) {
+        Some(f) => Ok(f),
+        None => bail!("no field `{name}`"),
+    }
+}
+
+fn element(f: FieldRef, index: Option<usize>) -> Result<QueryValue> {
+    fn at<T: Copy + Into<i64>>(v: &[T], index: Option<usize>, name: &str) -> Result<QueryValue> {
+        match index {
+            Some(i) => Ok(v.get(i).map(|v| QueryValue::Int((*v).into())).unwrap_or(QueryValue::Null)),
+            None => bail!("`{name}` is an array, index it like `{name}[0]`"),
+        }
+    }
+
+    let value = match &f.value {
+        Value::I8Array(v) => return at(v, index, &f.name),
+        Value::I16Array(v) => return at(v, index, &f.name),
+        Value::I32Array(v) => return at(v, index, &f.name),
+        Value::U8Array(v) => return at(v, index, &f.name),
+        Value::U16Array(v) => return at(v, index, &f.name),
+        Value::U32Array(v) => return at(v, index, &f.name),
+        Value::ItemCode(code) => QueryValue::Str(item_code_str(*code)),
+        Value::String(s) => QueryValue::Str(s.clone()),
+        v => QueryValue::Int(v.as_i64().unwrap()),
+    };
+
+    if index.is_some() {
+        bail!("`{}` is not an array", f.name);
+    }
+
+    Ok(value)
+}
+
+impl Query {
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parser = Parser{
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+
+        let expr = parser.or()?;
+        if let Some(t) = parser.peek() {
+            bail!("unexpected {t:?} in `{s}`");
+        }
+
+        Ok(Self {
+            text: s.to_string(),
+            expr,
+        })
+    }
+
+    /// splits a comma separated column list, commas inside quotes or calls
+    /// do not split columns
+    pub fn split_columns(columns: &str) -> Vec<&str> {
+        let mut split = Vec::new();
+        let (mut depth, mut quote, mut start) = (0, None, 0);
+
+        for (i, c) in columns.char_indices() {
+            match c {
+                '"' | '\'' if quote == Some(c) => quote = None,
+                '"' | '\'' if quote.is_none() => quote = Some(c),
+                '(' | '[' if quote.is_none() => depth += 1,
+                ')' | ']' if quote.is_none() => depth -= 1,
+                ',' if quote.is_none() && depth == 0 => {
+                    split.push(columns[start..i].trim());
+                    start = i + 1;
+                },
+                _ => {},
+            }
+        }
+
+        split.push(columns[start..].trim());
+        split.retain(|c| !c.is_empty());
+        split
+    }
+
+    pub fn eval(&self, tbls: &DataTblsManager, record: &Record) -> Result<QueryValue> {
+        Self::eval_expr(&self.expr, tbls, record)
+    }
+
+    pub fn matches(&self, tbls: &DataTblsManager, record: &Record) -> Result<bool> {
+        Ok(self.eval(tbls, record)?.is_true())
+    }
+
+    fn eval_expr(expr: &QueryExpr, tbls: &DataTblsManager, record: &Record) -> Result<QueryValue> {
+        let eval = |e: &QueryExpr| Self::eval_expr(e, tbls, record);
+
+        let value = match expr {
+            QueryExpr::Const(v) => v.clone(),
+            QueryExpr::Field(name, index) => element(field(record, name)?, *index)?,
+            QueryExpr::Not(e) => QueryValue::Bool(!eval(e)?.is_true()),
+            QueryExpr::And(a, b) => QueryValue::Bool(eval(a)?.is_true() && eval(b)?.is_true()),
+            QueryExpr::Or(a, b) => QueryValue::Bool(eval(a)?.is_true() || eval(b)?.is_true()),
+            QueryExpr::Cmp(op, a, b) => {
+                let (a, b) = (eval(a)?, eval(b)?);
+
+                let result = match op {
+                    CmpOp::Contains => a.to_string().to_lowercase().contains(&b.to_string().to_lowercase()),
+                    op => match a.compare(&b) {
+                        Some(ord) => match op {
+                            CmpOp::Eq => ord.is_eq(),
+                            CmpOp::Ne => ord.is_ne(),
+                            CmpOp::Lt => ord.is_lt(),
+                            CmpOp::Gt => ord.is_gt(),
+                            CmpOp::Le => ord.is_le(),
+                            CmpOp::Ge => ord.is_ge(),
+                            CmpOp::Contains => unreachable!(),
+                        },
+                        None => *op == CmpOp::Ne,
+                    },
+                };
+
+                QueryValue::Bool(result)
+            },
+            QueryExpr::Call(func, args) => Self::call(func, args, tbls, record)?,
+        };
+
+        Ok(value)
+    }
+
+    fn call(func: &str, args: &[QueryExpr], tbls: &DataTblsManager, record: &Record) -> Result<QueryValue> {
+        let (name, index) = match args.first() {
+            Some(QueryExpr::Field(name, index)) => (name.as_str(), *index),
+            _ => bail!("{func}() takes a field as its first argument"),
+        };
+        let f = field(record, name)?;
+
+        let value = match (func, args.len()) {
+            ("name", 1) => {
+                let i = index.unwrap_or(0);
+
+                if let Some(table) = f.reference {
+                    let row = f.ref_rows().get(i).copied().flatten();
+                    row.and_then(|row| tbls.table(table)?.records().get(row))
+                        .and_then(|r| tbls.row_name(table, r))
+                        .map(QueryValue::Str)
+                        .unwrap_or(QueryValue::Null)
+                } else if let Value::StringId(id) = f.value {
+                    tbls.get_string_by_index(*id).map(|s| QueryValue::Str(s.trim_end().to_string())).unwrap_or(QueryValue::Null)
+                } else if let Some(e) = f.enumeration {
+                    match element(f, index)? {
+                        QueryValue::Int(v) => QueryValue::Str(e.format(v)),
+                        v => v,
+                    }
+                } else {
+                    element(f, index)?
+                }
+            },
+            ("has", 2) => {
+                let flag = match &args[1] {
+                    QueryExpr::Const(QueryValue::Str(flag)) => flag,
+                    _ => bail!("has() takes a flag name as its second argument"),
+                };
+
+                match f.flag_values().into_iter().find(|(n, _)| n.eq_ignore_ascii_case(flag)) {
+                    Some((_, set)) => QueryValue::Bool(set),
+                    None => bail!("`{name}` has no flag `{flag}`"),
+                }
+            },
+            ("len", 1) => QueryValue::Int(match &f.value {
+                Value::I8Array(v) => v.len(),
+                Value::I16Array(v) => v.len(),
+                Value::I32Array(v) => v.len(),
+                Value::U8Array(v) => v.len(),
+                Value::U16Array(v) => v.len(),
+                Value::U32Array(v) => v.len(),
+                Value::String(s) => s.chars().count(),
+                _ => 1,
+            } as i64),
+            _ => bail!("unknown function {func}() with {} arguments", args.len()),
+        };
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields;
+    use crate::bin::tests::zeroed;
+    use crate::stringtbl::tests::manager;
+
+    fn int(v: i64) -> QueryExpr {
+        QueryExpr::Const(QueryValue::Int(v))
+    }
+
+    fn field(name: &str, index: Option<usize>) -> QueryExpr {
+        QueryExpr::Field(name.into(), index)
+    }
+
+    fn cmp(op: CmpOp, a: QueryExpr, b: QueryExpr) -> QueryExpr {
+        QueryExpr::Cmp(op, Box::new(a), Box::new(b))
+    }
+
+    fn parse(s: &str) -> QueryExpr {
+        Query::parse(s).unwrap().expr
+    }
+
+    fn error(s: &str) -> String {
+        Query::parse(s).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn tokenizes_numbers_strings_and_operators() {
+        assert_eq!(tokenize("a[1]>=-0x10 || b!='x y'").unwrap(), [
+            Token::Ident("a".into()), Token::Op("["), Token::Int(1), Token::Op("]"), Token::Op(">="), Token::Int(-16),
+            Token::Op("||"), Token::Ident("b".into()), Token::Op("!="), Token::Str("x y".into()),
+        ]);
+        assert_eq!(tokenize("0x7F -3 \"\"").unwrap(), [Token::Int(127), Token::Int(-3), Token::Str(String::new())]);
+    }
+
+    #[test]
+    fn keywords_and_symbols_parse_the_same() {
+        assert_eq!(parse("a > 1 and not b == 2 or c"), parse("a > 1 && !b == 2 || c"));
+        assert_eq!(parse("a AND b"), parse("a && b"));
+
+        // and binds tighter than or, not tighter than and
+        let expr = QueryExpr::Or(
+            Box::new(QueryExpr::And(Box::new(field("a", None)), Box::new(QueryExpr::Not(Box::new(field("b", None)))))),
+            Box::new(field("c", None)),
+        );
+        assert_eq!(parse("a and not b or c"), expr);
+    }
+
+    #[test]
+    fn parses_operands() {
+        assert_eq!(parse("type[1] = -2"), cmp(CmpOp::Eq, field("type", Some(1)), int(-2)));
+        assert_eq!(parse("flags != 0x20"), cmp(CmpOp::Ne, field("flags", None), int(32)));
+        assert_eq!(parse("name(type[0]) contains 'axe'"), cmp(
+            CmpOp::Contains,
+            QueryExpr::Call("name".into(), vec![field("type", Some(0))]),
+            QueryExpr::Const(QueryValue::Str("axe".into())),
+        ));
+        assert_eq!(parse("HAS(flags, \"x\")"), QueryExpr::Call("has".into(), vec![field("flags", None), QueryExpr::Const(QueryValue::Str("x".into()))]));
+        assert_eq!(parse("len(type) == 2 and true"), QueryExpr::And(
+            Box::new(cmp(CmpOp::Eq, QueryExpr::Call("len".into(), vec![field("type", None)]), int(2))),
+            Box::new(QueryExpr::Const(QueryValue::Bool(true))),
+        ));
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        assert_eq!(error("a == 'x"), "unterminated string at 5");
+        assert_eq!(error("a == 0xZZ"), "bad number `0xZZ`");
+        assert_eq!(error("a # 1"), "unexpected `#` at 2");
+        assert_eq!(error("(a == 1"), "expected `)`, found None");
+        assert_eq!(error("a[-1]"), "expected an index, found Some(Int(-1))");
+        assert_eq!(error("a =="), "expected an operand, found None");
+        assert_eq!(error("a b"), "unexpected Ident(\"b\") in `a b`");
+    }
+
+    #[test]
+    fn splits_columns_outside_quotes_and_calls() {
+        assert_eq!(Query::split_columns("code, has(flags, \"a,b\"), type[0],, 'x,y' "), ["code", "has(flags, \"a,b\")", "type[0]", "'x,y'"]);
+        assert!(Query::split_columns(" ").is_empty());
+    }
+
+    /// one weapons row named "Hand Axe" of type 3, 2 sockets and the quivered flag
+    fn tbls() -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+        tbls.strtbl = manager(&[("dummy", ""), ("hax", "Hand Axe")], &[], &[]);
+
+        tbls.weapon.load_bytes({
+            let mut bin = zeroed(&fields::ITEMS, 1);
+            let item = bin.get_mut(0).unwrap();
+            item.set("name_str", Value::StringId(1));
+            item.set("type", Value::I16Array(vec![3, -1]));
+            item.set("gem_sockets", Value::UInt8(2));
+            item.set("bit_field_1", Value::UInt32(0x2));
+            bin.to_bytes().unwrap()
+        }).unwrap();
+
+        tbls
+    }
+
+    fn eval(tbls: &DataTblsManager, s: &str) -> Result<QueryValue> {
+        Query::parse(s)?.eval(tbls, &tbls.weapon.records().records()[0])
+    }
+
+    #[test]
+    fn evaluates_against_a_record() {
+        let tbls = tbls();
+        let check = |s: &str| eval(&tbls, s).unwrap();
+
+        assert_eq!(check("gem_sockets"), QueryValue::Int(2));
+        assert_eq!(check("name(name_str)"), QueryValue::Str("Hand Axe".into()));
+        assert_eq!(check("name(name_str) contains 'AXE' && type[0] == 3"), QueryValue::Bool(true));
+        assert_eq!(check("has(bit_field_1, 'QUIVERED') and !has(bit_field_1, 'nodurability')"), QueryValue::Bool(true));
+        assert_eq!(check("len(type)"), QueryValue::Int(2));
+        assert_eq!(check("gem_sockets >= 0x2 or missing"), QueryValue::Bool(true));
+
+        assert_eq!(eval(&tbls, "type == 3").unwrap_err().to_string(), "`type` is an array, index it like `type[0]`");
+        assert_eq!(eval(&tbls, "gem_sockets[0]").unwrap_err().to_string(), "`gem_sockets` is not an array");
+        assert_eq!(eval(&tbls, "nothing > 1").unwrap_err().to_string(), "no field `nothing`");
+        assert_eq!(eval(&tbls, "has(bit_field_1, 'x')").unwrap_err().to_string(), "`bit_field_1` has no flag `x`");
+        assert_eq!(eval(&tbls, "len(1)").unwrap_err().to_string(), "len() takes a field as its first argument");
+        assert_eq!(eval(&tbls, "len(type, 1)").unwrap_err().to_string(), "unknown function len() with 2 arguments");
+    }
+
+    #[test]
+    fn null_only_equals_null() {
+        let tbls = tbls();
+        let check = |s: &str| eval(&tbls, s).unwrap();
+
+        assert_eq!(check("type[5]"), QueryValue::Null);
+        assert_eq!(check("type[5] != 3"), QueryValue::Bool(true));
+        for op in ["==", "<", ">", "<=", ">="] {
+            assert_eq!(check(&format!("type[5] {op} 3")), QueryValue::Bool(false), "{op}");
+        }
+        assert_eq!(check("type[5] == type[6]"), QueryValue::Bool(true));
+        assert_eq!(check("type[5] != type[6]"), QueryValue::Bool(false));
+        assert_eq!(check("not type[5]"), QueryValue::Bool(true));
+    }
+}
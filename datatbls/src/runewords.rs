@@ -0,0 +1,259 @@
+#![allow(unused)]
+
+//! runewords of runes.bin with their runes, the item types they can be made
+//! in and the text of their properties.
+
+use std::io::Write;
+use anyhow::Result;
+
+use crate::bin::*;
+use crate::itemtbl::{ItemClass, ItemKind, item_code_str};
+use crate::stringtbl::strip_color_codes;
+use crate::statdesc::{ItemProp, StatDesc};
+use crate::datatbls_mgr::{DataTblsManager, json_str};
+
+const PROP_KEYS: [&str; 4] = ["prop", "par", "min", "max"];
+
+#[derive(Debug, Clone)]
+pub struct Runeword {
+    pub row         : usize,
+    pub index       : String,
+    pub name        : Option<String>,
+    /// the English name runes.bin carries besides the string id
+    pub rune_name   : String,
+    pub complete    : bool,
+    /// the rune items in socket order
+    pub runes       : Vec<ItemClass>,
+    pub rune_names  : Vec<String>,
+    /// itemtypes.bin rows the runeword can be made in
+    pub itypes      : Vec<usize>,
+    /// itemtypes.bin rows excluded from `itypes`
+    pub etypes      : Vec<usize>,
+    pub props       : Vec<ItemProp>,
+    pub lines       : Vec<String>,
+}
+
+impl Runeword {
+    fn new(tbls: &DataTblsManager, desc: &StatDesc, row: usize, record: &Record) -> Self {
+        // runes are stored as item class ids, they all live in misc.bin
+        let runes: Vec<ItemClass> = (1..=6)
+            .map(|i| record.get(&format!("rune_{i}")).value.i32())
+            .filter(|id| *id >= 0)
+            .filter_map(|id| tbls.items.get(id as u32).copied())
+            .filter(|c| c.kind == ItemKind::Misc)
+            .collect();
+
+        let rune_names = runes.iter()
+            .map(|c| tbls.row_name("misc", tbls.item_record(c)).unwrap_or_else(|| item_code_str(c.code)))
+            .collect();
+
+        let suffixes: Vec<String> = (1..=7).map(|i| i.to_string()).collect();
+        let props: Vec<ItemProp> = suffixes.iter().filter_map(|s| ItemProp::read(record, PROP_KEYS, s)).collect();
+
+        Self {
+            row,
+            index       : record.get("index").value.str().to_string(),
+            name        : tbls.row_name("runes", record),
+            rune_name   : record.get("rune_name").value.str().to_string(),
+            complete    : record.get("complete").value.u8() != 0,
+            runes,
+            rune_names,
+            itypes      : record.get("itype").ref_rows().into_iter().flatten().collect(),
+            etypes      : record.get("etype").ref_rows().into_iter().flatten().collect(),
+            lines       : desc.describe_props(&props),
+            props,
+        }
+    }
+
+    pub fn rune_codes(&self) -> Vec<String> {
+        self.runes.iter().map(|c| item_code_str(c.code)).collect()
+    }
+
+    /// the display name, the string table name when there is one
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.rune_name.clone())
+    }
+
+    /// whether the runeword can be made in base item `class`: one of its types
+    /// is an itype, none is an etype and it has a socket for every rune
+    pub fn fits(&self, tbls: &DataTblsManager, class: &ItemClass) -> bool {
+        let item = tbls.item_record(class);
+
+        if (item.get("gem_sockets").value.u8() as usize) < self.runes.len() {
+            return false;
+        }
+
+        let types = tbls.item_types_of(item);
+        let any = |of: &[usize]| of.iter().any(|t| types.contains(t));
+
+        any(&self.itypes) && !any(&self.etypes)
+    }
+}
+
+pub struct RunewordCatalog {
+    runewords   : Vec<Runeword>,
+}
+
+impl Default for RunewordCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunewordCatalog {
+    pub fn new() -> Self {
+        Self {
+            runewords   : Vec::new(),
+        }
+    }
+
+    pub fn build(tbls: &DataTblsManager) -> Self {
+        let desc = StatDesc::new(tbls);
+
+        Self {
+            runewords: tbls.runes.iter().enumerate().map(|(row, r)| Runeword::new(tbls, &desc, row, r)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.runewords.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runewords.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Runeword> {
+        self.runewords.iter()
+    }
+
+    pub fn get(&self, row: usize) -> Option<&Runeword> {
+        self.runewords.get(row)
+    }
+
+    /// case insensitive, color codes are ignored
+    pub fn get_by_name(&self, name: &str) -> Option<&Runeword> {
+        let key = |s: &str| strip_color_codes(s).trim().to_lowercase();
+        let name = key(name);
+
+        self.runewords.iter().find(|r| key(&r.display_name()) == name)
+    }
+
+    /// the complete runewords that can be made in base item `class`
+    pub fn for_item(&self, tbls: &DataTblsManager, class: &ItemClass) -> Vec<&Runeword> {
+        self.runewords.iter().filter(|r| r.complete && r.fits(tbls, class)).collect()
+    }
+
+    pub fn dump_json(&self, tbls: &DataTblsManager, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        let list = |v: &[String]| format!("[{}]", v.iter().map(|s| json_str(s)).collect::<Vec<_>>().join(", "));
+        let types = |rows: &[usize]| -> Vec<String> {
+            rows.iter().map(|t| tbls.item_type_tree.code(*t).unwrap_or_else(|| t.to_string())).collect()
+        };
+
+        lines.push("[".into());
+
+        for (i, rw) in self.runewords.iter().enumerate() {
+            lines.extend(vec![
+                "  {".to_string(),
+                format!("    \"index\": {},", json_str(&rw.index)),
+                format!("    \"name\": {},", json_str(rw.display_name().trim_end())),
+                format!("    \"complete\": {},", rw.complete),
+                format!("    \"runes\": {},", list(&rw.rune_codes())),
+                format!("    \"rune_names\": {},", list(&rw.rune_names)),
+                format!("    \"itypes\": {},", list(&types(&rw.itypes))),
+                format!("    \"etypes\": {},", list(&types(&rw.etypes))),
+                format!("    \"props\": {}", list(&rw.lines)),
+                format!("  }}{}", if i + 1 < self.runewords.len() { "," } else { "" }),
+            ]);
+        }
+
+        lines.push("]".into());
+
+        let mut json = std::fs::File::create(file_name)?;
+        json.write_all(lines.join("\n").as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields;
+    use crate::bin::tests::zeroed;
+    use crate::itemtbl::{ItemCatalog, item_code};
+    use crate::itemtypes::ItemTypes;
+
+    const WEAP: usize = 0;
+    const MELE: usize = 1;
+    const SWOR: usize = 2;
+    const SHIE: usize = 3;
+
+    /// weap <- mele <- swor and a lone shie, a 3 socket sword, a 1 socket
+    /// sword and a 3 socket shield
+    fn tbls() -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+
+        tbls.item_types = zeroed(&fields::ITEM_TYPES, 4);
+        for (row, (code, parent)) in [("weap", u16::MAX), ("mele", WEAP as u16), ("swor", MELE as u16), ("shie", u16::MAX)].into_iter().enumerate() {
+            let t = tbls.item_types.get_mut(row).unwrap();
+            t.set("code", Value::ItemCode(item_code(code)));
+            t.set("equiv", Value::U16Array(vec![parent, u16::MAX]));
+        }
+        tbls.item_type_tree = ItemTypes::build(&tbls.item_types);
+
+        let mut weapons = zeroed(&fields::ITEMS, 3);
+        for (row, (code, ty, sockets)) in [("crs", SWOR, 3), ("ssd", SWOR, 1), ("buc", SHIE, 3)].into_iter().enumerate() {
+            let item = weapons.get_mut(row).unwrap();
+            item.set("code", Value::ItemCode(item_code(code)));
+            item.set("type", Value::I16Array(vec![ty as i16, -1]));
+            item.set("gem_sockets", Value::UInt8(sockets));
+        }
+        tbls.weapon.load_bytes(weapons.to_bytes().unwrap()).unwrap();
+        tbls.items = ItemCatalog::build([&mut tbls.weapon, &mut tbls.armor, &mut tbls.misc]);
+
+        tbls
+    }
+
+    fn runeword(runes: usize, itypes: &[usize], etypes: &[usize]) -> Runeword {
+        Runeword {
+            row         : 0,
+            index       : "Runeword1".into(),
+            name        : None,
+            rune_name   : "Test".into(),
+            complete    : true,
+            runes       : vec![ItemClass { class_id: 0, kind: ItemKind::Misc, row: 0, code: item_code("r01") }; runes],
+            rune_names  : Vec::new(),
+            itypes      : itypes.to_vec(),
+            etypes      : etypes.to_vec(),
+            props       : Vec::new(),
+            lines       : Vec::new(),
+        }
+    }
+
+    #[test]
+    fn fits_an_itype_through_its_ancestors() {
+        let tbls = tbls();
+        let [crs, ssd, buc] = [0, 1, 2].map(|id| *tbls.items.get(id).unwrap());
+
+        let rw = runeword(2, &[WEAP], &[]);
+        assert!(rw.fits(&tbls, &crs));
+        assert!(!rw.fits(&tbls, &buc));
+
+        // one socket is not enough for two runes
+        assert!(!rw.fits(&tbls, &ssd));
+        assert!(runeword(1, &[WEAP], &[]).fits(&tbls, &ssd));
+    }
+
+    #[test]
+    fn an_etype_excludes_its_descendants() {
+        let tbls = tbls();
+        let crs = *tbls.items.get(0).unwrap();
+
+        assert!(!runeword(2, &[WEAP], &[MELE]).fits(&tbls, &crs));
+        assert!(runeword(2, &[WEAP, SHIE], &[SHIE]).fits(&tbls, &crs));
+        assert!(!runeword(2, &[], &[]).fits(&tbls, &crs));
+    }
+}
@@ -0,0 +1,462 @@
+#![allow(unused)]
+
+use std::collections::HashMap;
+use std::io::Write;
+use anyhow::Result;
+
+use crate::bin::*;
+use crate::calc::{CalcContext, Expr};
+use crate::datatbls_mgr::{DataTblsManager, csv_str, json_str};
+
+/// nested skill(...) and clc lookups deeper than this evaluate to 0, so
+/// calcs referring to themselves end
+const MAX_DEPTH: u32 = 16;
+
+/// what a skill is evaluated against besides its own level
+#[derive(Debug, Clone, Default)]
+pub struct SkillContext {
+    /// +skills from items, added to every skill that has a hard point
+    pub bonus_levels    : i32,
+    /// hard points in other skills by skill id, used by synergies
+    pub skill_levels    : HashMap<i16, i32>,
+    /// character stats by itemstatcost id
+    pub stats           : HashMap<u16, i32>,
+}
+
+/// evaluates the calcs of one skills.bin row at a base level
+pub struct SkillEval<'a> {
+    tbls    : &'a DataTblsManager,
+    skill   : &'a Record,
+    blvl    : i32,
+    ctx     : &'a SkillContext,
+    depth   : u32,
+}
+
+/// sums a value that grows by a different amount per level range, the
+/// ranges are 2-8, 9-16, 17-22, 23-28 and 29+ like the game's damage columns.
+fn leveled(base: i64, per_level: &[i64], lvl: i64) -> i64 {
+    const RANGES: [(i64, i64); 5] = [(1, 8), (8, 16), (16, 22), (22, 28), (28, i64::MAX)];
+
+    let mut value = base;
+    for (i, add) in per_level.iter().enumerate() {
+        let (from, to) = RANGES[i];
+        let to = if i + 1 == per_level.len() { i64::MAX } else { to };
+        if lvl > from {
+            value += (lvl.min(to) - from) * add;
+        }
+    }
+    value
+}
+
+/// `ln12` style linear value
+fn linear(base: i64, per_level: i64, lvl: i64) -> i64 {
+    base + (lvl - 1) * per_level
+}
+
+/// `dm12` style diminishing value, approaches `max` at high levels. each
+/// step truncates like the game does
+fn diminishing(min: i64, max: i64, lvl: i64) -> i64 {
+    min + (max - min) * ((110 * lvl) / (lvl + 6)) / 100
+}
+
+impl<'a> SkillEval<'a> {
+    pub fn new(tbls: &'a DataTblsManager, skill: &'a Record, blvl: i32, ctx: &'a SkillContext) -> Self {
+        Self {
+            tbls,
+            skill,
+            blvl,
+            ctx,
+            depth: 0,
+        }
+    }
+
+    /// the evaluation of `skill` one lookup deeper, `None` past `MAX_DEPTH`
+    fn nested(&self, skill: &'a Record, blvl: i32) -> Option<SkillEval<'a>> {
+        (self.depth < MAX_DEPTH).then(|| SkillEval{
+            tbls    : self.tbls,
+            skill,
+            blvl,
+            ctx     : self.ctx,
+            depth   : self.depth + 1,
+        })
+    }
+
+    /// a calc of this skill used by one of its calcs
+    fn clc(&self, key: &str) -> i64 {
+        self.nested(self.skill, self.blvl).map_or(0, |e| e.calc(key))
+    }
+
+    fn get(&self, key: &str) -> i64 {
+        self.skill.get(key).value.as_i64().unwrap()
+    }
+
+    fn param(&self, i: usize) -> i64 {
+        self.get(&format!("param_{i}"))
+    }
+
+    /// base level plus bonus levels, 0 without hard points
+    pub fn lvl(&self) -> i64 {
+        if self.blvl > 0 { (self.blvl + self.ctx.bonus_levels) as i64 } else { 0 }
+    }
+
+    /// evaluates the calc field `key`, 0 for empty calcs or a missing code buffer
+    pub fn calc(&self, key: &str) -> i64 {
+        self.expr(key).map(|e| e.eval(self)).unwrap_or(0)
+    }
+
+    /// evaluates a calc of another code buffer (e.g. skilldesc) against this skill
+    pub fn calc_at(&self, code: &str, offset: u32) -> i64 {
+        self.tbls.calc_code(code)
+            .and_then(|code| code.decode(offset).ok().flatten())
+            .map(|e| e.eval(self))
+            .unwrap_or(0)
+    }
+
+    pub fn expr(&self, key: &str) -> Option<Expr> {
+        let field = self.skill.get(key);
+        let code = self.tbls.calc_code(field.calc?)?;
+        code.decode(field.calc_offsets()[0]).ok().flatten()
+    }
+
+    fn shifted(&self, value: i64) -> i64 {
+        (value << self.get("to_hit_shift")) >> 8
+    }
+
+    fn with_synergy(&self, value: i64, key: &str) -> i64 {
+        value * (100 + self.calc(key)) / 100
+    }
+
+    pub fn mana_cost(&self) -> i64 {
+        let cost = ((linear(self.get("mana"), self.get("level_mana"), self.lvl())) << self.get("mana_shift")) >> 8;
+        cost.max(self.get("min_mana"))
+    }
+
+    pub fn to_hit(&self) -> i64 {
+        linear(self.get("to_hit"), self.get("lev_to_hit"), self.lvl())
+    }
+
+    fn damage(&self, base: &str, per_level: &str) -> i64 {
+        let per_level: Vec<i64> = (1..=5).map(|i| self.get(&format!("{per_level}_{i}"))).collect();
+        self.shifted(leveled(self.get(base), &per_level, self.lvl()))
+    }
+
+    pub fn min_damage(&self) -> i64 {
+        self.with_synergy(self.damage("min_dam", "min_lvl_dam"), "dmg_sym_per_calc")
+    }
+
+    pub fn max_damage(&self) -> i64 {
+        self.with_synergy(self.damage("max_dam", "max_lvl_dam"), "dmg_sym_per_calc")
+    }
+
+    pub fn elem_min(&self) -> i64 {
+        self.with_synergy(self.damage("e_min", "e_min_lev"), "e_dmg_sym_per_calc")
+    }
+
+    pub fn elem_max(&self) -> i64 {
+        self.with_synergy(self.damage("e_max", "e_max_lev"), "e_dmg_sym_per_calc")
+    }
+
+    fn elem_base_len(&self) -> i64 {
+        let per_level: Vec<i64> = (1..=3).map(|i| self.get(&format!("e_lev_len_{i}"))).collect();
+        leveled(self.get("e_len"), &per_level, self.lvl())
+    }
+
+    /// elemental length in frames
+    pub fn elem_len(&self) -> i64 {
+        self.with_synergy(self.elem_base_len(), "e_len_sym_per_calc")
+    }
+
+    /// (stat id, value) of the aura stats with a stat set
+    pub fn aura_stats(&self) -> Vec<(u16, i64)> {
+        (1..=6)
+            .filter(|i| self.get(&format!("arua_stat_{i}")) >= 0)
+            .map(|i| (self.get(&format!("arua_stat_{i}")) as u16, self.calc(&format!("arua_stat_calc_{i}"))))
+            .collect()
+    }
+
+    /// (stat id, value) of the passive stats with a stat set
+    pub fn passive_stats(&self) -> Vec<(u16, i64)> {
+        (1..=5)
+            .filter(|i| self.get(&format!("passive_stat_{i}")) >= 0)
+            .map(|i| (self.get(&format!("passive_stat_{i}")) as u16, self.calc(&format!("passive_calc_{i}"))))
+            .collect()
+    }
+}
+
+impl<'a> CalcContext for SkillEval<'a> {
+    fn value(&self, name: &str) -> i64 {
+        let lvl = self.lvl();
+
+        match name {
+            "ln12" => linear(self.param(1), self.param(2), lvl),
+            "ln34" => linear(self.param(3), self.param(4), lvl),
+            "ln56" => linear(self.param(5), self.param(6), lvl),
+            "ln78" => linear(self.param(7), self.param(8), lvl),
+            "dm12" => diminishing(self.param(1), self.param(2), lvl),
+            "dm34" => diminishing(self.param(3), self.param(4), lvl),
+            "dm56" => diminishing(self.param(5), self.param(6), lvl),
+            "dm78" => diminishing(self.param(7), self.param(8), lvl),
+            "clc1" => self.clc("calc_1"),
+            "clc2" => self.clc("calc_2"),
+            "clc3" => self.clc("calc_3"),
+            "clc4" => self.clc("calc_4"),
+            "edmn" => self.damage("e_min", "e_min_lev"),
+            "edmx" => self.damage("e_max", "e_max_lev"),
+            "edln" => self.elem_base_len(),
+            "usmc" => self.mana_cost(),
+            "toht" => self.to_hit(),
+            "lvl" => lvl,
+            "blvl" => self.blvl as i64,
+            _ => match name.strip_prefix("par").and_then(|i| i.parse::<usize>().ok()) {
+                Some(i) => self.param(i),
+                None => 0,
+            },
+        }
+    }
+
+    fn reference(&self, func: &str, id: u16, param: &str) -> i64 {
+        match func {
+            "skill" | "sklvl" => {
+                let skill = match self.tbls.skills.records().get(id as usize) {
+                    Some(skill) => skill,
+                    None => return 0,
+                };
+
+                let blvl = self.ctx.skill_levels.get(&(id as i16)).copied().unwrap_or(0);
+                self.nested(skill, blvl).map_or(0, |other| other.value(param))
+            },
+            "stat" => match param {
+                "accr" | "base" => self.ctx.stats.get(&id).copied().unwrap_or(0) as i64,
+                _ => 0,
+            },
+            _ => 0,
+        }
+    }
+}
+
+/// the evaluated values of a skill from level 1 to its max level
+pub struct ProgressionTable {
+    pub skill_id    : i16,
+    pub columns     : Vec<String>,
+    pub rows        : Vec<Vec<i64>>,
+}
+
+impl ProgressionTable {
+    /// `max_lvl` of 0 means the skill has no cap, 20 levels are evaluated then
+    pub fn build(tbls: &DataTblsManager, skill: &Record, ctx: &SkillContext) -> Self {
+        let max_lvl = match skill.get("max_lvl").value.u16() {
+            0 => 20,
+            n => n as i32,
+        };
+
+        let stat_name = |id: u16| {
+            tbls.table("itemstatcost")
+                .and_then(|t| t.records().get(id as usize))
+                .and_then(|r| tbls.row_name("itemstatcost", r))
+                .unwrap_or_else(|| format!("stat_{id}"))
+        };
+
+        let first = SkillEval::new(tbls, skill, 1, ctx);
+        let calcs: Vec<&str> = ["calc_1", "calc_2", "calc_3", "calc_4"].into_iter().filter(|c| first.expr(c).is_some()).collect();
+
+        let mut columns: Vec<String> = ["level", "mana", "to_hit", "min_dam", "max_dam", "e_min", "e_max", "e_len"]
+            .iter().map(|c| c.to_string()).collect();
+        columns.extend(calcs.iter().map(|c| c.to_string()));
+        columns.extend(first.aura_stats().iter().map(|(id, _)| format!("aura:{}", stat_name(*id))));
+        columns.extend(first.passive_stats().iter().map(|(id, _)| format!("passive:{}", stat_name(*id))));
+
+        let mut rows = Vec::new();
+
+        for blvl in 1..=max_lvl {
+            let eval = SkillEval::new(tbls, skill, blvl, ctx);
+
+            let mut row = vec![
+                eval.lvl(),
+                eval.mana_cost(),
+                eval.to_hit(),
+                eval.min_damage(),
+                eval.max_damage(),
+                eval.elem_min(),
+                eval.elem_max(),
+                eval.elem_len(),
+            ];
+            row.extend(calcs.iter().map(|c| eval.calc(c)));
+            row.extend(eval.aura_stats().into_iter().map(|(_, v)| v));
+            row.extend(eval.passive_stats().into_iter().map(|(_, v)| v));
+
+            rows.push(row);
+        }
+
+        Self {
+            skill_id: skill.get("skill_id").value.i16(),
+            columns,
+            rows,
+        }
+    }
+
+    pub fn dump_csv(&self, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        lines.push(self.columns.iter().map(|c| csv_str(c)).collect::<Vec<_>>().join(","));
+
+        for row in self.rows.iter() {
+            lines.push(row.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(","));
+        }
+
+        let mut csv = std::fs::File::create(file_name)?;
+        csv.write_all(lines.join("\r\n").as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn dump_json(&self, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        lines.push("{".into());
+        lines.push(format!("  \"skill_id\": {},", self.skill_id));
+        lines.push("  \"levels\": [".into());
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let values: Vec<String> = self.columns.iter().zip(row.iter()).map(|(c, v)| format!("{}: {}", json_str(c), v)).collect();
+            lines.push(format!("    {{{}}}{}", values.join(", "), if i + 1 < self.rows.len() { "," } else { "" }));
+        }
+
+        lines.push("  ]".into());
+        lines.push("}".into());
+
+        let mut json = std::fs::File::create(file_name)?;
+        json.write_all(lines.join("\n").as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bin::tests::zeroed;
+    use crate::calc::{CalcCode, SKILL_CALC};
+    use crate::fields;
+
+    /// one skill with 3 levels, mana, to hit, damage, two calcs and an aura stat.
+    /// offset 0 of the code is `0` so the zeroed calc fields evaluate to 0
+    fn tbls(calcs: &[(&str, &str)]) -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+        let mut code = CalcCode::from_bytes(Vec::new(), SKILL_CALC);
+        code.encode("0").unwrap();
+
+        tbls.skills = zeroed(&fields::SKILLS, 1);
+        let skill = tbls.skills.get_mut(0).unwrap();
+
+        for (key, value) in [
+            ("skill_id",        Value::Int16(7)),
+            ("max_lvl",         Value::UInt16(3)),
+            ("param_1",         Value::Int32(10)),
+            ("param_2",         Value::Int32(5)),
+            ("mana",            Value::Int16(10)),
+            ("level_mana",      Value::Int16(4)),
+            ("mana_shift",      Value::UInt8(6)),
+            ("min_mana",        Value::UInt16(3)),
+            ("to_hit",          Value::UInt32(20)),
+            ("lev_to_hit",      Value::UInt32(5)),
+            ("to_hit_shift",    Value::UInt8(8)),
+            ("min_dam",         Value::UInt32(2)),
+            ("max_dam",         Value::UInt32(4)),
+            ("min_lvl_dam_1",   Value::UInt32(1)),
+            ("max_lvl_dam_1",   Value::UInt32(2)),
+            ("calc_3",          Value::UInt32(u32::MAX)),
+            ("calc_4",          Value::UInt32(u32::MAX)),
+        ] {
+            skill.set(key, value);
+        }
+
+        for i in 1..=6 {
+            skill.set(&format!("arua_stat_{i}"), Value::Int16(if i == 1 { 0 } else { -1 }));
+        }
+
+        for i in 1..=5 {
+            skill.set(&format!("passive_stat_{i}"), Value::Int16(-1));
+        }
+
+        skill.set("arua_stat_calc_1", Value::Int32(code.encode("ln12").unwrap() as i32));
+
+        for (key, calc) in calcs {
+            skill.set(key, Value::UInt32(code.encode(calc).unwrap()));
+        }
+
+        tbls.skills_code = code;
+        tbls
+    }
+
+    #[test]
+    fn leveled_switches_rate_at_the_range_ends() {
+        let per_level = [1, 2, 3, 4, 5];
+
+        assert_eq!(leveled(10, &per_level, 1), 10);
+        assert_eq!(leveled(10, &per_level, 8), 17);
+        assert_eq!(leveled(10, &per_level, 9), 19);
+        assert_eq!(leveled(10, &per_level, 16), 33);
+        assert_eq!(leveled(10, &per_level, 17), 36);
+
+        // the last rate goes on past its range
+        assert_eq!(leveled(0, &[1, 2, 3], 30), 7 + 16 + 14 * 3);
+    }
+
+    #[test]
+    fn diminishing_truncates_each_step() {
+        assert_eq!(diminishing(0, 1000, 1), 150);
+        assert_eq!(diminishing(10, 110, 20), 94);
+        assert_eq!(diminishing(0, 100, 0), 0);
+    }
+
+    #[test]
+    fn mana_cost_is_shifted_and_clamped() {
+        let tbls = tbls(&[]);
+        let skill = &tbls.skills.records()[0];
+        let ctx = SkillContext::default();
+        let mana = |blvl| SkillEval::new(&tbls, skill, blvl, &ctx).mana_cost();
+
+        // (10 + 4 * (lvl - 1)) / 4
+        assert_eq!(mana(1), 3);
+        assert_eq!(mana(2), 3);
+        assert_eq!(mana(3), 4);
+        assert_eq!(mana(6), 7);
+    }
+
+    #[test]
+    fn clc_cycles_end_at_the_depth_limit() {
+        let tbls = tbls(&[("calc_1", "ln12"), ("calc_2", "clc1*2"), ("calc_3", "clc3+1")]);
+        let skill = &tbls.skills.records()[0];
+        let ctx = SkillContext::default();
+        let eval = SkillEval::new(&tbls, skill, 2, &ctx);
+
+        assert_eq!(eval.calc("calc_2"), 30);
+        assert_eq!(eval.calc("calc_3"), MAX_DEPTH as i64 + 1);
+    }
+
+    #[test]
+    fn progression_has_a_row_per_level() {
+        let tbls = tbls(&[("calc_1", "lvl*2"), ("calc_2", "dm12")]);
+        let skill = &tbls.skills.records()[0];
+        let table = ProgressionTable::build(&tbls, skill, &SkillContext::default());
+
+        assert_eq!(table.skill_id, 7);
+        assert_eq!(table.columns, ["level", "mana", "to_hit", "min_dam", "max_dam", "e_min", "e_max", "e_len", "calc_1", "calc_2", "aura:stat_0"]);
+        // dm12 from 10 to 5 truncates toward zero
+        assert_eq!(table.rows, [
+            [1, 3, 20, 2, 4, 0, 0, 0, 2, 10, 10],
+            [2, 3, 25, 3, 6, 0, 0, 0, 4, 9, 15],
+            [3, 4, 30, 4, 8, 0, 0, 0, 6, 9, 20],
+        ]);
+
+        let path = std::env::temp_dir().join(format!("datatbls-progression-{}.csv", std::process::id()));
+        table.dump_csv(path.to_str().unwrap()).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(csv, "level,mana,to_hit,min_dam,max_dam,e_min,e_max,e_len,calc_1,calc_2,aura:stat_0\r\n\
+            1,3,20,2,4,0,0,0,2,10,10\r\n\
+            2,3,25,3,6,0,0,0,4,9,15\r\n\
+            3,4,30,4,8,0,0,0,6,9,20");
+    }
+}
@@ -0,0 +1,348 @@
+#![allow(unused)]
+
+use std::collections::HashMap;
+use std::io::Write;
+use anyhow::Result;
+
+use crate::bin::*;
+use crate::fields;
+use crate::stringtbl::strip_color_codes;
+use crate::datatbls_mgr::{DataTblsManager, json_str};
+
+/// the skilldesc.bin row of a skill
+#[derive(Debug, Clone)]
+pub struct SkillDesc {
+    pub row         : usize,
+    pub page        : u8,
+    pub tree_row    : u8,
+    pub tree_column : u8,
+    pub list_row    : u8,
+    pub list_pool   : u8,
+    pub icon_cel    : u8,
+}
+
+/// a skills.bin row joined with its skilldesc.bin row and strings
+#[derive(Debug, Clone)]
+pub struct SkillDef {
+    pub id              : i16,
+    pub row             : usize,
+    pub char_class      : i8,
+    pub name            : Option<String>,
+    pub short_desc      : Option<String>,
+    pub long_desc       : Option<String>,
+    pub alt_desc        : Option<String>,
+    pub req_level       : u16,
+    pub req_str         : u16,
+    pub req_dex         : u16,
+    pub req_int         : u16,
+    pub req_vit         : u16,
+    pub max_lvl         : u16,
+    pub prerequisites   : Vec<i16>,
+    pub desc            : Option<SkillDesc>,
+}
+
+impl SkillDef {
+    fn new(tbls: &DataTblsManager, row: usize, skill: &Record) -> Self {
+        let desc = tbls.resolve(skill, "skill_desc");
+        let string = |key: &str| -> Option<String> {
+            let index = desc?.get(key).value.str_id();
+            tbls.get_string_by_index(index).map(|s| s.to_string())
+        };
+
+        let prerequisites = (1..=3)
+            .map(|i| skill.get(&format!("req_skill_{i}")).value.i16())
+            .filter(|id| *id >= 0)
+            .collect();
+
+        Self {
+            id              : skill.get("skill_id").value.i16(),
+            row,
+            char_class      : skill.get("char_class").value.i8(),
+            name            : string("str_name"),
+            short_desc      : string("str_short"),
+            long_desc       : string("str_long"),
+            alt_desc        : string("str_alt"),
+            req_level       : skill.get("req_level").value.u16(),
+            req_str         : skill.get("req_str").value.u16(),
+            req_dex         : skill.get("req_dex").value.u16(),
+            req_int         : skill.get("req_int").value.u16(),
+            req_vit         : skill.get("req_vit").value.u16(),
+            max_lvl         : skill.get("max_lvl").value.u16(),
+            prerequisites,
+            desc            : skill.get("skill_desc").ref_row().zip(desc).map(|(row, desc)| SkillDesc::new(row, desc)),
+        }
+    }
+
+    pub fn class_name(&self) -> String {
+        fields::CHAR_CLASS.format(self.char_class as i64)
+    }
+}
+
+impl SkillDesc {
+    fn new(row: usize, desc: &Record) -> Self {
+        Self {
+            row,
+            page        : desc.get("skill_page").value.u8(),
+            tree_row    : desc.get("skill_row").value.u8(),
+            tree_column : desc.get("skill_column").value.u8(),
+            list_row    : desc.get("list_row").value.u8(),
+            list_pool   : desc.get("list_pool").value.u8(),
+            icon_cel    : desc.get("icon_cel").value.u8(),
+        }
+    }
+}
+
+pub struct SkillCatalog {
+    skills  : Vec<SkillDef>,
+    by_id   : HashMap<i16, usize>,
+    by_name : HashMap<String, usize>,
+}
+
+impl Default for SkillCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SkillCatalog {
+    pub fn new() -> Self {
+        Self {
+            skills  : Vec::new(),
+            by_id   : HashMap::new(),
+            by_name : HashMap::new(),
+        }
+    }
+
+    pub fn build(tbls: &DataTblsManager) -> Self {
+        let mut catalog = Self::new();
+
+        for (row, skill) in tbls.skills.iter().enumerate() {
+            let def = SkillDef::new(tbls, row, skill);
+
+            catalog.by_id.entry(def.id).or_insert(row);
+
+            if let Some(name) = &def.name {
+                catalog.by_name.entry(Self::name_key(name)).or_insert(row);
+            }
+
+            catalog.skills.push(def);
+        }
+
+        catalog
+    }
+
+    fn name_key(name: &str) -> String {
+        strip_color_codes(name).trim().to_lowercase()
+    }
+
+    pub fn len(&self) -> usize {
+        self.skills.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.skills.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, SkillDef> {
+        self.skills.iter()
+    }
+
+    pub fn get(&self, id: i16) -> Option<&SkillDef> {
+        self.by_id.get(&id).map(|row| &self.skills[*row])
+    }
+
+    /// case insensitive, color codes are ignored
+    pub fn get_by_name(&self, name: &str) -> Option<&SkillDef> {
+        self.by_name.get(&Self::name_key(name)).map(|row| &self.skills[*row])
+    }
+
+    pub fn get_by_class(&self, char_class: i8) -> Vec<&SkillDef> {
+        self.skills.iter().filter(|s| s.char_class == char_class).collect()
+    }
+
+    /// the classes that have skills, in ascending order
+    pub fn classes(&self) -> Vec<i8> {
+        let mut classes: Vec<i8> = self.skills.iter().map(|s| s.char_class).collect();
+        classes.sort();
+        classes.dedup();
+        classes
+    }
+
+    /// writes the named skills grouped by class
+    pub fn dump_json(&self, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        lines.push("{".into());
+
+        let classes = self.classes();
+        for (i, char_class) in classes.iter().enumerate() {
+            let skills: Vec<&SkillDef> = self.get_by_class(*char_class).into_iter().filter(|s| s.name.is_some()).collect();
+            let class_name = fields::CHAR_CLASS.format(*char_class as i64);
+
+            lines.push(format!("  {}: [", json_str(&class_name)));
+
+            for (j, skill) in skills.iter().enumerate() {
+                let opt = |s: &Option<String>| s.as_deref().map(json_str).unwrap_or_else(|| "null".to_string());
+                let prerequisites: Vec<String> = skill.prerequisites.iter().map(|id| id.to_string()).collect();
+
+                lines.extend(vec![
+                    "    {".to_string(),
+                    format!("      \"id\": {},", skill.id),
+                    format!("      \"name\": {},", opt(&skill.name)),
+                    format!("      \"str_long\": {},", opt(&skill.long_desc)),
+                    format!("      \"char_class\": {},", json_str(&class_name)),
+                    format!("      \"req_level\": {},", skill.req_level),
+                    format!("      \"max_lvl\": {},", skill.max_lvl),
+                    format!("      \"prerequisites\": [{}]", prerequisites.join(", ")),
+                    format!("    }}{}", if j + 1 < skills.len() { "," } else { "" }),
+                ]);
+            }
+
+            lines.push(format!("  ]{}", if i + 1 < classes.len() { "," } else { "" }));
+        }
+
+        lines.push("}".into());
+
+        let mut json = std::fs::File::create(file_name)?;
+        json.write_all(lines.join("\n").as_bytes())?;
+
+        Ok(())
+    }
+
+    /// writes every skill as a tab separated row
+    pub fn dump_txt(&self, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        lines.push([
+            "id", "name", "charclass", "reqlevel", "maxlvl",
+            "reqstr", "reqdex", "reqint", "reqvit", "reqskills",
+            "SkillPage", "SkillRow", "SkillColumn", "IconCel",
+        ].join("\t"));
+
+        for skill in self.skills.iter() {
+            let prerequisites: Vec<String> = skill.prerequisites.iter().map(|id| id.to_string()).collect();
+            let desc = |f: fn(&SkillDesc) -> u8| skill.desc.as_ref().map(|d| f(d).to_string()).unwrap_or_default();
+
+            lines.push([
+                skill.id.to_string(),
+                skill.name.clone().unwrap_or_default(),
+                skill.class_name(),
+                skill.req_level.to_string(),
+                skill.max_lvl.to_string(),
+                skill.req_str.to_string(),
+                skill.req_dex.to_string(),
+                skill.req_int.to_string(),
+                skill.req_vit.to_string(),
+                prerequisites.join(","),
+                desc(|d| d.page),
+                desc(|d| d.tree_row),
+                desc(|d| d.tree_column),
+                desc(|d| d.icon_cel),
+            ].join("\t"));
+        }
+
+        let mut txt = std::fs::File::create(file_name)?;
+        txt.write_all(lines.join("\r\n").as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bin::tests::zeroed;
+    use crate::stringtbl::tests::manager;
+
+    /// Attack without a class, Fire Bolt and Warmth of the sorceress, Magic Arrow
+    /// of the amazon. Warmth has no skilldesc row
+    fn tbls() -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+        tbls.strtbl = manager(&[("dummy", ""), ("skillname0", "Attack"), ("skillname36", "Fire Bolt"), ("skillname6", "ÿc4Magic Arrow")], &[], &[]);
+
+        tbls.skill_desc = zeroed(&fields::SKILL_DESC, 3);
+        for (row, (name, page, column)) in [(1, 0, 0), (2, 1, 2), (3, 1, 1)].into_iter().enumerate() {
+            let desc = tbls.skill_desc.get_mut(row).unwrap();
+            desc.set("str_name", Value::StringId(name));
+            desc.set("skill_page", Value::UInt8(page));
+            desc.set("skill_row", Value::UInt8(1));
+            desc.set("skill_column", Value::UInt8(column));
+        }
+
+        tbls.skills = zeroed(&fields::SKILLS, 4);
+        for (row, (id, class, desc, req)) in [(0, -1, 0, -1), (36, 1, 1, -1), (37, 1, u16::MAX, 36), (6, 0, 2, -1)].into_iter().enumerate() {
+            let skill = tbls.skills.get_mut(row).unwrap();
+            skill.set("skill_id", Value::Int16(id));
+            skill.set("char_class", Value::Int8(class));
+            skill.set("skill_desc", Value::UInt16(desc));
+            skill.set("req_skill_1", Value::Int16(req));
+            skill.set("req_skill_2", Value::Int16(-1));
+            skill.set("req_skill_3", Value::Int16(-1));
+            skill.set("max_lvl", Value::UInt16(20));
+        }
+
+        tbls
+    }
+
+    #[test]
+    fn joins_skills_with_their_desc() {
+        let tbls = tbls();
+        let catalog = SkillCatalog::build(&tbls);
+        assert_eq!(catalog.len(), 4);
+
+        let bolt = catalog.get(36).unwrap();
+        assert_eq!(bolt.row, 1);
+        assert_eq!(bolt.name.as_deref(), Some("Fire Bolt"));
+        assert_eq!(bolt.class_name(), "sor");
+        assert!(bolt.prerequisites.is_empty());
+        let desc = bolt.desc.as_ref().unwrap();
+        assert_eq!((desc.row, desc.page, desc.tree_row, desc.tree_column), (1, 1, 1, 2));
+
+        // skill_desc 0xFFFF has no skilldesc row, so no name or desc
+        let warmth = catalog.get(37).unwrap();
+        assert_eq!(warmth.name, None);
+        assert!(warmth.desc.is_none());
+        assert_eq!(warmth.prerequisites, [36]);
+
+        assert!(std::ptr::eq(catalog.get_by_name("MAGIC arrow ").unwrap(), catalog.get(6).unwrap()));
+        assert!(catalog.get_by_name("warmth").is_none());
+        assert!(catalog.get(99).is_none());
+    }
+
+    #[test]
+    fn groups_skills_by_class() {
+        let catalog = SkillCatalog::build(&tbls());
+
+        assert_eq!(catalog.classes(), [-1, 0, 1]);
+        let ids = |class: i8| catalog.get_by_class(class).iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(ids(1), [36, 37]);
+        assert_eq!(ids(0), [6]);
+        assert!(ids(5).is_empty());
+    }
+
+    #[test]
+    fn dumps_named_skills_as_json_and_every_skill_as_txt() {
+        let catalog = SkillCatalog::build(&tbls());
+        let path = |ext: &str| std::env::temp_dir().join(format!("datatbls-skills-{}.{ext}", std::process::id()));
+        let (json_path, txt_path) = (path("json"), path("txt"));
+
+        catalog.dump_json(json_path.to_str().unwrap()).unwrap();
+        catalog.dump_txt(txt_path.to_str().unwrap()).unwrap();
+        let json = std::fs::read_to_string(&json_path).unwrap();
+        let txt = std::fs::read_to_string(&txt_path).unwrap();
+        std::fs::remove_file(&json_path).unwrap();
+        std::fs::remove_file(&txt_path).unwrap();
+
+        // the unnamed Warmth is left out of the json
+        let classes: Vec<&str> = json.lines().filter(|l| l.ends_with(": [")).map(|l| l.trim()).collect();
+        assert_eq!(classes, [r#""-1": ["#, r#""ama": ["#, r#""sor": ["#]);
+        assert!(json.contains(r#""name": "Fire Bolt","#));
+        assert!(!json.contains(r#""id": 37,"#));
+
+        let rows: Vec<&str> = txt.split("\r\n").collect();
+        assert_eq!(rows.len(), 5);
+        assert!(rows[0].starts_with("id\tname\tcharclass"));
+        assert_eq!(rows[2], "36\tFire Bolt\tsor\t0\t20\t0\t0\t0\t0\t\t1\t1\t2\t0");
+        assert_eq!(rows[3], "37\t\tsor\t0\t20\t0\t0\t0\t0\t36\t\t\t\t");
+    }
+}
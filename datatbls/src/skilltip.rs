@@ -0,0 +1,412 @@
+#![allow(unused)]
+
+//! renders the in-game skill tooltip from the skilldesc.bin description lines.
+//! `desc_line[17]` holds the function of each line: lines 0..6 are the
+//! `descline` columns shown for the current and the next level, 6..10 the
+//! `dsc2line` columns above them and 10..17 the `dsc3line` columns of the
+//! synergy section. Each kind numbers its functions on its own.
+
+use crate::bin::*;
+use crate::fields;
+use crate::stringtbl::{StringTableManager, strip_color_codes};
+use crate::skillcalc::{SkillContext, SkillEval};
+use crate::datatbls_mgr::DataTblsManager;
+
+const LEVEL_LINES   : std::ops::Range<usize> = 0..6;
+const TOP_LINES     : std::ops::Range<usize> = 6..10;
+const SYNERGY_LINES : std::ops::Range<usize> = 10..17;
+
+/// templates of the `descline` functions. `{ta}`/`{tb}` are desc_text_a/b as
+/// the string tables have them, punctuation and units included, `{a}`/`{b}`
+/// desc_calc_a/b, `{as}` calc a in seconds (frames / 25) and `{ay}` in yards
+/// (subtiles * 2 / 3). Only functions 1-13 are implemented, higher ones render
+/// as `{ta}{a}{tb}`.
+pub static DESC_LINE_FUNCS: &[(u8, &str)] = &[
+    (1,     "{ta}+{a}%"),
+    (2,     "{ta}+{a}"),
+    (3,     "{ta}{a}"),
+    (4,     "{ta}{a}%"),
+    (5,     "{ta}{a}{tb}"),
+    (6,     "{ta}+{a}{tb}"),
+    (7,     "{ta}{a}-{b}{tb}"),
+    (8,     "{ta}+{a}-{b}{tb}"),
+    (9,     "{ta}{as}{tb}"),
+    (10,    "{ta}{ay}{tb}"),
+    (11,    "{ta}"),
+    (12,    "{ta}{tb}"),
+    (13,    "{tb}{a}{ta}"),
+];
+
+/// templates of the `dsc2line` functions, see `DESC_LINE_FUNCS`. Functions
+/// above 6 render as `{ta}{a}{tb}`
+pub static DSC2_LINE_FUNCS: &[(u8, &str)] = &[
+    (1,     "{ta}{a}{tb}"),
+    (2,     "{ta}"),
+    (3,     "{ta}{tb}"),
+    (4,     "{ta}{as}{tb}"),
+    (5,     "{ta}{ay}{tb}"),
+    (6,     "{ta}{a}-{b}{tb}"),
+];
+
+/// templates of the `dsc3line` functions, see `DESC_LINE_FUNCS`. Functions
+/// above 4 render as `{ta}{a}{tb}`
+pub static DSC3_LINE_FUNCS: &[(u8, &str)] = &[
+    (1,     "{ta}+{a}%{tb}"),
+    (2,     "{ta}+{a}{tb}"),
+    (3,     "{ta}{a}{tb}"),
+    (4,     "{ta}{tb}"),
+];
+
+/// the fixed texts of the tooltip. `{n}`, `{min}`, `{max}` and `{elem}` are
+/// replaced when rendering
+pub struct TooltipLabels {
+    pub current_level   : String,
+    pub next_level      : String,
+    pub required_level  : String,
+    pub mana_cost       : String,
+    pub damage          : String,
+    pub elem_damage     : String,
+    pub synergies       : String,
+    /// elemtypes.txt code and its name
+    pub elements        : Vec<(&'static str, String)>,
+}
+
+/// the string keys `TooltipLabels::from_strings` reads, in field order, with
+/// the English text used when a key is missing
+pub static LABEL_KEYS: [(&str, &str); 7] = [
+    ("StrSkill1",   "Current Skill Level: %d"),
+    ("StrSkill2",   "Next Level"),
+    ("ItemStats1p", "Required Level: %d"),
+    ("StrSkill3",   "Mana Cost: %d"),
+    ("StrSkill4",   "Damage: %d-%d"),
+    ("StrSkill5",   "%s Damage: %d-%d"),
+    ("StrSkill6",   "Receives Bonuses From:"),
+];
+
+/// the elemtypes.txt codes with the string key of their name and the English
+/// text used when the key is missing
+pub static ELEM_KEYS: [(&str, &str, &str); 12] = [
+    ("fire",    "StrElemFire",  "Fire"),
+    ("ltng",    "StrElemLtng",  "Lightning"),
+    ("mag",     "StrElemMag",   "Magic"),
+    ("cold",    "StrElemCold",  "Cold"),
+    ("pois",    "StrElemPois",  "Poison"),
+    ("life",    "StrElemLife",  "Life Drain"),
+    ("mana",    "StrElemMana",  "Mana Drain"),
+    ("stam",    "StrElemStam",  "Stamina Drain"),
+    ("stun",    "StrElemStun",  "Stun"),
+    ("rand",    "StrElemRand",  "Random"),
+    ("burn",    "StrElemBurn",  "Burning"),
+    ("frze",    "StrElemFrze",  "Freeze"),
+];
+
+/// replaces the `%d`/`%s` of a game string by `{name}` placeholders in order
+fn placeholders(s: &str, names: &[&str]) -> String {
+    let mut out = s.to_string();
+
+    for name in names {
+        let at = ["%d", "%s"].iter().filter_map(|p| out.find(p)).min();
+        if let Some(at) = at {
+            out.replace_range(at..at + 2, &format!("{{{name}}}"));
+        }
+    }
+
+    out
+}
+
+impl Default for TooltipLabels {
+    fn default() -> Self {
+        Self::from_texts(LABEL_KEYS.map(|(_, text)| text), ELEM_KEYS.map(|(_, _, text)| text))
+    }
+}
+
+impl TooltipLabels {
+    /// the labels of the loaded string tables, English for missing keys
+    pub fn from_strings(strtbl: &StringTableManager) -> Self {
+        Self::from_texts(
+            LABEL_KEYS.map(|(key, text)| strtbl.get_string_by_key(key).unwrap_or(text)),
+            ELEM_KEYS.map(|(_, key, text)| strtbl.get_string_by_key(key).unwrap_or(text)),
+        )
+    }
+
+    fn from_texts(texts: [&str; 7], elements: [&str; 12]) -> Self {
+        Self {
+            current_level   : placeholders(texts[0], &["n"]),
+            next_level      : texts[1].into(),
+            required_level  : placeholders(texts[2], &["n"]),
+            mana_cost       : placeholders(texts[3], &["n"]),
+            damage          : placeholders(texts[4], &["min", "max"]),
+            elem_damage     : placeholders(texts[5], &["elem", "min", "max"]),
+            synergies       : texts[6].into(),
+            elements        : ELEM_KEYS.iter().zip(elements).map(|((code, _, _), text)| (*code, text.trim().to_string())).collect(),
+        }
+    }
+
+    /// the name of elemtypes.txt code `elem`, unknown codes are shown as they are
+    pub fn elem_label<'s>(&'s self, elem: &'s str) -> &'s str {
+        self.elements.iter().find(|(code, _)| *code == elem).map(|(_, name)| name.as_str()).unwrap_or(elem)
+    }
+}
+
+pub struct SkillTooltip<'a> {
+    tbls            : &'a DataTblsManager,
+    pub labels      : TooltipLabels,
+    pub strip_colors: bool,
+}
+
+impl<'a> SkillTooltip<'a> {
+    pub fn new(tbls: &'a DataTblsManager) -> Self {
+        Self {
+            tbls,
+            labels: TooltipLabels::from_strings(&tbls.strtbl),
+            strip_colors: false,
+        }
+    }
+
+    /// the tooltip of skill `id` with `blvl` hard points, `None` for skills without skilldesc
+    pub fn render(&self, id: i16, blvl: i32, ctx: &SkillContext) -> Option<String> {
+        let def = self.tbls.skill_catalog.get(id)?;
+        let skill = &self.tbls.skills.records()[def.row];
+        let desc = &self.tbls.skill_desc.records()[def.desc.as_ref()?.row];
+
+        let mut lines = Vec::<String>::new();
+
+        lines.extend(def.name.clone());
+        lines.extend(def.long_desc.clone());
+        lines.push(self.labels.required_level.replace("{n}", &def.req_level.to_string()));
+        lines.extend(self.desc_lines(desc, &SkillEval::new(self.tbls, skill, blvl.max(1), ctx), TOP_LINES, DSC2_LINE_FUNCS));
+
+        if blvl > 0 {
+            lines.push(String::new());
+            lines.push(self.labels.current_level.replace("{n}", &blvl.to_string()));
+            lines.extend(self.level_lines(skill, desc, blvl, ctx));
+        }
+
+        if def.max_lvl == 0 || blvl < def.max_lvl as i32 {
+            lines.push(String::new());
+            lines.push(self.labels.next_level.clone());
+            lines.extend(self.level_lines(skill, desc, blvl + 1, ctx));
+        }
+
+        let synergies = self.desc_lines(desc, &SkillEval::new(self.tbls, skill, blvl.max(1), ctx), SYNERGY_LINES, DSC3_LINE_FUNCS);
+        if !synergies.is_empty() {
+            lines.push(String::new());
+            lines.push(self.labels.synergies.clone());
+            lines.extend(synergies);
+        }
+
+        let text = lines.join("\n");
+
+        Some(if self.strip_colors { strip_color_codes(&text) } else { text })
+    }
+
+    fn level_lines(&self, skill: &Record, desc: &Record, blvl: i32, ctx: &SkillContext) -> Vec<String> {
+        let eval = SkillEval::new(self.tbls, skill, blvl, ctx);
+        let mut lines = self.desc_lines(desc, &eval, LEVEL_LINES, DESC_LINE_FUNCS);
+
+        let (min, max) = (eval.min_damage(), eval.max_damage());
+        if max > 0 {
+            lines.push(self.labels.damage.replace("{min}", &min.to_string()).replace("{max}", &max.to_string()));
+        }
+
+        let (min, max) = (eval.elem_min(), eval.elem_max());
+        if max > 0 {
+            let elem = skill.get("e_type").enum_format();
+            lines.push(self.labels.elem_damage
+                .replace("{elem}", self.labels.elem_label(&elem))
+                .replace("{min}", &min.to_string())
+                .replace("{max}", &max.to_string()));
+        }
+
+        // the skill's own str_mana text is shown in front of the cost when it has one
+        let mana = eval.mana_cost();
+        if mana > 0 {
+            let str_mana = desc.get("str_mana").value.str_id();
+            lines.push(match self.tbls.get_string_by_index(str_mana) {
+                Some(text) if str_mana != 0 && !text.is_empty() => format!("{text}{mana}"),
+                _ => self.labels.mana_cost.replace("{n}", &mana.to_string()),
+            });
+        }
+
+        lines
+    }
+
+    fn desc_lines(&self, desc: &Record, eval: &SkillEval, range: std::ops::Range<usize>, templates: &[(u8, &str)]) -> Vec<String> {
+        let funcs = desc.get("desc_line").value.clone();
+        let text_a = desc.get("desc_text_a").value.clone();
+        let text_b = desc.get("desc_text_b").value.clone();
+        let calc_a = desc.get("desc_calc_a").calc_offsets();
+        let calc_b = desc.get("desc_calc_b").calc_offsets();
+
+        let (funcs, text_a, text_b) = match (funcs, text_a, text_b) {
+            (Value::U8Array(f), Value::U16Array(a), Value::U16Array(b)) => (f, a, b),
+            _ => panic!("unexpected skilldesc layout"),
+        };
+
+        let mut lines = Vec::new();
+
+        for i in range {
+            if funcs[i] == 0 {
+                continue;
+            }
+
+            let string = |index: u16| self.tbls.get_string_by_index(index).unwrap_or("").to_string();
+            let a = eval.calc_at("skilldesc", calc_a[i]);
+            let b = eval.calc_at("skilldesc", calc_b[i]);
+
+            let template = templates.iter()
+                .find(|(f, _)| *f == funcs[i])
+                .map(|(_, t)| *t)
+                .unwrap_or("{ta}{a}{tb}");
+
+            let line = template
+                .replace("{ta}", &string(text_a[i]))
+                .replace("{tb}", &string(text_b[i]))
+                .replace("{as}", &format!("{:.1}", a as f64 / 25.0))
+                .replace("{ay}", &format!("{:.1}", a as f64 * 2.0 / 3.0))
+                .replace("{a}", &a.to_string())
+                .replace("{b}", &b.to_string());
+
+            lines.push(line.trim().to_string());
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bin::tests::zeroed;
+    use crate::calc::{CalcCode, SKILL_CALC};
+    use crate::skills::SkillCatalog;
+    use crate::stringtbl::tests::manager;
+
+    /// a bolt skill: radius and damage per level, one top line and one synergy
+    fn tbls() -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+
+        tbls.strtbl = manager(
+            &[
+                ("dummy",       ""),
+                ("skillname36", "Fire Bolt"),
+                ("skillld36",   "Fires a bolt"),
+                ("StrSkill3",   "Mana Cost: "),
+                ("StrSkill21",  "Radius: "),
+                ("StrSkill22",  " yards"),
+                ("StrSkill11",  "Fire Damage: "),
+                ("StrSkill12",  " Per Level"),
+                ("skillsd36",   "Bolt"),
+            ],
+            &[
+                ("StrSkill1",   "当前技能等级：%d"),
+                ("StrSkill2",   "下一级"),
+            ],
+            &[
+                ("ItemStats1p", "需要等级：%d"),
+            ],
+        );
+
+        let mut code = CalcCode::from_bytes(Vec::new(), SKILL_CALC);
+        let radius = code.encode("lvl*3").unwrap();
+        let synergy = code.encode("16").unwrap();
+        tbls.skill_desc_code = code;
+
+        tbls.skills = zeroed(&fields::SKILLS, 1);
+        let skill = tbls.skills.get_mut(0).unwrap();
+        for (key, value) in [
+            ("skill_id",        Value::Int16(36)),
+            ("req_level",       Value::UInt16(1)),
+            ("max_lvl",         Value::UInt16(20)),
+            ("mana",            Value::Int16(5)),
+            ("level_mana",      Value::Int16(1)),
+            ("mana_shift",      Value::UInt8(8)),
+            ("to_hit_shift",    Value::UInt8(8)),
+            ("min_dam",         Value::UInt32(3)),
+            ("max_dam",         Value::UInt32(6)),
+            ("min_lvl_dam_1",   Value::UInt32(1)),
+            ("max_lvl_dam_1",   Value::UInt32(2)),
+        ] {
+            skill.set(key, value);
+        }
+
+        let mut funcs = vec![0u8; 17];
+        let mut text_a = vec![0u16; 17];
+        let mut text_b = vec![0u16; 17];
+        let mut calc_a = vec![u32::MAX; 17];
+
+        (funcs[0], text_a[0], text_b[0], calc_a[0]) = (10, 4, 5, radius);
+        (funcs[6], text_a[6]) = (2, 8);
+        // no dsc2line function 99, it renders as {ta}{a}{tb}
+        (funcs[7], text_a[7], text_b[7], calc_a[7]) = (99, 4, 5, radius);
+        (funcs[10], text_a[10], text_b[10], calc_a[10]) = (1, 6, 7, synergy);
+
+        tbls.skill_desc = zeroed(&fields::SKILL_DESC, 1);
+        let desc = tbls.skill_desc.get_mut(0).unwrap();
+        for (key, value) in [
+            ("str_name",        Value::StringId(1)),
+            ("str_long",        Value::StringId(2)),
+            ("str_mana",        Value::StringId(3)),
+            ("desc_line",       Value::U8Array(funcs)),
+            ("desc_text_a",     Value::U16Array(text_a)),
+            ("desc_text_b",     Value::U16Array(text_b)),
+            ("desc_calc_a",     Value::U32Array(calc_a)),
+        ] {
+            desc.set(key, value);
+        }
+
+        tbls.skill_catalog = SkillCatalog::build(&tbls);
+        tbls
+    }
+
+    #[test]
+    fn renders_a_known_skill() {
+        let tbls = tbls();
+        let tooltip = SkillTooltip::new(&tbls);
+
+        let expect = [
+            "Fire Bolt",
+            "Fires a bolt",
+            "需要等级：1",
+            "Bolt",
+            "Radius: 3 yards",
+            "",
+            "当前技能等级：1",
+            "Radius: 2.0 yards",
+            "Damage: 3-6",
+            "Mana Cost: 5",
+            "",
+            "下一级",
+            "Radius: 4.0 yards",
+            "Damage: 4-8",
+            "Mana Cost: 6",
+            "",
+            "Receives Bonuses From:",
+            "Fire Damage: +16% Per Level",
+        ];
+
+        assert_eq!(tooltip.render(36, 1, &SkillContext::default()).unwrap(), expect.join("\n"));
+        assert_eq!(tooltip.render(37, 1, &SkillContext::default()), None);
+    }
+
+    #[test]
+    fn labels_fill_game_placeholders() {
+        let labels = TooltipLabels::default();
+
+        assert_eq!(labels.current_level, "Current Skill Level: {n}");
+        assert_eq!(labels.elem_damage, "{elem} Damage: {min}-{max}");
+        assert_eq!(placeholders("%d%%: %s", &["a", "b"]), "{a}%%: {b}");
+    }
+
+    #[test]
+    fn element_names_come_from_the_string_tables() {
+        let strtbl = manager(&[("StrElemFire", "火焰"), ("StrElemCold", "冰冷 ")], &[], &[]);
+        let labels = TooltipLabels::from_strings(&strtbl);
+
+        assert_eq!(labels.elem_label("fire"), "火焰");
+        assert_eq!(labels.elem_label("cold"), "冰冷");
+        assert_eq!(labels.elem_label("ltng"), "Lightning");
+        assert_eq!(labels.elem_label("abcd"), "abcd");
+        assert_eq!(TooltipLabels::default().elem_label("fire"), "Fire");
+    }
+}
@@ -0,0 +1,224 @@
+#![allow(unused)]
+
+//! draws the skill trees of a class from the skilldesc page/row/column
+//! placement, with arrows for the req_skill prerequisites.
+
+use std::io::Write;
+use anyhow::Result;
+
+use crate::fields;
+use crate::stringtbl::strip_color_codes;
+use crate::skills::{SkillCatalog, SkillDef};
+use crate::datatbls_mgr::html_str;
+
+const CELL_W    : i32 = 120;
+const CELL_H    : i32 = 56;
+const GAP_X     : i32 = 24;
+const GAP_Y     : i32 = 36;
+const PAGE_GAP  : i32 = 48;
+const TITLE_H   : i32 = 32;
+
+pub struct SkillTree<'a> {
+    pub char_class  : i8,
+    catalog         : &'a SkillCatalog,
+    skills          : Vec<&'a SkillDef>,
+    pages           : Vec<u8>,
+    /// the column count of each page, the largest skilldesc column on it
+    columns         : Vec<i32>,
+    rows            : i32,
+}
+
+impl<'a> SkillTree<'a> {
+    /// the skills of `char_class` that are placed on a tree page
+    pub fn build(catalog: &'a SkillCatalog, char_class: i8) -> Self {
+        let skills: Vec<&SkillDef> = catalog.get_by_class(char_class)
+            .into_iter()
+            .filter(|s| s.desc.as_ref().map(|d| d.page > 0).unwrap_or(false))
+            .collect();
+
+        let mut pages: Vec<u8> = skills.iter().map(|s| s.desc.as_ref().unwrap().page).collect();
+        pages.sort();
+        pages.dedup();
+
+        let columns = pages.iter()
+            .map(|page| skills.iter()
+                .map(|s| s.desc.as_ref().unwrap())
+                .filter(|d| d.page == *page)
+                .map(|d| d.tree_column as i32)
+                .max()
+                .unwrap_or(0)
+                .max(1))
+            .collect();
+
+        let rows = skills.iter().map(|s| s.desc.as_ref().unwrap().tree_row as i32).max().unwrap_or(0);
+
+        Self {
+            char_class,
+            catalog,
+            skills,
+            pages,
+            columns,
+            rows,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.skills.is_empty()
+    }
+
+    fn page_width(columns: i32) -> i32 {
+        columns * (CELL_W + GAP_X) + PAGE_GAP
+    }
+
+    fn page_columns(&self, page: u8) -> i32 {
+        self.columns[self.pages.iter().position(|p| *p == page).unwrap()]
+    }
+
+    fn page_x(&self, page: u8) -> i32 {
+        let index = self.pages.iter().position(|p| *p == page).unwrap();
+        PAGE_GAP / 2 + self.columns[..index].iter().map(|c| Self::page_width(*c)).sum::<i32>()
+    }
+
+    /// top left corner of a skill's cell, rows and columns count from 1
+    fn cell(&self, skill: &SkillDef) -> (i32, i32) {
+        let desc = skill.desc.as_ref().unwrap();
+        let x = self.page_x(desc.page) + (desc.tree_column as i32 - 1).max(0) * (CELL_W + GAP_X);
+        let y = TITLE_H + GAP_Y / 2 + (desc.tree_row as i32 - 1).max(0) * (CELL_H + GAP_Y);
+        (x, y)
+    }
+
+    fn name(skill: &SkillDef) -> String {
+        skill.name.as_deref().map(|n| strip_color_codes(n).trim().to_string()).unwrap_or_else(|| format!("skill {}", skill.id))
+    }
+
+    pub fn to_svg(&self) -> String {
+        let width = self.columns.iter().map(|c| Self::page_width(*c)).sum::<i32>();
+        let height = TITLE_H + self.rows * (CELL_H + GAP_Y);
+
+        let mut lines = Vec::<String>::new();
+
+        lines.push(format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="12">"#));
+        lines.push(r##"  <defs><marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" orient="auto"><path d="M0,0 L10,5 L0,10 z" fill="#c8a050"/></marker></defs>"##.into());
+        lines.push(format!(r##"  <rect width="{width}" height="{height}" fill="#1a1410"/>"##));
+
+        for page in self.pages.iter() {
+            let x = self.page_x(*page) + self.page_columns(*page) * (CELL_W + GAP_X) / 2 - GAP_X / 2;
+            lines.push(format!(r##"  <text x="{x}" y="{}" fill="#c8a050" text-anchor="middle" font-size="14">Page {page}</text>"##, TITLE_H - 10));
+        }
+
+        for skill in self.skills.iter() {
+            let (x, y) = self.cell(skill);
+            let page = skill.desc.as_ref().unwrap().page;
+
+            for id in skill.prerequisites.iter() {
+                let req = match self.skills.iter().find(|s| s.id == *id) {
+                    Some(req) if req.desc.as_ref().unwrap().page == page => req,
+                    _ => continue,
+                };
+
+                let (rx, ry) = self.cell(req);
+                lines.push(format!(
+                    r##"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#c8a050" stroke-width="2" marker-end="url(#arrow)"/>"##,
+                    rx + CELL_W / 2, ry + CELL_H, x + CELL_W / 2, y,
+                ));
+            }
+        }
+
+        for skill in self.skills.iter() {
+            let (x, y) = self.cell(skill);
+            let desc = skill.desc.as_ref().unwrap();
+
+            // prerequisites on another page can not get an arrow, name them instead
+            let off_page: Vec<String> = skill.prerequisites.iter()
+                .filter(|id| !self.skills.iter().any(|s| s.id == **id && s.desc.as_ref().unwrap().page == desc.page))
+                .filter_map(|id| self.catalog.get(*id))
+                .map(Self::name)
+                .collect();
+
+            lines.push(format!(r##"  <g><title>{}</title>"##, html_str(&format!("{} (id {}, icon {})", Self::name(skill), skill.id, desc.icon_cel))));
+            lines.push(format!(r##"    <rect x="{x}" y="{y}" width="{CELL_W}" height="{CELL_H}" rx="6" fill="#2b221a" stroke="#c8a050"/>"##));
+            lines.push(format!(r##"    <text x="{}" y="{}" fill="#f0e0c0" text-anchor="middle">{}</text>"##, x + CELL_W / 2, y + 20, html_str(&Self::name(skill))));
+            lines.push(format!(r##"    <text x="{}" y="{}" fill="#a09070" text-anchor="middle">Lv {}</text>"##, x + CELL_W / 2, y + 36, skill.req_level));
+
+            if !off_page.is_empty() {
+                lines.push(format!(r##"    <text x="{}" y="{}" fill="#a09070" text-anchor="middle" font-size="10">{}</text>"##, x + CELL_W / 2, y + 50, html_str(&off_page.join(", "))));
+            }
+
+            lines.push("  </g>".into());
+        }
+
+        lines.push("</svg>".into());
+
+        lines.join("\n")
+    }
+
+    pub fn dump_svg(&self, file_name: &str) -> Result<()> {
+        let mut svg = std::fs::File::create(file_name)?;
+        svg.write_all(self.to_svg().as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// writes one html page with the tree of every class
+pub fn dump_html(catalog: &SkillCatalog, file_name: &str) -> Result<()> {
+    let mut lines = Vec::<String>::new();
+
+    lines.push("<!DOCTYPE html>".into());
+    lines.push(r#"<html><head><meta charset="utf-8"><title>Skill Trees</title></head>"#.into());
+    lines.push(r#"<body style="background:#0d0a08;color:#f0e0c0;font-family:sans-serif">"#.into());
+
+    for char_class in catalog.classes() {
+        let tree = SkillTree::build(catalog, char_class);
+
+        if tree.is_empty() {
+            continue;
+        }
+
+        lines.push(format!("<h2>{}</h2>", html_str(&fields::CHAR_CLASS.format(char_class as i64))));
+        lines.push(tree.to_svg());
+    }
+
+    lines.push("</body></html>".into());
+
+    let mut html = std::fs::File::create(file_name)?;
+    html.write_all(lines.join("\n").as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bin::Value;
+    use crate::bin::tests::zeroed;
+    use crate::datatbls_mgr::DataTblsManager;
+    use crate::stringtbl::tests::manager;
+
+    #[test]
+    fn pages_are_as_wide_as_their_columns() {
+        let mut tbls = DataTblsManager::new();
+        tbls.strtbl = manager(&[("dummy", "")], &[], &[]);
+        tbls.skills = zeroed(&fields::SKILLS, 3);
+        tbls.skill_desc = zeroed(&fields::SKILL_DESC, 3);
+
+        // page 1 has a skill in column 4, page 2 only uses column 2
+        for (row, (page, column)) in [(1, 1), (1, 4), (2, 2)].into_iter().enumerate() {
+            let skill = tbls.skills.get_mut(row).unwrap();
+            skill.set("skill_id", Value::Int16(row as i16));
+            skill.set("skill_desc", Value::UInt16(row as u16));
+
+            let desc = tbls.skill_desc.get_mut(row).unwrap();
+            desc.set("skill_page", Value::UInt8(page));
+            desc.set("skill_row", Value::UInt8(1));
+            desc.set("skill_column", Value::UInt8(column));
+        }
+
+        let catalog = SkillCatalog::build(&tbls);
+        let tree = SkillTree::build(&catalog, 0);
+
+        assert_eq!(tree.columns, [4, 2]);
+        assert_eq!(tree.page_x(2), PAGE_GAP / 2 + 4 * (CELL_W + GAP_X) + PAGE_GAP);
+        assert!(tree.to_svg().contains(&format!(r#"width="{}""#, 6 * (CELL_W + GAP_X) + 2 * PAGE_GAP)));
+    }
+}
@@ -0,0 +1,143 @@
+#![allow(unused)]
+
+//! exports every loaded table and the string tables into one SQLite database,
+//! built with the `sqlite` feature.
+//!
+//! Each bin becomes a table with a `row` primary key and one column per field,
+//! arrays are expanded into `name1..N` like `dump_txt`. `StringId` columns get
+//! a `name_text` column with the resolved string next to them. Column types
+//! follow the field types.
+
+use std::path::Path;
+use anyhow::Result;
+use rusqlite::{Connection, Transaction, params_from_iter, types::Value as SqlValue};
+
+use crate::bin::*;
+use crate::datatbls_mgr::{DataTblsManager, Cell};
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// (column, sql type) of the fields of `schema`, named like `record_cells`
+fn schema_columns(schema: &Schema) -> Vec<(String, &'static str)> {
+    let mut columns = Vec::new();
+
+    for f in schema.fields().iter().filter(|f| !f.name.starts_with("__pad")) {
+        match f.ty {
+            FieldType::Array(_, len) => columns.extend((1..=len).map(|i| (format!("{}{i}", f.name), "INTEGER"))),
+            FieldType::Primitive(Primitive::StringId) => {
+                columns.push((f.name.clone(), "INTEGER"));
+                columns.push((format!("{}_text", f.name), "TEXT"));
+            },
+            FieldType::Primitive(Primitive::ItemCode) | FieldType::String(_) => columns.push((f.name.clone(), "TEXT")),
+            FieldType::Primitive(_) => columns.push((f.name.clone(), "INTEGER")),
+        }
+    }
+
+    columns
+}
+
+fn export_table(tx: &Transaction, tbls: &DataTblsManager, name: &str, records: &BinRecord) -> Result<()> {
+    let columns = schema_columns(records.schema());
+
+    let defs: Vec<String> = columns.iter().map(|(c, t)| format!("{} {t}", quote(c))).collect();
+    tx.execute(&format!("CREATE TABLE {} (\"row\" INTEGER PRIMARY KEY, {})", quote(name), defs.join(", ")), [])?;
+
+    let names: Vec<String> = columns.iter().map(|(c, _)| quote(c)).collect();
+    let params = vec!["?"; columns.len() + 1].join(", ");
+    let mut insert = tx.prepare(&format!("INSERT INTO {} (\"row\", {}) VALUES ({params})", quote(name), names.join(", ")))?;
+
+    for (row, record) in records.iter().enumerate() {
+        let values = std::iter::once(SqlValue::Integer(row as i64))
+            .chain(tbls.record_cells(record, true).into_iter().map(|(_, cell)| match cell {
+                Cell::Int(v) => SqlValue::Integer(v),
+                Cell::Text(s) => SqlValue::Text(s),
+            }));
+
+        insert.execute(params_from_iter(values))?;
+    }
+
+    Ok(())
+}
+
+/// every string table entry. indices of different tables can collide (e.g. a
+/// string.tbl longer than 10000 entries), so `index` is not a key and
+/// `source` tells the entries apart
+fn export_strings(tx: &Transaction, tbls: &DataTblsManager) -> Result<()> {
+    tx.execute("CREATE TABLE \"strings\" (\"index\" INTEGER, \"key\" TEXT, \"value\" TEXT, \"source\" TEXT)", [])?;
+    tx.execute("CREATE INDEX \"strings_index\" ON \"strings\" (\"index\")", [])?;
+
+    let mut insert = tx.prepare("INSERT INTO \"strings\" (\"index\", \"key\", \"value\", \"source\") VALUES (?, ?, ?, ?)")?;
+
+    for (index, source, entry) in tbls.strtbl.entries() {
+        insert.execute(rusqlite::params![index, entry.key, entry.value, source])?;
+    }
+
+    Ok(())
+}
+
+/// writes the tables and strings into `conn`, which should be empty
+pub fn export_to(tbls: &DataTblsManager, conn: &mut Connection) -> Result<()> {
+    let tx = conn.transaction()?;
+
+    for (name, records) in tbls.tables() {
+        export_table(&tx, tbls, name, records)?;
+    }
+    export_strings(&tx, tbls)?;
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// writes the database to `path`, an existing file is replaced
+pub fn export<P: AsRef<Path>>(tbls: &DataTblsManager, path: P) -> Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    export_to(tbls, &mut Connection::open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields;
+    use crate::bin::tests::zeroed;
+    use crate::itemtbl::item_code;
+    use crate::stringtbl::tests::manager;
+
+    #[test]
+    fn exports_typed_tables_and_every_string() {
+        let mut tbls = DataTblsManager::new();
+
+        // string.tbl index 10000 collides with the first patchstring entry
+        let keys: Vec<String> = (0..=10000).map(|i| format!("s{i}")).collect();
+        let string: Vec<(&str, &str)> = keys.iter().map(|k| (k.as_str(), "")).collect();
+        tbls.strtbl = manager(&string, &[("p0", "patch")], &[]);
+
+        tbls.item_types = zeroed(&fields::ITEM_TYPES, 2);
+        tbls.item_types.get_mut(1).unwrap().set("code", Value::ItemCode(item_code("weap")));
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        export_to(&tbls, &mut conn).unwrap();
+
+        let column_type = |name: &str| -> String {
+            conn.query_row("SELECT \"type\" FROM pragma_table_info('itemtypes') WHERE \"name\" = ?", [name], |r| r.get(0)).unwrap()
+        };
+        assert_eq!(column_type("code"), "TEXT");
+        assert_eq!(column_type("equiv2"), "INTEGER");
+        assert_eq!(column_type("repair"), "INTEGER");
+
+        let code: String = conn.query_row("SELECT \"code\" FROM \"itemtypes\" WHERE \"row\" = 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(code, "weap");
+
+        let sources: Vec<String> = conn
+            .prepare("SELECT \"source\" FROM \"strings\" WHERE \"index\" = 10000 ORDER BY \"source\"").unwrap()
+            .query_map([], |r| r.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(sources, ["patchstring", "string"]);
+    }
+}
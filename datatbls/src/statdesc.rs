@@ -0,0 +1,487 @@
+#![allow(unused)]
+
+//! turns an item stat (stat id, param, value) into the line the game shows on
+//! an item, following the desc_func, desc_val and desc_str_pos/neg/2 columns of
+//! itemstatcost.bin. Lines are ordered by desc_priority, highest first.
+
+use crate::bin::*;
+use crate::stringtbl::strip_color_codes;
+use crate::datatbls_mgr::DataTblsManager;
+
+/// stat ids of properties.bin are u16, unused slots hold this
+const NO_STAT: u16 = 0xFFFF;
+
+/// the charstats.txt string keys desc_func 13, 14 and 27 use
+#[derive(Debug)]
+pub struct ClassStrings {
+    /// StrAllSkills, `+%d to Amazon Skill Levels`
+    pub all_skills  : &'static str,
+    /// StrSkillTab1-3, `+%d to Bow and Crossbow Skills`
+    pub skill_tabs  : [&'static str; 3],
+    /// StrClassOnly, `(Amazon Only)`
+    pub only        : &'static str,
+}
+
+/// in char class order
+pub static CLASS_STRINGS: &[ClassStrings] = &[
+    ClassStrings { all_skills: "ModStr3a",  skill_tabs: ["StrSklTabItem3", "StrSklTabItem2", "StrSklTabItem1"],    only: "AmaOnly" },
+    ClassStrings { all_skills: "ModStr3d",  skill_tabs: ["StrSklTabItem15", "StrSklTabItem14", "StrSklTabItem13"], only: "SorOnly" },
+    ClassStrings { all_skills: "ModStr3c",  skill_tabs: ["StrSklTabItem8", "StrSklTabItem9", "StrSklTabItem7"],    only: "NecOnly" },
+    ClassStrings { all_skills: "ModStr3b",  skill_tabs: ["StrSklTabItem6", "StrSklTabItem5", "StrSklTabItem4"],    only: "PalOnly" },
+    ClassStrings { all_skills: "ModStr3e",  skill_tabs: ["StrSklTabItem11", "StrSklTabItem12", "StrSklTabItem10"], only: "BarOnly" },
+    ClassStrings { all_skills: "ModStre8a", skill_tabs: ["StrSklTabItem16", "StrSklTabItem17", "StrSklTabItem18"], only: "DruOnly" },
+    ClassStrings { all_skills: "ModStre8b", skill_tabs: ["StrSklTabItem19", "StrSklTabItem20", "StrSklTabItem21"], only: "AssOnly" },
+];
+
+/// `Repairs %d durability in %d seconds`, for desc_func 11 when desc_str_pos has no placeholders
+const REPAIR_KEY: &str = "ModStre9u";
+/// `Level %d %s %s`, the skill and level before the charges of desc_func 24
+const CHARGES_KEY: &str = "ModStre10b";
+/// `%+d to %s %s`, desc_func 27
+const CLASS_SKILL_KEY: &str = "ItemModifierClassSkill";
+/// `%+d to %s`, desc_func 28
+const NON_CLASS_SKILL_KEY: &str = "ItemModifierNonClassSkill";
+
+/// one of the 7 stat slots of a properties.bin row
+#[derive(Debug, Clone)]
+pub struct PropertyStat {
+    pub set     : u8,
+    pub val     : u16,
+    pub func    : u8,
+    /// `None` for functions that do not write a stat of their own (e.g. min/max damage)
+    pub stat    : Option<u16>,
+}
+
+/// a `prop/par/min/max` column set of uniqueitems, setitems, sets or runes
+#[derive(Debug, Clone)]
+pub struct ItemProp {
+    /// properties.bin row
+    pub prop    : usize,
+    pub par     : i32,
+    pub min     : i32,
+    pub max     : i32,
+}
+
+impl ItemProp {
+    /// reads the columns `{keys[i]}_{suffix}`, `None` for an unused slot
+    pub fn read(record: &Record, keys: [&str; 4], suffix: &str) -> Option<Self> {
+        let get = |i: usize| record.get(&format!("{}_{suffix}", keys[i]));
+
+        Some(Self {
+            prop: get(0).ref_row()?,
+            par: get(1).value.i32(),
+            min: get(2).value.i32(),
+            max: get(3).value.i32(),
+        })
+    }
+}
+
+/// merges the lines of the min and max value into `+(150-200)% Enhanced Damage`
+fn range_line(min: &str, max: &str) -> String {
+    if min == max {
+        return min.to_string();
+    }
+
+    let (a, b): (Vec<char>, Vec<char>) = (min.chars().collect(), max.chars().collect());
+
+    let mut prefix = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+    while prefix > 0 && a[prefix - 1].is_ascii_digit() {
+        prefix -= 1;
+    }
+
+    let mut suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    suffix = suffix.min(a.len() - prefix).min(b.len() - prefix);
+    while suffix > 0 && a[a.len() - suffix].is_ascii_digit() {
+        suffix -= 1;
+    }
+
+    let part = |s: &[char]| s.iter().collect::<String>();
+
+    format!(
+        "{}({}-{}){}",
+        part(&a[..prefix]),
+        part(&a[prefix..a.len() - suffix]),
+        part(&b[prefix..b.len() - suffix]),
+        part(&a[a.len() - suffix..]),
+    )
+}
+
+/// fills the `%d`, `%+d`, `%s` and `%%` placeholders of a game string in order,
+/// missing arguments leave the placeholder empty
+pub fn format_game_string(s: &str, args: &[String]) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut args = args.iter();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        let signed = chars.peek() == Some(&'+');
+        if signed {
+            chars.next();
+        }
+
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('d' | 'i' | 'u' | 's') => {
+                let arg = args.next().map(|a| a.as_str()).unwrap_or("");
+                if signed && !arg.starts_with('-') {
+                    out.push('+');
+                }
+                out.push_str(arg);
+            },
+            Some(c) => {
+                out.push('%');
+                out.push(c);
+            },
+            None => out.push('%'),
+        }
+    }
+
+    out
+}
+
+fn signed(value: i64) -> String {
+    if value >= 0 { format!("+{value}") } else { value.to_string() }
+}
+
+pub struct StatDesc<'a> {
+    tbls            : &'a DataTblsManager,
+    pub strip_colors: bool,
+}
+
+impl<'a> StatDesc<'a> {
+    pub fn new(tbls: &'a DataTblsManager) -> Self {
+        Self {
+            tbls,
+            strip_colors: false,
+        }
+    }
+
+    fn stat(&self, stat: u16) -> Option<&Record> {
+        self.tbls.item_stat_cost.records().get(stat as usize)
+    }
+
+    fn string(&self, index: u16) -> String {
+        self.tbls.get_string_by_index(index).unwrap_or("").trim().to_string()
+    }
+
+    fn string_by_key(&self, key: &str) -> String {
+        self.tbls.strtbl.get_string_by_key(key).unwrap_or("").trim().to_string()
+    }
+
+    fn skill_name(&self, id: i64) -> String {
+        self.tbls.skill_catalog.get(id as i16)
+            .and_then(|s| s.name.as_deref())
+            .map(|n| n.trim().to_string())
+            .unwrap_or_else(|| format!("skill {id}"))
+    }
+
+    fn class_strings(class: i64) -> Option<&'static ClassStrings> {
+        CLASS_STRINGS.get(usize::try_from(class).ok()?)
+    }
+
+    /// the desc_priority of `stat`, 0 for unknown stats
+    pub fn priority(&self, stat: u16) -> u16 {
+        self.stat(stat).map(|r| r.get("desc_priority").value.u16()).unwrap_or(0)
+    }
+
+    /// the line of one stat, `None` for unknown stats and stats without a desc_func
+    pub fn describe(&self, stat: u16, param: i32, value: i32) -> Option<String> {
+        let record = self.stat(stat)?;
+        let func = record.get("desc_func").value.u8();
+        if func == 0 {
+            return None;
+        }
+
+        let (param, value) = (param as i64, value as i64);
+        let desc_val = record.get("desc_val").value.u8();
+        let text = self.string(if value >= 0 {
+            record.get("desc_str_pos").value.str_id()
+        } else {
+            record.get("desc_str_neg").value.str_id()
+        });
+        let text_2 = self.string(record.get("desc_str_2").value.str_id());
+
+        // desc_val 0 hides the value, 1 puts it before the text and 2 after it
+        let place = |v: String, text: &str| match desc_val {
+            0 => text.to_string(),
+            2 => format!("{text} {v}"),
+            _ => format!("{v} {text}"),
+        };
+
+        let line = match func {
+            1 | 12 => place(signed(value), &text),
+            2 => place(format!("{value}%"), &text),
+            3 => place(value.to_string(), &text),
+            4 => place(format!("{}%", signed(value)), &text),
+            5 => place(format!("{}%", value * 100 / 128), &text),
+            6 => format!("{} {text_2}", place(signed(value), &text)),
+            7 => format!("{} {text_2}", place(format!("{value}%"), &text)),
+            8 => format!("{} {text_2}", place(format!("{}%", signed(value)), &text)),
+            9 => format!("{} {text_2}", place(value.to_string(), &text)),
+            10 => format!("{} {text_2}", place(format!("{}%", value * 100 / 128), &text)),
+            11 if value != 0 => {
+                let text = if text.contains('%') { text.clone() } else { self.string_by_key(REPAIR_KEY) };
+                format_game_string(&text, &["1".into(), (100 / value).to_string()])
+            },
+            13 => match Self::class_strings(param) {
+                Some(class) => format_game_string(&self.string_by_key(class.all_skills), &[value.to_string()]),
+                None => place(signed(value), &text),
+            },
+            // param is class * 8 + tab
+            14 => match Self::class_strings(param / 8).and_then(|c| Some((c.skill_tabs.get((param % 8) as usize)?, c.only))) {
+                Some((tab, only)) => format!(
+                    "{} {}",
+                    format_game_string(&self.string_by_key(tab), &[value.to_string()]),
+                    self.string_by_key(only),
+                ),
+                None => place(signed(value), &text),
+            },
+            // param is skill << 6 | level, value the chance
+            15 => format_game_string(&text, &[value.to_string(), (param & 0x3F).to_string(), self.skill_name(param >> 6)]),
+            16 => format_game_string(&text, &[value.to_string(), self.skill_name(param)]),
+            // time based stats scale with the day cycle, only the base value is shown
+            17 => place(value.to_string(), &text),
+            18 => place(format!("{value}%"), &text),
+            19 => format_game_string(&text, &[value.to_string()]),
+            20 => place(format!("{}%", -value), &text),
+            21 => place((-value).to_string(), &text),
+            22 | 23 => place(format!("{value}%"), &text),
+            // param is skill << 6 | level, value is max charges << 8 | charges
+            24 => format_game_string(&self.string_by_key(CHARGES_KEY), &[
+                (param & 0x3F).to_string(),
+                self.skill_name(param >> 6),
+                format_game_string(&text, &[(value & 0xFF).to_string(), (value >> 8).to_string()]),
+            ]),
+            27 => {
+                let class = self.tbls.skill_catalog.get(param as i16).map(|s| s.char_class as i64).unwrap_or(-1);
+                let only = Self::class_strings(class).map(|c| self.string_by_key(c.only)).unwrap_or_default();
+                format_game_string(&self.string_by_key(CLASS_SKILL_KEY), &[value.to_string(), self.skill_name(param), only])
+            },
+            28 => format_game_string(&self.string_by_key(NON_CLASS_SKILL_KEY), &[value.to_string(), self.skill_name(param)]),
+            _ => place(value.to_string(), &text),
+        };
+
+        let line = line.trim().to_string();
+
+        Some(if self.strip_colors { strip_color_codes(&line) } else { line })
+    }
+
+    /// the lines of a stat list as (stat id, param, value), highest desc_priority first
+    pub fn describe_all(&self, stats: &[(u16, i32, i32)]) -> Vec<String> {
+        let mut stats = stats.to_vec();
+        stats.sort_by_key(|(stat, _, _)| std::cmp::Reverse(self.priority(*stat)));
+
+        stats.into_iter().filter_map(|(stat, param, value)| self.describe(stat, param, value)).collect()
+    }
+
+    /// like `describe` but for a `min..=max` roll, e.g. `+(150-200)% Enhanced Damage`
+    pub fn describe_range(&self, stat: u16, param: i32, min: i32, max: i32) -> Option<String> {
+        if min >= max {
+            return self.describe(stat, param, min);
+        }
+
+        Some(range_line(&self.describe(stat, param, min)?, &self.describe(stat, param, max)?))
+    }
+
+    /// the lines of an item property, stats of the property that render the same line are shown once
+    pub fn describe_prop(&self, prop: &ItemProp) -> Vec<String> {
+        let mut lines = Vec::<String>::new();
+
+        for ps in self.property_stats(prop.prop) {
+            let stat = match ps.stat {
+                Some(stat) => stat,
+                None => continue,
+            };
+
+            // the property function decides how par/min/max map to the stat's param and value
+            let line = match ps.func {
+                // skill on event: par is the skill, min the chance and max the level
+                11 => self.describe(stat, (prop.par << 6) | (prop.max & 0x3F), prop.min),
+                // per level: par is the value
+                17 => self.describe(stat, 0, prop.par),
+                // charges: par is the skill, min the charges and max the level. the
+                // value is max charges << 8 | current charges, items drop fully charged
+                19 => {
+                    let (max_charges, charges) = (prop.min & 0xFF, prop.min & 0xFF);
+                    self.describe(stat, (prop.par << 6) | (prop.max & 0x3F), (max_charges << 8) | charges)
+                },
+                // class skills: the class is the val of the property
+                21 => self.describe_range(stat, ps.val as i32, prop.min, prop.max),
+                _ => self.describe_range(stat, prop.par, prop.min, prop.max),
+            };
+
+            if let Some(line) = line {
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+        }
+
+        lines
+    }
+
+    /// the lines of several properties, highest desc_priority of their first stat first
+    pub fn describe_props(&self, props: &[ItemProp]) -> Vec<String> {
+        let mut props = props.to_vec();
+        props.sort_by_key(|p| {
+            let stat = self.property_stats(p.prop).into_iter().find_map(|s| s.stat);
+            std::cmp::Reverse(stat.map(|s| self.priority(s)).unwrap_or(0))
+        });
+
+        props.iter().flat_map(|p| self.describe_prop(p)).collect()
+    }
+
+    /// the used stat slots of properties.bin row `prop`
+    pub fn property_stats(&self, prop: usize) -> Vec<PropertyStat> {
+        let record = match self.tbls.properties.records().get(prop) {
+            Some(record) => record,
+            None => return Vec::new(),
+        };
+
+        let (set, val, func, stat) = match (
+            record.get("set").value.clone(),
+            record.get("val").value.clone(),
+            record.get("func").value.clone(),
+            record.get("stat").value.clone(),
+        ) {
+            (Value::U8Array(set), Value::U16Array(val), Value::U8Array(func), Value::U16Array(stat)) => (set, val, func, stat),
+            _ => panic!("unexpected properties layout"),
+        };
+
+        (0..func.len())
+            .filter(|i| func[*i] != 0)
+            .map(|i| PropertyStat {
+                set: set[i],
+                val: val[i],
+                func: func[i],
+                stat: if stat[i] == NO_STAT { None } else { Some(stat[i]) },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields;
+    use crate::bin::tests::zeroed;
+    use crate::skills::SkillCatalog;
+    use crate::stringtbl::tests::manager;
+
+    const ENCHANT: i16 = 52;
+    const ITEM_CHARGED_SKILL: u16 = 204;
+
+    /// the `charged` property and the item_charged_skill stat
+    fn tbls() -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+        tbls.strtbl = manager(
+            &[
+                ("dummy", ""), ("ModStre10d", "(%d/%d Charges)"), ("skillname52", "Enchant"), ("ModStre10b", "Level %d %s %s"),
+                ("ModStre9u", "Repairs %d durability in %d seconds"), ("ModStr3d", "+%d to Sorceress Skill Levels"),
+                ("StrSklTabItem14", "+%d to Lightning Skills"), ("SorOnly", "(Sorceress Only)"),
+                ("ItemModifierClassSkill", "%+d to %s %s"), ("ItemModifierNonClassSkill", "%+d to %s"), ("ModStr2u", "Repairs Durability"),
+            ],
+            &[],
+            &[],
+        );
+
+        tbls.item_stat_cost = zeroed(&fields::ITEM_STAT_COST, ITEM_CHARGED_SKILL as usize + 1);
+        let stat = tbls.item_stat_cost.get_mut(ITEM_CHARGED_SKILL as usize).unwrap();
+        stat.set("stat_id", Value::UInt32(ITEM_CHARGED_SKILL as u32));
+        stat.set("desc_func", Value::UInt8(24));
+        stat.set("desc_str_pos", Value::StringId(1));
+
+        tbls.properties = zeroed(&fields::PROPERTIES, 1);
+        let mut funcs = vec![0u8; 7];
+        let mut stats = vec![NO_STAT; 7];
+        (funcs[0], stats[0]) = (19, ITEM_CHARGED_SKILL);
+        let prop = tbls.properties.get_mut(0).unwrap();
+        prop.set("func", Value::U8Array(funcs));
+        prop.set("stat", Value::U16Array(stats));
+
+        tbls.skills = zeroed(&fields::SKILLS, 1);
+        tbls.skill_desc = zeroed(&fields::SKILL_DESC, 1);
+        tbls.skills.get_mut(0).unwrap().set("skill_id", Value::Int16(ENCHANT));
+        tbls.skills.get_mut(0).unwrap().set("char_class", Value::Int8(1));
+        tbls.skill_desc.get_mut(0).unwrap().set("str_name", Value::StringId(2));
+        tbls.skill_catalog = SkillCatalog::build(&tbls);
+
+        tbls
+    }
+
+    #[test]
+    fn charged_prop_fills_both_charge_bytes() {
+        let tbls = tbls();
+        let desc = StatDesc::new(&tbls);
+
+        // Demon Limb: charged Enchant, 20 charges at level 23
+        let prop = ItemProp { prop: 0, par: ENCHANT as i32, min: 20, max: 23 };
+        assert_eq!(desc.describe_prop(&prop), ["Level 23 Enchant (20/20 Charges)"]);
+
+        // a used up item keeps its max charges in the high byte
+        let param = ((ENCHANT as i32) << 6) | 23;
+        assert_eq!(desc.describe(ITEM_CHARGED_SKILL, param, (20 << 8) | 5).unwrap(), "Level 23 Enchant (5/20 Charges)");
+    }
+
+    /// sets stat row `id` to `func`/`val` with string ids for the positive, negative and second text
+    fn set_stat(tbls: &mut DataTblsManager, id: u16, func: u8, val: u8, strs: [u16; 3]) {
+        let stat = tbls.item_stat_cost.get_mut(id as usize).unwrap();
+        stat.set("desc_func", Value::UInt8(func));
+        stat.set("desc_val", Value::UInt8(val));
+        stat.set("desc_str_pos", Value::StringId(strs[0]));
+        stat.set("desc_str_neg", Value::StringId(strs[1]));
+        stat.set("desc_str_2", Value::StringId(strs[2]));
+    }
+
+    #[test]
+    fn desc_val_places_the_value_and_its_sign_picks_the_text() {
+        let mut tbls = tbls();
+        set_stat(&mut tbls, 0, 1, 1, [4, 5, 0]);
+        set_stat(&mut tbls, 1, 4, 2, [6, 7, 0]);
+        set_stat(&mut tbls, 2, 3, 0, [6, 7, 0]);
+        set_stat(&mut tbls, 3, 6, 1, [4, 5, 99]);
+        tbls.strtbl = manager(
+            &[("dummy", ""), ("a", ""), ("b", ""), ("c", ""), ("pos", "to Strength"), ("neg", "from Strength"), ("cold", "Cold Resist"), ("cold neg", "Cold Vulnerable")],
+            &[],
+            &[],
+        );
+        let desc = StatDesc::new(&tbls);
+
+        assert_eq!(desc.describe(0, 0, 10).unwrap(), "+10 to Strength");
+        assert_eq!(desc.describe(0, 0, -5).unwrap(), "-5 from Strength");
+        assert_eq!(desc.describe(1, 0, 30).unwrap(), "Cold Resist +30%");
+        assert_eq!(desc.describe(1, 0, -30).unwrap(), "Cold Vulnerable -30%");
+        assert_eq!(desc.describe(2, 0, 7).unwrap(), "Cold Resist");
+        // desc_func 6 adds desc_str_2, a missing string is left out
+        assert_eq!(desc.describe(3, 0, 2).unwrap(), "+2 to Strength");
+        // stats without a desc_func have no line
+        assert_eq!(desc.describe(4, 0, 1), None);
+        assert_eq!(desc.describe(999, 0, 1), None);
+    }
+
+    #[test]
+    fn class_and_skill_funcs_read_their_text_by_key() {
+        let mut tbls = tbls();
+        set_stat(&mut tbls, 0, 13, 1, [0, 0, 0]);
+        set_stat(&mut tbls, 1, 14, 1, [0, 0, 0]);
+        set_stat(&mut tbls, 2, 27, 1, [0, 0, 0]);
+        set_stat(&mut tbls, 3, 28, 1, [0, 0, 0]);
+        set_stat(&mut tbls, 4, 11, 1, [10, 0, 0]);
+        let desc = StatDesc::new(&tbls);
+
+        assert_eq!(desc.describe(0, 1, 2).unwrap(), "+2 to Sorceress Skill Levels");
+        assert_eq!(desc.describe(1, 8 + 1, 3).unwrap(), "+3 to Lightning Skills (Sorceress Only)");
+        assert_eq!(desc.describe(2, ENCHANT as i32, 1).unwrap(), "+1 to Enchant (Sorceress Only)");
+        assert_eq!(desc.describe(3, ENCHANT as i32, -1).unwrap(), "-1 to Enchant");
+        // desc_str_pos without placeholders falls back to the repair string
+        assert_eq!(desc.describe(4, 0, 5).unwrap(), "Repairs 1 durability in 20 seconds");
+
+        // classes past the char classes show the value with desc_str_pos
+        assert_eq!(desc.describe(0, 9, 2).unwrap(), "+2");
+    }
+}
@@ -1,10 +1,12 @@
 #![allow(unused)]
 
 use std::path::Path;
-use std::io::{Seek, SeekFrom, BufReader, BufRead};
+use std::io::{Seek, SeekFrom, BufReader, BufRead, Cursor};
 use ml::io::{File, ReadExt, LittleEndian};
 use anyhow::Result;
 
+use crate::vfs::Vfs;
+
 type LE = LittleEndian;
 
 struct StringTableHeader {
@@ -38,8 +40,12 @@ impl std::fmt::Debug for StringTableHeader {
     }
 }
 
-impl From<&mut File> for StringTableHeader {
-    fn from(fs: &mut File) -> Self {
+impl StringTableHeader {
+    const SIZE: usize = 21;
+}
+
+impl From<&mut Cursor<&[u8]>> for StringTableHeader {
+    fn from(fs: &mut Cursor<&[u8]>) -> Self {
         Self {
             crc                 : fs.u16::<LE>(),
             count               : fs.u16::<LE>(),
@@ -52,77 +58,140 @@ impl From<&mut File> for StringTableHeader {
     }
 }
 
+/// removes the `ÿcX` color codes of a game string
+pub fn strip_color_codes(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == 'ÿ' && chars.peek() == Some(&'c') {
+            chars.next();
+            chars.next();
+            continue;
+        }
+        out.push(c);
+    }
+
+    out
+}
+
 pub struct StringTableEntry {
     pub key     : String,
     pub value   : String,
 }
 
-pub struct StringTable {
-    file: File,
+/// an entry of a `StringTableView`, borrowed from the table's bytes. the
+/// value keeps its newlines
+#[derive(Debug, Clone, Copy)]
+pub struct StringEntryRef<'a> {
+    pub key     : &'a str,
+    pub value   : &'a str,
 }
 
-impl StringTable {
-    pub fn open<T: AsRef<Path>>(path: T) -> Result<StringTable> {
-        Ok(Self{
-            file: File::open(path)?,
-        })
-    }
+/// a string table parsed in place from its bytes, e.g. an mmap or an
+/// in-archive buffer
+pub struct StringTableView<'a> {
+    data        : &'a [u8],
+    header      : StringTableHeader,
+    /// the hash table slot of each entry in index order
+    slots       : Vec<u16>,
+    node_start  : usize,
+}
 
-    pub fn read(&mut self) -> Result<Vec<StringTableEntry>> {
-        let mut header = StringTableHeader::from(&mut self.file);
-        // println!("{:#?}", header);
+impl<'a> StringTableView<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<StringTableView<'a>> {
+        if data.len() < StringTableHeader::SIZE {
+            anyhow::bail!("string table is {} bytes, too short for a header", data.len());
+        }
 
-        let mut fs = &mut self.file;
-        let mut offset_into_hash_array = Vec::with_capacity(header.count as usize);
+        let mut fs = Cursor::new(data);
+        let header = StringTableHeader::from(&mut fs);
+        let node_start = StringTableHeader::SIZE + header.count as usize * 2;
 
-        for i in 0..header.count {
-            offset_into_hash_array.push(fs.u16::<LE>());
+        if data.len() < node_start {
+            anyhow::bail!("string table is {} bytes, too short for {} indices", data.len(), header.count);
         }
 
-        let node_start_offset = fs.pos()?;
+        let slots = data[StringTableHeader::SIZE..node_start]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
 
-        // println!("node_start_offset = 0x{node_start_offset:08X}");
+        Ok(Self{
+            data,
+            header,
+            slots,
+            node_start,
+        })
+    }
 
-        let mut entries = Vec::new();
+    pub fn len(&self) -> usize {
+        self.header.count as usize
+    }
 
-        for i in 0..header.count {
-            let entry_offset = node_start_offset + u64::from(offset_into_hash_array[i as usize]) * 17;
+    pub fn is_empty(&self) -> bool {
+        self.header.count == 0
+    }
 
-            fs.seek(SeekFrom::Start(entry_offset))?;
+    pub fn entry(&self, index: usize) -> Result<StringEntryRef<'a>> {
+        let slot = *self.slots.get(index).ok_or_else(|| anyhow::anyhow!("string {index} not exists"))?;
+        let node = self.node_start + slot as usize * 17;
 
-            let used = fs.u8();
-            let index = fs.u16::<LE>();
-            let hash_value = fs.u32::<LE>();
-            let key_offset = fs.u32::<LE>();
-            let val_offset = fs.u32::<LE>();
-            let val_length = fs.u16::<LE>();
+        // used u8, index u16, hash u32, key offset u32, value offset u32, value length u16
+        let node = self.data.get(node..node + 17).ok_or_else(|| anyhow::anyhow!("hash node of string {index} out of bounds"))?;
+        let offset = |at: usize| u32::from_le_bytes(node[at..at + 4].try_into().unwrap()) as usize;
 
-            fs.seek(SeekFrom::Start(u64::from(key_offset)))?;
+        Ok(StringEntryRef{
+            key     : self.c_str(offset(7))?,
+            value   : self.c_str(offset(11))?,
+        })
+    }
 
-            let key = {
-                let mut reader = BufReader::new(&mut fs);
-                let mut buf = Vec::new();
-                reader.read_until(0, &mut buf)?;
-                buf.pop();
+    /// every entry in index order
+    pub fn entries(&self) -> Result<Vec<StringEntryRef<'a>>> {
+        (0..self.len()).map(|i| self.entry(i)).collect()
+    }
 
-                String::from_utf8(buf)?
-            };
+    fn c_str(&self, offset: usize) -> Result<&'a str> {
+        let b = self.data.get(offset..).ok_or_else(|| anyhow::anyhow!("string offset 0x{offset:X} out of bounds"))?;
+        let b = &b[..b.iter().position(|c| *c == 0).unwrap_or(b.len())];
 
-            fs.seek(SeekFrom::Start(u64::from(val_offset)))?;
+        Ok(std::str::from_utf8(b)?)
+    }
+}
 
-            let value = {
-                let mut reader = BufReader::new(&mut fs);
-                let mut buf = Vec::new();
-                reader.read_until(0, &mut buf)?;
-                buf.pop();
+pub struct StringTable {
+    data: Vec<u8>,
+}
 
-                String::from_utf8(buf)?.replace("\n", "\\n")
-            };
+impl StringTable {
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<StringTable> {
+        let mut file = File::open(path)?;
+        let size = file.size()? as usize;
 
-            entries.push(StringTableEntry{key, value});
+        Ok(Self::from_bytes(file.read_bytes(size)?))
+    }
+
+    /// a table already in memory, e.g. read out of an mpq
+    pub fn from_bytes(data: Vec<u8>) -> StringTable {
+        Self{
+            data,
         }
+    }
 
-        Ok(entries)
+    /// the entries borrowed from the table's bytes, see `StringTableView`
+    pub fn view(&self) -> Result<StringTableView<'_>> {
+        StringTableView::parse(&self.data)
+    }
+
+    /// the entries with newlines escaped as `\n`
+    pub fn read(&mut self) -> Result<Vec<StringTableEntry>> {
+        let entries = self.view()?.entries()?.into_iter().map(|e| StringTableEntry{
+            key     : e.key.to_string(),
+            value   : e.value.replace("\n", "\\n"),
+        });
+
+        Ok(entries.collect())
     }
 }
 
@@ -134,6 +203,12 @@ pub struct StringTableManager {
     duckpermstring  : Vec<StringTableEntry>,
 }
 
+impl Default for StringTableManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StringTableManager {
     pub fn new() -> Self {
         Self {
@@ -150,52 +225,244 @@ impl StringTableManager {
         self.patchstring = StringTable::open(patchstring)?.read()?;
         self.expansionstring = StringTable::open(expansionstring)?.read()?;
 
-        match duckmodstring {
-            Some(ref x) => self.duckmodstring = StringTable::open(x)?.read()?,
-            None => (),
-        };
+        if let Some(path) = duckmodstring {
+            self.duckmodstring = StringTable::open(path)?.read()?;
+        }
+
+        if let Some(path) = duckpermstring {
+            self.duckpermstring = StringTable::open(path)?.read()?;
+        }
+
+        Ok(())
+    }
+
+    /// like `load` with the tables read through `vfs`
+    pub fn load_vfs(&mut self, vfs: &Vfs, string: &str, patchstring: &str, expansionstring: &str, duckmodstring: Option<&str>, duckpermstring: Option<&str>) -> Result<()> {
+        let read = |name: &str| StringTable::from_bytes(vfs.read(name)?).read();
+
+        self.string = read(string)?;
+        self.patchstring = read(patchstring)?;
+        self.expansionstring = read(expansionstring)?;
+
+        if let Some(name) = duckmodstring {
+            self.duckmodstring = read(name)?;
+        }
+
+        if let Some(name) = duckpermstring {
+            self.duckpermstring = read(name)?;
+        }
 
-        match duckmodstring {
-            Some(ref x) => self.duckpermstring = StringTable::open(x)?.read()?,
-            None => (),
-        };
         Ok(())
     }
 
     pub fn get_string_by_index(&self, index: u16) -> Option<&str> {
+        self.get_entry_by_index(index).map(|e| e.value.as_str())
+    }
+
+    pub fn get_key_by_index(&self, index: u16) -> Option<&str> {
+        self.get_entry_by_index(index).map(|e| e.key.as_str())
+    }
+
+    /// the value of `key` the way the game looks it up: the mod tables first,
+    /// then patchstring, expansionstring and string
+    pub fn get_string_by_key(&self, key: &str) -> Option<&str> {
+        [&self.duckpermstring, &self.duckmodstring, &self.patchstring, &self.expansionstring, &self.string]
+            .into_iter()
+            .find_map(|tbl| tbl.iter().find(|e| e.key == key))
+            .map(|e| e.value.as_str())
+    }
+
+    /// every entry with the index the bins refer to it by and the table it comes from
+    pub fn entries(&self) -> Vec<(u16, &'static str, &StringTableEntry)> {
+        let mut entries = Vec::new();
+
+        entries.extend(self.string.iter().enumerate().map(|(i, e)| (i as u16, "string", e)));
+        entries.extend(self.patchstring.iter().enumerate().map(|(i, e)| ((10000 + i) as u16, "patchstring", e)));
+        entries.extend(self.expansionstring.iter().enumerate().map(|(i, e)| ((20000 + i) as u16, "expansionstring", e)));
+        entries.extend(self.duckmodstring.iter().enumerate().map(|(i, e)| (-(i as i32 + 1000) as u16, "duckmodstring", e)));
+        entries.extend(self.duckpermstring.iter().enumerate().map(|(i, e)| (-(i as i32 + 2) as u16, "duckpermstring", e)));
+
+        entries
+    }
+
+    /// the entry `index` refers to, ids past the end of their table are None
+    fn get_entry_by_index(&self, index: u16) -> Option<&StringTableEntry> {
         let index = index as usize;
         match index {
-            0..=9999 => {
-                Some(self.string[index].value.as_str())
-            },
-            10000..=19999 => {
-                self.get_string_from_tbl(&self.patchstring, index - 10000)
-                // Some(self.patchstring[index - 10000].value.as_str())
-            },
-            20000..=29999 => {
-                Some(self.expansionstring[index - 20000].value.as_str())
-            },
-            0x86E8..=0xFC18 => {
-                Some(self.duckmodstring[-(index as i16 + 1000) as usize].value.as_str())
-            },
-            0xFC19..=0xFFFE => {
-                Some(self.duckpermstring[-(index as i16 + 2) as usize].value.as_str())
-            },
-            _ => {
-                None
-                // todo!("unsupported index: {index}");
-            },
+            0..=9999 => self.string.get(index),
+            10000..=19999 => self.patchstring.get(index - 10000),
+            20000..=29999 => self.expansionstring.get(index - 20000),
+            0x86E8..=0xFC18 => self.duckmodstring.get(-(index as i16 + 1000) as usize),
+            0xFC19..=0xFFFE => self.duckpermstring.get(-(index as i16 + 2) as usize),
+            _ => None,
         }
     }
+}
 
-    fn get_string_from_tbl<'a>(&self, str_tbl: &'a Vec<StringTableEntry>, index: usize) -> Option<&'a str> {
-        let mut index = index;
-
-        if index >= str_tbl.len() {
-            index = 500;
-            // return None;
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// a .tbl holding `entries` in order, one hash slot per entry
+    pub(crate) fn tbl_bytes(entries: &[(&str, &str)]) -> Vec<u8> {
+        let count = entries.len();
+        let start = StringTableHeader::SIZE + count * 2 + count * 17;
+
+        let mut nodes = Vec::new();
+        let mut strings = Vec::new();
+
+        for (i, (key, value)) in entries.iter().enumerate() {
+            let key_at = start + strings.len();
+            strings.extend(key.as_bytes());
+            strings.push(0);
+
+            let value_at = start + strings.len();
+            strings.extend(value.as_bytes());
+            strings.push(0);
+
+            nodes.push(1);
+            nodes.extend((i as u16).to_le_bytes());
+            nodes.extend(0u32.to_le_bytes());
+            nodes.extend((key_at as u32).to_le_bytes());
+            nodes.extend((value_at as u32).to_le_bytes());
+            nodes.extend((value.len() as u16 + 1).to_le_bytes());
         }
 
-        Some(str_tbl[index].value.as_str())
+        let mut b = Vec::new();
+        b.extend(0u16.to_le_bytes());
+        b.extend((count as u16).to_le_bytes());
+        b.extend((count as u32).to_le_bytes());
+        b.push(0);
+        b.extend((start as u32).to_le_bytes());
+        b.extend(0u32.to_le_bytes());
+        b.extend(((start + strings.len()) as u32).to_le_bytes());
+        b.extend((0..count as u16).flat_map(|i| i.to_le_bytes()));
+        b.extend(nodes);
+        b.extend(strings);
+
+        b
+    }
+
+    fn read(entries: &[(&str, &str)]) -> Vec<StringTableEntry> {
+        StringTable::from_bytes(tbl_bytes(entries)).read().unwrap()
+    }
+
+    /// a manager with string, patchstring and expansionstring loaded
+    pub(crate) fn manager(string: &[(&str, &str)], patch: &[(&str, &str)], expansion: &[(&str, &str)]) -> StringTableManager {
+        let mut strtbl = StringTableManager::new();
+        strtbl.string = read(string);
+        strtbl.patchstring = read(patch);
+        strtbl.expansionstring = read(expansion);
+
+        strtbl
+    }
+
+    /// whether `s` points into `data` rather than a copy
+    fn borrows(data: &[u8], s: &str) -> bool {
+        data.as_ptr_range().contains(&s.as_ptr())
+    }
+
+    #[test]
+    fn view_borrows_entries_from_the_slice() {
+        let data = tbl_bytes(&[("key1", "first\nline"), ("key2", "second")]);
+        let view = StringTableView::parse(&data).unwrap();
+
+        assert_eq!(view.len(), 2);
+        assert!(!view.is_empty());
+
+        let e = view.entry(0).unwrap();
+        assert_eq!((e.key, e.value), ("key1", "first\nline"));
+        assert!(borrows(&data, e.key) && borrows(&data, e.value));
+
+        let keys: Vec<&str> = view.entries().unwrap().iter().map(|e| e.key).collect();
+        assert_eq!(keys, ["key1", "key2"]);
+
+        // the owned entries escape newlines
+        assert_eq!(read(&[("k", "a\nb")])[0].value, "a\\nb");
+        assert!(StringTableView::parse(&tbl_bytes(&[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn view_reports_out_of_bounds_tables() {
+        let err = |data: &[u8]| StringTableView::parse(data).err().unwrap().to_string();
+        let data = tbl_bytes(&[("key", "value")]);
+
+        assert_eq!(err(&data[..10]), "string table is 10 bytes, too short for a header");
+        assert_eq!(err(&data[..22]), "string table is 22 bytes, too short for 1 indices");
+
+        let view = StringTableView::parse(&data).unwrap();
+        assert_eq!(view.entry(1).unwrap_err().to_string(), "string 1 not exists");
+
+        let cut = &data[..StringTableHeader::SIZE + 2 + 10];
+        assert_eq!(StringTableView::parse(cut).unwrap().entry(0).unwrap_err().to_string(), "hash node of string 0 out of bounds");
+
+        // the key offset of the only node points past the end
+        let mut bad = data.clone();
+        let at = StringTableHeader::SIZE + 2 + 7;
+        bad[at..at + 4].copy_from_slice(&0x1000u32.to_le_bytes());
+        assert_eq!(StringTableView::parse(&bad).unwrap().entry(0).unwrap_err().to_string(), "string offset 0x1000 out of bounds");
+
+        let mut bad = data.clone();
+        let len = bad.len();
+        bad[len - 2] = 0xFF;
+        assert!(StringTableView::parse(&bad).unwrap().entry(0).is_err());
+    }
+
+    #[test]
+    fn load_reads_each_mod_table_from_its_own_path() {
+        let dir = std::env::temp_dir().join(format!("datatbls-stringtbl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let write = |name: &str, entries: &[(&str, &str)]| {
+            let path = dir.join(name);
+            std::fs::write(&path, tbl_bytes(entries)).unwrap();
+            path
+        };
+
+        let string = write("string.tbl", &[("a", "string")]);
+        let patch = write("patchstring.tbl", &[("a", "patch")]);
+        let expansion = write("expansionstring.tbl", &[("a", "expansion")]);
+        let duckmod = write("DuckModString.tbl", &[("m", "mod")]);
+        let duckperm = write("DuckPermString.tbl", &[("p", "perm")]);
+
+        let mut strtbl = StringTableManager::new();
+        let loaded = strtbl.load(string, patch, expansion, Some(duckmod), Some(duckperm));
+        std::fs::remove_dir_all(&dir).unwrap();
+        loaded.unwrap();
+
+        assert_eq!(strtbl.get_string_by_index(-1000i16 as u16), Some("mod"));
+        assert_eq!(strtbl.get_string_by_index(-2i16 as u16), Some("perm"));
+        assert_eq!(strtbl.get_string_by_key("p"), Some("perm"));
+    }
+
+    #[test]
+    fn key_lookup_prefers_patch_then_expansion() {
+        let strtbl = manager(
+            &[("a", "string a"), ("b", "string b"), ("c", "string c")],
+            &[("a", "patch a")],
+            &[("a", "expansion a"), ("b", "expansion b")],
+        );
+
+        assert_eq!(strtbl.get_string_by_key("a"), Some("patch a"));
+        assert_eq!(strtbl.get_string_by_key("b"), Some("expansion b"));
+        assert_eq!(strtbl.get_string_by_key("c"), Some("string c"));
+        assert_eq!(strtbl.get_string_by_key("d"), None);
+        assert_eq!(strtbl.get_string_by_index(10000), Some("patch a"));
+    }
+
+    #[test]
+    fn ids_past_the_end_of_a_table_are_none() {
+        let strtbl = manager(&[("a", "string a")], &[], &[("x", "expansion x")]);
+
+        assert_eq!(strtbl.get_string_by_index(0), Some("string a"));
+        assert_eq!(strtbl.get_string_by_index(1), None);
+        assert_eq!(strtbl.get_string_by_index(9999), None);
+        assert_eq!(strtbl.get_string_by_index(10000), None);
+        assert_eq!(strtbl.get_string_by_index(10500), None);
+        assert_eq!(strtbl.get_string_by_index(20000), Some("expansion x"));
+        assert_eq!(strtbl.get_key_by_index(20001), None);
+        assert_eq!(strtbl.get_string_by_index(-1000i16 as u16), None);
+        assert_eq!(strtbl.get_string_by_index(-2i16 as u16), None);
+        assert_eq!(strtbl.get_string_by_index(0xFFFF), None);
     }
 }
@@ -0,0 +1,593 @@
+#![allow(unused)]
+
+//! unique and set items joined with their base item, names and the text of
+//! their properties.
+
+use std::io::Write;
+use anyhow::Result;
+
+use crate::bin::*;
+use crate::itemtbl::{ItemClass, item_code_str};
+use crate::stringtbl::strip_color_codes;
+use crate::statdesc::{ItemProp, StatDesc};
+use crate::datatbls_mgr::{DataTblsManager, json_str, html_str};
+
+const PROP_KEYS         : [&str; 4] = ["prop", "par", "min", "max"];
+const SET_ITEM_BONUS_KEYS: [&str; 4] = ["aprop", "apar", "amin", "amax"];
+const SET_BONUS_KEYS    : [&str; 4] = ["p_code", "p_param", "p_min", "p_max"];
+const SET_FULL_KEYS     : [&str; 4] = ["f_code", "f_param", "f_min", "f_max"];
+
+/// the base item of a unique or set item
+#[derive(Debug, Clone)]
+pub struct BaseItem {
+    pub code    : u32,
+    /// `None` when no weapons/armor/misc row has the code
+    pub class   : Option<ItemClass>,
+    pub name    : Option<String>,
+}
+
+impl BaseItem {
+    fn new(tbls: &DataTblsManager, code: u32) -> Self {
+        let class = tbls.items.get_by_code(code).copied();
+        let name = class.and_then(|c| tbls.row_name(c.kind.table_name(), tbls.item_record(&c)));
+
+        Self {
+            code,
+            class,
+            name,
+        }
+    }
+
+    pub fn code_str(&self) -> String {
+        item_code_str(self.code)
+    }
+}
+
+/// bonuses that apply once `items` pieces of a set are worn
+#[derive(Debug, Clone)]
+pub struct SetBonus {
+    pub items   : u8,
+    pub lines   : Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UniqueItem {
+    pub id      : u16,
+    pub row     : usize,
+    pub index   : String,
+    pub name    : Option<String>,
+    pub base    : BaseItem,
+    pub enabled : bool,
+    pub lvl     : u16,
+    pub lvl_req : u16,
+    pub props   : Vec<ItemProp>,
+    pub lines   : Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SetItem {
+    pub id      : u16,
+    pub row     : usize,
+    pub index   : String,
+    pub name    : Option<String>,
+    /// sets.bin row
+    pub set     : Option<usize>,
+    pub base    : BaseItem,
+    pub lvl     : u16,
+    pub lvl_req : u16,
+    pub props   : Vec<ItemProp>,
+    pub lines   : Vec<String>,
+    /// bonuses of this item for the number of worn set pieces
+    pub bonuses : Vec<SetBonus>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemSet {
+    pub id      : u16,
+    pub row     : usize,
+    pub name    : Option<String>,
+    /// setitems.bin rows
+    pub items   : Vec<usize>,
+    pub partial : Vec<SetBonus>,
+    pub full    : Vec<String>,
+}
+
+fn string(tbls: &DataTblsManager, record: &Record, key: &str) -> Option<String> {
+    tbls.get_string_by_index(record.get(key).value.str_id()).map(|s| s.trim_end().to_string())
+}
+
+fn read_props(record: &Record, keys: [&str; 4], suffixes: &[String]) -> Vec<ItemProp> {
+    suffixes.iter().filter_map(|s| ItemProp::read(record, keys, s)).collect()
+}
+
+/// the suffixes `2a, 2b, 3a, ...` of a/b bonus columns, `first` is the lowest item count
+fn bonus_suffixes(first: u8, last: u8) -> Vec<(u8, [String; 2])> {
+    (first..=last).map(|i| (i, [format!("{i}a"), format!("{i}b")])).collect()
+}
+
+impl UniqueItem {
+    fn new(tbls: &DataTblsManager, desc: &StatDesc, row: usize, record: &Record) -> Self {
+        let suffixes: Vec<String> = (1..=12).map(|i| i.to_string()).collect();
+        let props = read_props(record, PROP_KEYS, &suffixes);
+
+        Self {
+            id      : record.get("unique_id").value.u16(),
+            row,
+            index   : record.get("index").value.str().to_string(),
+            name    : string(tbls, record, "name_str"),
+            base    : BaseItem::new(tbls, record.get("code").value.item_code()),
+            enabled : record.get("flags").is_set("enabled"),
+            lvl     : record.get("lvl").value.u16(),
+            lvl_req : record.get("lvl_req").value.u16(),
+            lines   : desc.describe_props(&props),
+            props,
+        }
+    }
+}
+
+impl SetItem {
+    fn new(tbls: &DataTblsManager, desc: &StatDesc, row: usize, record: &Record) -> Self {
+        let suffixes: Vec<String> = (1..=9).map(|i| i.to_string()).collect();
+        let props = read_props(record, PROP_KEYS, &suffixes);
+
+        // aprop1a/1b apply with 2 pieces worn, up to 5a/5b with 6
+        let bonuses = bonus_suffixes(1, 5).into_iter()
+            .map(|(i, s)| SetBonus {
+                items: i + 1,
+                lines: desc.describe_props(&read_props(record, SET_ITEM_BONUS_KEYS, &s)),
+            })
+            .filter(|b| !b.lines.is_empty())
+            .collect();
+
+        Self {
+            id      : record.get("set_item_id").value.u16(),
+            row,
+            index   : record.get("index").value.str().to_string(),
+            name    : string(tbls, record, "name_str"),
+            set     : record.get("set").ref_row(),
+            base    : BaseItem::new(tbls, record.get("code").value.item_code()),
+            lvl     : record.get("lvl").value.u16(),
+            lvl_req : record.get("lvl_req").value.u16(),
+            lines   : desc.describe_props(&props),
+            props,
+            bonuses,
+        }
+    }
+}
+
+impl ItemSet {
+    fn new(tbls: &DataTblsManager, desc: &StatDesc, row: usize, record: &Record) -> Self {
+        let partial = bonus_suffixes(2, 5).into_iter()
+            .map(|(i, s)| SetBonus {
+                items: i,
+                lines: desc.describe_props(&read_props(record, SET_BONUS_KEYS, &s)),
+            })
+            .filter(|b| !b.lines.is_empty())
+            .collect();
+
+        let full: Vec<String> = (1..=8).map(|i| i.to_string()).collect();
+
+        Self {
+            id      : record.get("set_id").value.u16(),
+            row,
+            name    : string(tbls, record, "name_str"),
+            items   : Vec::new(),
+            partial,
+            full    : desc.describe_props(&read_props(record, SET_FULL_KEYS, &full)),
+        }
+    }
+}
+
+pub struct UniqueCatalog {
+    uniques : Vec<UniqueItem>,
+}
+
+impl Default for UniqueCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UniqueCatalog {
+    pub fn new() -> Self {
+        Self {
+            uniques: Vec::new(),
+        }
+    }
+
+    pub fn build(tbls: &DataTblsManager) -> Self {
+        let desc = StatDesc::new(tbls);
+
+        Self {
+            uniques: tbls.unique_items.iter().enumerate().map(|(row, r)| UniqueItem::new(tbls, &desc, row, r)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.uniques.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uniques.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, UniqueItem> {
+        self.uniques.iter()
+    }
+
+    pub fn get(&self, row: usize) -> Option<&UniqueItem> {
+        self.uniques.get(row)
+    }
+
+    /// the uniques on the base item with `class_id`
+    pub fn get_by_base(&self, class_id: u32) -> Vec<&UniqueItem> {
+        self.uniques.iter().filter(|u| u.base.class.map(|c| c.class_id) == Some(class_id)).collect()
+    }
+
+    pub fn dump_json(&self, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        lines.push("[".into());
+
+        for (i, unique) in self.uniques.iter().enumerate() {
+            lines.extend(vec![
+                "  {".to_string(),
+                format!("    \"id\": {},", unique.id),
+                format!("    \"index\": {},", json_str(&unique.index)),
+                format!("    \"name\": {},", opt_json(&unique.name)),
+                format!("    \"code\": {},", json_str(&unique.base.code_str())),
+                format!("    \"base\": {},", opt_json(&unique.base.name)),
+                format!("    \"enabled\": {},", unique.enabled),
+                format!("    \"lvl\": {},", unique.lvl),
+                format!("    \"lvl_req\": {},", unique.lvl_req),
+                format!("    \"props\": {}", json_lines(&unique.lines)),
+                format!("  }}{}", if i + 1 < self.uniques.len() { "," } else { "" }),
+            ]);
+        }
+
+        lines.push("]".into());
+
+        let mut json = std::fs::File::create(file_name)?;
+        json.write_all(lines.join("\n").as_bytes())?;
+
+        Ok(())
+    }
+}
+
+pub struct SetCatalog {
+    sets    : Vec<ItemSet>,
+    items   : Vec<SetItem>,
+}
+
+impl Default for SetCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SetCatalog {
+    pub fn new() -> Self {
+        Self {
+            sets    : Vec::new(),
+            items   : Vec::new(),
+        }
+    }
+
+    pub fn build(tbls: &DataTblsManager) -> Self {
+        let desc = StatDesc::new(tbls);
+
+        let items: Vec<SetItem> = tbls.set_items.iter().enumerate().map(|(row, r)| SetItem::new(tbls, &desc, row, r)).collect();
+        let mut sets: Vec<ItemSet> = tbls.sets.iter().enumerate().map(|(row, r)| ItemSet::new(tbls, &desc, row, r)).collect();
+
+        for item in items.iter() {
+            if let Some(set) = item.set.and_then(|s| sets.get_mut(s)) {
+                set.items.push(item.row);
+            }
+        }
+
+        Self {
+            sets,
+            items,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ItemSet> {
+        self.sets.iter()
+    }
+
+    pub fn get(&self, row: usize) -> Option<&ItemSet> {
+        self.sets.get(row)
+    }
+
+    pub fn items(&self) -> std::slice::Iter<'_, SetItem> {
+        self.items.iter()
+    }
+
+    pub fn get_item(&self, row: usize) -> Option<&SetItem> {
+        self.items.get(row)
+    }
+
+    /// the pieces of `set`
+    pub fn set_items(&self, set: &ItemSet) -> Vec<&SetItem> {
+        set.items.iter().filter_map(|row| self.items.get(*row)).collect()
+    }
+
+    pub fn dump_json(&self, file_name: &str) -> Result<()> {
+        let mut lines = Vec::<String>::new();
+
+        lines.push("[".into());
+
+        for (i, set) in self.sets.iter().enumerate() {
+            let items = self.set_items(set);
+
+            lines.extend(vec![
+                "  {".to_string(),
+                format!("    \"id\": {},", set.id),
+                format!("    \"name\": {},", opt_json(&set.name)),
+                format!("    \"partial\": {},", json_bonuses(&set.partial)),
+                format!("    \"full\": {},", json_lines(&set.full)),
+                "    \"items\": [".to_string(),
+            ]);
+
+            for (j, item) in items.iter().enumerate() {
+                lines.extend(vec![
+                    "      {".to_string(),
+                    format!("        \"id\": {},", item.id),
+                    format!("        \"index\": {},", json_str(&item.index)),
+                    format!("        \"name\": {},", opt_json(&item.name)),
+                    format!("        \"code\": {},", json_str(&item.base.code_str())),
+                    format!("        \"base\": {},", opt_json(&item.base.name)),
+                    format!("        \"lvl\": {},", item.lvl),
+                    format!("        \"lvl_req\": {},", item.lvl_req),
+                    format!("        \"props\": {},", json_lines(&item.lines)),
+                    format!("        \"bonuses\": {}", json_bonuses(&item.bonuses)),
+                    format!("      }}{}", if j + 1 < items.len() { "," } else { "" }),
+                ]);
+            }
+
+            lines.push("    ]".into());
+            lines.push(format!("  }}{}", if i + 1 < self.sets.len() { "," } else { "" }));
+        }
+
+        lines.push("]".into());
+
+        let mut json = std::fs::File::create(file_name)?;
+        json.write_all(lines.join("\n").as_bytes())?;
+
+        Ok(())
+    }
+}
+
+fn opt_json(s: &Option<String>) -> String {
+    s.as_deref().map(json_str).unwrap_or_else(|| "null".to_string())
+}
+
+fn json_lines(lines: &[String]) -> String {
+    format!("[{}]", lines.iter().map(|l| json_str(l)).collect::<Vec<_>>().join(", "))
+}
+
+fn json_bonuses(bonuses: &[SetBonus]) -> String {
+    let bonuses: Vec<String> = bonuses.iter().map(|b| format!("{{\"items\": {}, \"props\": {}}}", b.items, json_lines(&b.lines))).collect();
+    format!("[{}]", bonuses.join(", "))
+}
+
+fn html_text(s: &str) -> String {
+    html_str(strip_color_codes(s).trim())
+}
+
+fn html_props(lines: &[String]) -> String {
+    lines.iter().map(|l| html_text(l)).collect::<Vec<_>>().join("<br>")
+}
+
+/// writes one html page listing every unique and set item
+pub fn dump_html(uniques: &UniqueCatalog, sets: &SetCatalog, file_name: &str) -> Result<()> {
+    let mut lines = Vec::<String>::new();
+
+    let name = |name: &Option<String>, index: &str| html_text(name.as_deref().unwrap_or(index));
+    let base = |base: &BaseItem| html_text(base.name.as_deref().unwrap_or(&base.code_str()));
+
+    lines.push("<!DOCTYPE html>".into());
+    lines.push(r#"<html><head><meta charset="utf-8"><title>Unique and Set Items</title></head>"#.into());
+    lines.push(r#"<body style="background:#0d0a08;color:#f0e0c0;font-family:sans-serif">"#.into());
+
+    lines.push("<h2>Unique Items</h2>".into());
+    lines.push("<table>".into());
+    lines.push("<tr><th>Name</th><th>Base</th><th>Level</th><th>Required Level</th><th>Properties</th></tr>".into());
+
+    for unique in uniques.iter().filter(|u| u.enabled) {
+        lines.push(format!(
+            r##"<tr><td style="color:#c8a050">{}</td><td>{}</td><td>{}</td><td>{}</td><td style="color:#8080ff">{}</td></tr>"##,
+            name(&unique.name, &unique.index), base(&unique.base), unique.lvl, unique.lvl_req, html_props(&unique.lines),
+        ));
+    }
+
+    lines.push("</table>".into());
+
+    lines.push("<h2>Sets</h2>".into());
+
+    for set in sets.iter() {
+        lines.push(format!(r##"<h3 style="color:#00c000">{}</h3>"##, name(&set.name, &set.id.to_string())));
+        lines.push("<table>".into());
+
+        for item in sets.set_items(set) {
+            let bonuses: Vec<String> = item.bonuses.iter().map(|b| format!("({} Items) {}", b.items, html_props(&b.lines))).collect();
+
+            lines.push(format!(
+                r##"<tr><td style="color:#00c000">{}</td><td>{}</td><td>{}</td><td style="color:#8080ff">{}</td><td style="color:#00c000">{}</td></tr>"##,
+                name(&item.name, &item.index), base(&item.base), item.lvl_req, html_props(&item.lines), bonuses.join("<br>"),
+            ));
+        }
+
+        lines.push("</table>".into());
+
+        for bonus in set.partial.iter() {
+            lines.push(format!(r##"<p>({} Items) <span style="color:#00c000">{}</span></p>"##, bonus.items, html_props(&bonus.lines)));
+        }
+
+        if !set.full.is_empty() {
+            lines.push(format!(r##"<p>Full Set <span style="color:#c8a050">{}</span></p>"##, html_props(&set.full)));
+        }
+    }
+
+    lines.push("</body></html>".into());
+
+    let mut html = std::fs::File::create(file_name)?;
+    html.write_all(lines.join("\n").as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields;
+    use crate::bin::tests::zeroed;
+    use crate::itemtbl::{ItemCatalog, item_code};
+    use crate::stringtbl::tests::manager;
+
+    const STR: i32 = 0;
+    const RES_COLD: i32 = 1;
+
+    /// every property column of `record` unused
+    fn clear_props(record: &mut Record) {
+        let props: Vec<String> = record.schema().fields().iter()
+            .filter(|f| f.reference == Some("properties"))
+            .map(|f| f.name.clone())
+            .collect();
+
+        for prop in props {
+            record.set(&prop, Value::Int32(-1));
+        }
+    }
+
+    /// sets `{keys}_{suffix}` to `prop` with `min..=max`
+    fn set_prop(record: &mut Record, keys: [&str; 4], suffix: &str, prop: i32, min: i32, max: i32) {
+        record.set(&format!("{}_{suffix}", keys[0]), Value::Int32(prop));
+        record.set(&format!("{}_{suffix}", keys[2]), Value::Int32(min));
+        record.set(&format!("{}_{suffix}", keys[3]), Value::Int32(max));
+    }
+
+    /// a buckler, the str and res-cold properties, Civerb's Ward on a buckler,
+    /// a unique on a missing base and a set of two items with a third outside it
+    fn tbls() -> DataTblsManager {
+        let mut tbls = DataTblsManager::new();
+        tbls.strtbl = manager(
+            &[("dummy", ""), ("Buckler", "Buckler"), ("ModStr1e", "to Strength"), ("ModStr1g", "Cold Resist"), ("Civerb's Ward", "Civerb's Ward ")],
+            &[],
+            &[],
+        );
+
+        let mut armor = zeroed(&fields::ITEMS, 1);
+        armor.get_mut(0).unwrap().set("code", Value::ItemCode(item_code("buc")));
+        armor.get_mut(0).unwrap().set("name_str", Value::StringId(1));
+        tbls.armor.load_bytes(armor.to_bytes().unwrap()).unwrap();
+        tbls.items = ItemCatalog::build([&mut tbls.weapon, &mut tbls.armor, &mut tbls.misc]);
+
+        tbls.item_stat_cost = zeroed(&fields::ITEM_STAT_COST, 2);
+        for (row, (func, val, text)) in [(1, 1, 2), (4, 2, 3)].into_iter().enumerate() {
+            let stat = tbls.item_stat_cost.get_mut(row).unwrap();
+            stat.set("desc_func", Value::UInt8(func));
+            stat.set("desc_val", Value::UInt8(val));
+            stat.set("desc_str_pos", Value::StringId(text));
+        }
+
+        tbls.properties = zeroed(&fields::PROPERTIES, 2);
+        for row in 0..2 {
+            let mut stats = vec![u16::MAX; 7];
+            stats[0] = row as u16;
+            let prop = tbls.properties.get_mut(row).unwrap();
+            prop.set("func", Value::U8Array(vec![1, 0, 0, 0, 0, 0, 0]));
+            prop.set("stat", Value::U16Array(stats));
+        }
+
+        tbls.unique_items = zeroed(&fields::UNIQUE_ITEMS, 2);
+        for (row, code) in ["buc", "xyz"].into_iter().enumerate() {
+            let unique = tbls.unique_items.get_mut(row).unwrap();
+            clear_props(unique);
+            unique.set("unique_id", Value::UInt16(row as u16));
+            unique.set("code", Value::ItemCode(item_code(code)));
+        }
+        let ward = tbls.unique_items.get_mut(0).unwrap();
+        ward.set("name_str", Value::StringId(4));
+        ward.set("flags", Value::UInt32(1));
+        set_prop(ward, PROP_KEYS, "1", STR, 10, 15);
+
+        tbls.set_items = zeroed(&fields::SET_ITEMS, 3);
+        for (row, set) in [0, 0, -1].into_iter().enumerate() {
+            let item = tbls.set_items.get_mut(row).unwrap();
+            clear_props(item);
+            item.set("code", Value::ItemCode(item_code("buc")));
+            item.set("set", Value::Int16(set));
+        }
+        let shield = tbls.set_items.get_mut(0).unwrap();
+        set_prop(shield, SET_ITEM_BONUS_KEYS, "1a", STR, 5, 5);
+        set_prop(shield, SET_ITEM_BONUS_KEYS, "2b", RES_COLD, 20, 20);
+
+        tbls.sets = zeroed(&fields::SETS, 1);
+        let set = tbls.sets.get_mut(0).unwrap();
+        clear_props(set);
+        set_prop(set, SET_BONUS_KEYS, "2a", RES_COLD, 10, 10);
+        set_prop(set, SET_FULL_KEYS, "1", STR, 20, 20);
+
+        tbls
+    }
+
+    #[test]
+    fn uniques_join_their_base_item() {
+        let tbls = tbls();
+        let uniques = UniqueCatalog::build(&tbls);
+        assert_eq!(uniques.len(), 2);
+
+        let ward = uniques.get(0).unwrap();
+        assert_eq!(ward.name.as_deref(), Some("Civerb's Ward"));
+        assert!(ward.enabled);
+        assert_eq!(ward.base.code_str(), "buc");
+        assert_eq!(ward.base.name.as_deref(), Some("Buckler"));
+        assert_eq!(ward.props.len(), 1);
+        assert_eq!(ward.lines, ["+(10-15) to Strength"]);
+
+        let missing = uniques.get(1).unwrap();
+        assert!(!missing.enabled);
+        assert!(missing.base.class.is_none());
+        assert_eq!(missing.base.name, None);
+        assert!(missing.props.is_empty());
+
+        let class_id = ward.base.class.unwrap().class_id;
+        assert_eq!(uniques.get_by_base(class_id).len(), 1);
+    }
+
+    #[test]
+    fn set_bonuses_count_worn_pieces() {
+        let tbls = tbls();
+        let sets = SetCatalog::build(&tbls);
+        assert_eq!(sets.len(), 1);
+
+        // aprop1a applies with 2 pieces worn, aprop2b with 3
+        let shield = sets.get_item(0).unwrap();
+        let bonuses: Vec<(u8, &[String])> = shield.bonuses.iter().map(|b| (b.items, b.lines.as_slice())).collect();
+        assert_eq!(bonuses, [(2, &["+5 to Strength".to_string()][..]), (3, &["Cold Resist +20%".to_string()][..])]);
+        assert_eq!(shield.base.name.as_deref(), Some("Buckler"));
+        assert!(shield.props.is_empty());
+
+        // p_code2a applies with 2 pieces worn
+        let set = sets.get(0).unwrap();
+        assert_eq!(set.partial.len(), 1);
+        assert_eq!(set.partial[0].items, 2);
+        assert_eq!(set.partial[0].lines, ["Cold Resist +10%"]);
+        assert_eq!(set.full, ["+20 to Strength"]);
+
+        // the item with set -1 belongs to none
+        assert_eq!(set.items, [0, 1]);
+        assert_eq!(sets.set_items(set).iter().map(|i| i.row).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(sets.items().count(), 3);
+        assert_eq!(sets.get_item(2).unwrap().set, None);
+    }
+}
@@ -0,0 +1,215 @@
+#![allow(unused)]
+
+//! the game's file system: layers of directories and mpqs searched last added
+//! first, so `data\global\excel\weapons.bin` resolves through
+//! d2data.mpq < d2exp.mpq < patch_d2.mpq < mod mpqs < loose `-direct` files.
+//! names are case and separator insensitive like in the game.
+
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use anyhow::{Result, bail};
+
+use crate::mpq::MpqArchive;
+
+/// the archives the game opens, lowest priority first
+pub const GAME_ARCHIVES: [&str; 3] = ["d2data.mpq", "d2exp.mpq", "patch_d2.mpq"];
+
+/// the key names are looked up by: upper case, `\` separated, no leading separator
+pub fn normalize(name: &str) -> String {
+    name.replace('/', "\\").trim_start_matches('\\').to_ascii_uppercase()
+}
+
+pub enum Layer {
+    /// loose files under `root`, which stands for the `mount` directory of the game
+    Dir {
+        root    : PathBuf,
+        mount   : String,
+        files   : HashMap<String, PathBuf>,
+    },
+    Archive {
+        path    : PathBuf,
+        mpq     : MpqArchive,
+    },
+}
+
+impl Layer {
+    pub fn dir<T: AsRef<Path>>(root: T, mount: &str) -> Result<Layer> {
+        let root = root.as_ref().to_path_buf();
+
+        if !root.is_dir() {
+            bail!("{} is not a directory", root.display());
+        }
+
+        let mount = normalize(mount).trim_end_matches('\\').to_string();
+        let mut files = HashMap::new();
+        let mut dirs = vec![(root.clone(), mount.clone())];
+
+        while let Some((dir, prefix)) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_ascii_uppercase();
+                let key = if prefix.is_empty() { name } else { format!("{prefix}\\{name}") };
+
+                if entry.file_type()?.is_dir() {
+                    dirs.push((entry.path(), key));
+                } else {
+                    files.insert(key, entry.path());
+                }
+            }
+        }
+
+        Ok(Layer::Dir{root, mount, files})
+    }
+
+    pub fn archive<T: AsRef<Path>>(path: T) -> Result<Layer> {
+        Ok(Layer::Archive{
+            path    : path.as_ref().to_path_buf(),
+            mpq     : MpqArchive::open(path)?,
+        })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        match self {
+            Layer::Dir{files, ..} => files.contains_key(&normalize(name)),
+            Layer::Archive{mpq, ..} => mpq.contains(name),
+        }
+    }
+
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        match self {
+            Layer::Dir{files, ..} => match files.get(&normalize(name)) {
+                Some(path) => Ok(std::fs::read(path)?),
+                None => bail!("{name} not exists in {self}"),
+            },
+            Layer::Archive{mpq, ..} => mpq.read_file(name),
+        }
+    }
+
+    /// where the layer reads `name` from, the file path for directories
+    pub fn locate(&self, name: &str) -> Option<PathBuf> {
+        match self {
+            Layer::Dir{files, ..} => files.get(&normalize(name)).cloned(),
+            Layer::Archive{path, mpq} => mpq.contains(name).then(|| path.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layer::Dir{root, mount, ..} if mount.is_empty() => write!(f, "dir {}", root.display()),
+            Layer::Dir{root, mount, ..} => write!(f, "dir {} as {mount}", root.display()),
+            Layer::Archive{path, ..} => write!(f, "mpq {}", path.display()),
+        }
+    }
+}
+
+pub struct Vfs {
+    /// lowest priority first
+    layers  : Vec<Layer>,
+}
+
+impl Default for Vfs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Self {
+            layers  : Vec::new(),
+        }
+    }
+
+    /// the game's archives in `game_dir` that exist, then `mods` in the given
+    /// order, then the loose files of `game_dir\data` when `direct` is set
+    pub fn game<T: AsRef<Path>, M: AsRef<Path>>(game_dir: T, mods: &[M], direct: bool) -> Result<Vfs> {
+        let game_dir = game_dir.as_ref();
+        let mut vfs = Self::new();
+
+        for name in GAME_ARCHIVES {
+            let path = game_dir.join(name);
+            if path.exists() {
+                vfs.add_archive(path)?;
+            }
+        }
+
+        for path in mods {
+            vfs.add_archive(path)?;
+        }
+
+        if direct {
+            vfs.add_dir(game_dir.join("data"), "data")?;
+        }
+
+        Ok(vfs)
+    }
+
+    /// adds a directory standing for `mount`, e.g. an extracted `data` dir
+    /// mounted as `"data"`, above every layer added before
+    pub fn add_dir<T: AsRef<Path>>(&mut self, root: T, mount: &str) -> Result<()> {
+        self.layers.push(Layer::dir(root, mount)?);
+        Ok(())
+    }
+
+    /// adds an mpq above every layer added before
+    pub fn add_archive<T: AsRef<Path>>(&mut self, path: T) -> Result<()> {
+        self.layers.push(Layer::archive(path)?);
+        Ok(())
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// the layer `name` is read from, the highest one that has it
+    pub fn which(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().rev().find(|l| l.contains(name))
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.which(name).is_some()
+    }
+
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        match self.which(name) {
+            Some(layer) => layer.read(name),
+            None => bail!("{name} not exists"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/small.mpq");
+
+    #[test]
+    fn a_dir_overrides_an_archive_below_it() {
+        let root = std::env::temp_dir().join(format!("datatbls-vfs-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("Global").join("Excel")).unwrap();
+        std::fs::write(root.join("Global").join("Excel").join("Test.TXT"), b"loose").unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.add_archive(FIXTURE).unwrap();
+        let added = vfs.add_dir(&root, "data/");
+        std::fs::remove_dir_all(&root).unwrap();
+        added.unwrap();
+
+        assert_eq!(normalize("/data/global/excel/test.txt"), r"DATA\GLOBAL\EXCEL\TEST.TXT");
+
+        for name in [r"data\global\excel\test.txt", "DATA/Global/EXCEL/test.TXT", r"\Data\Global\Excel\Test.txt"] {
+            assert!(matches!(vfs.which(name), Some(Layer::Dir{..})), "{name}");
+            assert_eq!(vfs.layers()[1].locate(name), Some(root.join("Global").join("Excel").join("Test.TXT")));
+        }
+
+        // files only the archive has still come from it
+        assert!(matches!(vfs.which("README.txt"), Some(Layer::Archive{..})));
+        assert_eq!(vfs.read("readme.txt").unwrap(), b"a stored file\r\n");
+
+        assert!(!vfs.exists("data/global/excel/missing.txt"));
+        assert_eq!(vfs.read("missing.txt").unwrap_err().to_string(), "missing.txt not exists");
+    }
+}
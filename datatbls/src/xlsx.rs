@@ -0,0 +1,66 @@
+#![allow(unused)]
+
+//! xlsx workbooks of bin tables for the designers, built with the `xlsx` feature.
+//! The columns are the same as `DataTblsManager::dump_csv`, the header row is
+//! bold and frozen.
+
+use anyhow::Result;
+use rust_xlsxwriter::{Format, Workbook};
+
+use crate::bin::*;
+use crate::datatbls_mgr::{DataTblsManager, Cell};
+
+/// sheet names are limited to 31 characters
+const MAX_SHEET_NAME: usize = 31;
+
+/// the tables of `dump_default_workbook`, one sheet each
+pub static DEFAULT_SHEETS: &[&str] = &["weapons", "armor", "misc", "skills"];
+
+/// writes one sheet per (name, table) of `sheets`
+pub fn dump_workbook(tbls: &DataTblsManager, sheets: &[(&str, &BinRecord)], file_name: &str, expand_arrays: bool) -> Result<()> {
+    let mut workbook = Workbook::new();
+    let header = Format::new().set_bold();
+
+    for (name, rec) in sheets {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(name.chars().take(MAX_SHEET_NAME).collect::<String>())?;
+        sheet.set_freeze_panes(1, 0)?;
+
+        for (row, record) in rec.iter().enumerate() {
+            let cells = tbls.record_cells(record, expand_arrays);
+
+            if row == 0 {
+                for (col, (key, _)) in cells.iter().enumerate() {
+                    sheet.write_string_with_format(0, col as u16, key, &header)?;
+                }
+            }
+
+            for (col, (_, cell)) in cells.iter().enumerate() {
+                let (row, col) = (row as u32 + 1, col as u16);
+
+                match cell {
+                    Cell::Int(v) => sheet.write_number(row, col, *v as f64)?,
+                    Cell::Text(s) => sheet.write_string(row, col, s)?,
+                };
+            }
+        }
+    }
+
+    workbook.save(file_name)?;
+
+    Ok(())
+}
+
+/// writes `rec` as a workbook with a single sheet
+pub fn dump_xlsx(tbls: &DataTblsManager, rec: &BinRecord, sheet: &str, file_name: &str, expand_arrays: bool) -> Result<()> {
+    dump_workbook(tbls, &[(sheet, rec)], file_name, expand_arrays)
+}
+
+/// weapons, armor, misc and skills on separate sheets, arrays expanded
+pub fn dump_default_workbook(tbls: &DataTblsManager, file_name: &str) -> Result<()> {
+    let sheets: Vec<(&str, &BinRecord)> = DEFAULT_SHEETS.iter()
+        .filter_map(|name| Some((*name, tbls.table(name)?)))
+        .collect();
+
+    dump_workbook(tbls, &sheets, file_name, true)
+}